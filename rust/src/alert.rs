@@ -0,0 +1,389 @@
+//! 阈值告警规则引擎
+//!
+//! 规则语法 (每条 `--alert` 一条规则):
+//!
+//! ```text
+//! [DEVICE] METRIC OP VALUE [for DURATION] [clear OP VALUE [for DURATION]] [=> CHANNELS]
+//! ```
+//!
+//! - `DEVICE`:   网卡或组名 (含空格时用引号)；省略或 `*` 表示所有设备
+//! - `METRIC`:   `in` / `out` / `total` (收+发) 为速率，`quota` 为配额已用百分比
+//! - `OP`:       `>` `>=` `<` `<=`
+//! - `VALUE`:    速率同 --max (如 800M = 800 MiB/s)，配额为百分比 (如 90%)
+//! - `for`:      条件需持续多久才触发 (如 10s、2m、1h)，默认立即触发
+//! - `clear`:    恢复条件，默认为触发条件不再满足
+//! - `=>`:       触发 / 恢复时的投递通道，见 notify 模块
+//!
+//! 例: `eth0 in > 800M for 10s`、`out < 1K for 2m`、`wwan0 quota > 90%`
+
+use chrono::{DateTime, Local};
+
+use crate::notify::{self, Channel};
+use crate::quota::Quota;
+use crate::DeviceView;
+
+/// 告警指标
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    Incoming,
+    Outgoing,
+    Total,
+    Quota,
+}
+
+/// 比较运算符
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn check(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Gt => value > threshold,
+            Op::Ge => value >= threshold,
+            Op::Lt => value < threshold,
+            Op::Le => value <= threshold,
+        }
+    }
+}
+
+/// 单个条件: OP VALUE for DURATION
+#[derive(Clone, Copy, Debug)]
+pub struct Condition {
+    pub op: Op,
+    pub threshold: f64,
+    pub for_secs: f64,
+}
+
+/// 告警规则
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// 原始规则文本 (用于显示)
+    pub text: String,
+    /// None 表示所有设备
+    pub device: Option<String>,
+    pub metric: Metric,
+    pub trigger: Condition,
+    /// None 表示触发条件不满足即恢复
+    pub recover: Option<Condition>,
+    /// 触发 / 恢复时的投递通道
+    pub channels: Vec<Channel>,
+}
+
+impl Rule {
+    fn applies_to(&self, device: &str) -> bool {
+        self.device.as_deref().is_none_or(|d| d == device)
+    }
+}
+
+/// 解析时长，如 "500ms"、"10s"、"2m"、"1h"，纯数字为秒
+pub fn parse_duration(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let (num, mult) = if let Some(n) = s.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60.0)
+    } else if let Some(n) = s.strip_suffix('h') {
+        (n, 3600.0)
+    } else {
+        (s, 1.0)
+    };
+    let v: f64 = num.parse().map_err(|e| format!("invalid duration '{s}': {e}"))?;
+    if v < 0.0 {
+        return Err(format!("negative duration: {s}"));
+    }
+    Ok(v * mult)
+}
+
+/// 按空白切分，支持用单引号或双引号包住含空格的设备名
+fn tokenize(s: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut tok = String::new();
+            let mut closed = false;
+            for ch in chars.by_ref() {
+                if ch == c {
+                    closed = true;
+                    break;
+                }
+                tok.push(ch);
+            }
+            if !closed {
+                return Err(format!("unbalanced quote in: {s}"));
+            }
+            tokens.push(tok);
+        } else {
+            let mut tok = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() {
+                    break;
+                }
+                tok.push(ch);
+                chars.next();
+            }
+            tokens.push(tok);
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_metric(s: &str) -> Option<Metric> {
+    match s.to_lowercase().as_str() {
+        "in" | "incoming" | "rx" => Some(Metric::Incoming),
+        "out" | "outgoing" | "tx" => Some(Metric::Outgoing),
+        "total" | "both" => Some(Metric::Total),
+        "quota" => Some(Metric::Quota),
+        _ => None,
+    }
+}
+
+fn parse_op(s: &str) -> Result<Op, String> {
+    match s {
+        ">" => Ok(Op::Gt),
+        ">=" => Ok(Op::Ge),
+        "<" => Ok(Op::Lt),
+        "<=" => Ok(Op::Le),
+        _ => Err(format!("expected one of > >= < <=, got: {s}")),
+    }
+}
+
+fn parse_value(s: &str, metric: Metric) -> Result<f64, String> {
+    if metric == Metric::Quota {
+        let v = s.strip_suffix('%').unwrap_or(s);
+        v.parse().map_err(|e| format!("invalid percentage '{s}': {e}"))
+    } else {
+        crate::parse_max_value(s)
+    }
+}
+
+/// 从 tokens[i..] 解析 `OP VALUE [for DURATION]`，返回条件和下一个位置
+fn parse_condition(tokens: &[String], mut i: usize, metric: Metric) -> Result<(Condition, usize), String> {
+    let op = parse_op(tokens.get(i).ok_or("missing operator")?)?;
+    let threshold = parse_value(tokens.get(i + 1).ok_or("missing threshold")?, metric)?;
+    i += 2;
+    let mut for_secs = 0.0;
+    if tokens.get(i).map(|t| t.eq_ignore_ascii_case("for")) == Some(true) {
+        for_secs = parse_duration(tokens.get(i + 1).ok_or("missing duration after 'for'")?)?;
+        i += 2;
+    }
+    Ok((Condition { op, threshold, for_secs }, i))
+}
+
+/// 解析一条告警规则
+pub fn parse_rule(s: &str) -> Result<Rule, String> {
+    let (s, channels) = match s.split_once("=>") {
+        Some((rule, channels)) => (rule, notify::parse_channels(channels)?),
+        None => (s, Vec::new()),
+    };
+    let tokens = tokenize(s)?;
+    let mut i = 0;
+
+    let device = match tokens.first() {
+        Some(t) if parse_metric(t).is_none() => {
+            i = 1;
+            (t != "*").then(|| t.clone())
+        }
+        Some(_) => None,
+        None => return Err("empty alert rule".to_string()),
+    };
+    let metric = tokens
+        .get(i)
+        .and_then(|t| parse_metric(t))
+        .ok_or_else(|| format!("expected metric (in, out, total, quota) in: {s}"))?;
+    let (trigger, mut i) = parse_condition(&tokens, i + 1, metric)?;
+
+    let mut recover = None;
+    if tokens.get(i).map(|t| t.eq_ignore_ascii_case("clear")) == Some(true) {
+        let (cond, next) = parse_condition(&tokens, i + 1, metric)?;
+        recover = Some(cond);
+        i = next;
+    }
+    if let Some(extra) = tokens.get(i) {
+        return Err(format!("unexpected '{extra}' in alert rule: {s}"));
+    }
+
+    Ok(Rule {
+        text: s.trim().to_string(),
+        device,
+        metric,
+        trigger,
+        recover,
+        channels,
+    })
+}
+
+/// 告警状态
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    /// 正常
+    Ok,
+    /// 条件已满足，等待 for 时长
+    Pending { since: f64 },
+    /// 已触发
+    Firing,
+    /// 已触发，恢复条件已满足，等待恢复时长
+    Recovering { since: f64 },
+}
+
+/// 某条规则在某个设备上的告警实例
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub rule: usize,
+    pub device: String,
+    pub state: State,
+    /// 最近一次计算得到的指标值 (速率为 bytes/s，配额为百分比)
+    pub value: f64,
+    /// 最近一次触发的时间
+    pub fired_at: Option<DateTime<Local>>,
+}
+
+impl Alert {
+    pub fn is_firing(&self) -> bool {
+        matches!(self.state, State::Firing | State::Recovering { .. })
+    }
+}
+
+/// 告警状态变化
+#[derive(Clone, Debug)]
+pub struct AlertEvent {
+    pub rule: usize,
+    pub device: String,
+    pub value: f64,
+    /// true = 触发, false = 恢复
+    pub firing: bool,
+    /// 发生时间 (回放时为录制时的时间)
+    pub time: DateTime<Local>,
+}
+
+/// 告警引擎: 持有所有规则和每个 (规则, 设备) 的状态
+pub struct AlertEngine {
+    pub rules: Vec<Rule>,
+    pub alerts: Vec<Alert>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            alerts: Vec::new(),
+        }
+    }
+
+    /// 指标当前值；quota 指标在设备没有配额时返回 None
+    fn metric_value(metric: Metric, view: &DeviceView, quotas: &[Quota]) -> Option<f64> {
+        let engine = &view.engine;
+        match metric {
+            Metric::Incoming => Some(engine.incoming.current),
+            Metric::Outgoing => Some(engine.outgoing.current),
+            Metric::Total => Some(engine.incoming.current + engine.outgoing.current),
+            Metric::Quota => quotas
+                .iter()
+                .find(|q| q.target == view.info.name)
+                .map(|q| q.percent()),
+        }
+    }
+
+    /// 用最新统计评估所有规则，返回本次发生的触发 / 恢复事件
+    ///
+    /// `now` 为单调递增的秒数 (与快照的 elapsed_secs 同源)，`time` 为样本的墙上时间
+    /// (回放 / 读取抓包文件时为录制时的时间)。
+    pub fn evaluate(&mut self, now: f64, time: DateTime<Local>, views: &[DeviceView], quotas: &[Quota]) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+
+        for (rule_idx, rule) in self.rules.iter().enumerate() {
+            for view in views.iter().filter(|v| rule.applies_to(&v.info.name)) {
+                let Some(value) = Self::metric_value(rule.metric, view, quotas) else {
+                    continue;
+                };
+
+                let pos = self
+                    .alerts
+                    .iter()
+                    .position(|a| a.rule == rule_idx && a.device == view.info.name);
+                let alert = match pos {
+                    Some(p) => &mut self.alerts[p],
+                    None => {
+                        self.alerts.push(Alert {
+                            rule: rule_idx,
+                            device: view.info.name.clone(),
+                            state: State::Ok,
+                            value,
+                            fired_at: None,
+                        });
+                        self.alerts.last_mut().unwrap()
+                    }
+                };
+                alert.value = value;
+
+                let triggered = rule.trigger.op.check(value, rule.trigger.threshold);
+                let (recovered, recover_for) = match rule.recover {
+                    Some(c) => (c.op.check(value, c.threshold), c.for_secs),
+                    None => (!triggered, 0.0),
+                };
+
+                let prev_firing = alert.is_firing();
+                alert.state = match alert.state {
+                    State::Ok | State::Pending { .. } if !triggered => State::Ok,
+                    State::Ok => State::Pending { since: now },
+                    State::Firing | State::Recovering { .. } if !recovered => State::Firing,
+                    State::Firing => State::Recovering { since: now },
+                    other => other,
+                };
+                // 持续时长满足后完成迁移 (for 为 0 时在同一轮内完成)
+                match alert.state {
+                    State::Pending { since } if now - since >= rule.trigger.for_secs => {
+                        alert.state = State::Firing;
+                    }
+                    State::Recovering { since } if now - since >= recover_for => {
+                        alert.state = State::Ok;
+                    }
+                    _ => {}
+                }
+
+                if alert.is_firing() != prev_firing {
+                    let firing = alert.is_firing();
+                    if firing {
+                        alert.fired_at = Some(time);
+                    }
+                    events.push(AlertEvent {
+                        rule: rule_idx,
+                        device: alert.device.clone(),
+                        value,
+                        firing,
+                        time,
+                    });
+                }
+            }
+        }
+
+        events
+    }
+
+    /// 正在触发的告警
+    pub fn firing(&self) -> impl Iterator<Item = &Alert> {
+        self.alerts.iter().filter(|a| a.is_firing())
+    }
+
+    /// 设备某方向的面板是否需要高亮
+    pub fn panel_firing(&self, device: &str, incoming: bool) -> bool {
+        self.firing().any(|a| {
+            a.device == device
+                && match self.rules[a.rule].metric {
+                    Metric::Incoming => incoming,
+                    Metric::Outgoing => !incoming,
+                    Metric::Total | Metric::Quota => true,
+                }
+        })
+    }
+}
@@ -0,0 +1,293 @@
+//! 流量异常检测
+//!
+//! 按 "设备 × 方向 × 一天中的小时" 学习速率基线 (指数加权均值和方差)，
+//! 当前速率偏离基线超过 `threshold` 个标准差并持续几个采样点时，
+//! 记为一次异常区间。样本按其覆盖的时长计权，与刷新间隔无关；异常期间的样本只以很小的权重
+//! 参与学习: 短暂的尖峰带不偏基线，持续很久的偏离则逐渐被接受为新常态。
+//!
+//! 启用 --db 时基线保存在流量数据库中，跨运行持续学习。
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::{DateTime, Local, Timelike};
+
+/// 每天的时间桶数 (按小时)
+pub const BUCKETS: usize = 24;
+/// 桶内学到的数据少于这么多秒时只学习不判定
+const MIN_HISTORY: f64 = 60.0;
+/// 指数加权的记忆长度 (秒)
+const MEMORY: f64 = 1800.0;
+/// 异常期间的样本以正常权重的这一比例参与学习
+const ANOMALY_WEIGHT: f64 = 0.1;
+/// 两个样本的间隔最多按这么多秒计权，暂停或休眠之后的一个样本不会压过整个基线
+const MAX_STEP: f64 = 60.0;
+/// 连续多少个异常样本才算一次异常
+const MIN_STREAK: u32 = 3;
+/// 标准差下限 (bytes/s)，避免空闲网卡上的微小抖动被当成异常
+const MIN_STD: f64 = 1024.0;
+/// 保留的异常区间数量
+const MAX_PERIODS: usize = 256;
+
+/// 解析 --anomaly 的 z 值阈值
+pub fn parse_threshold(s: &str) -> Result<f64, String> {
+    let z: f64 = s.trim().parse().map_err(|e| format!("invalid anomaly threshold '{s}': {e}"))?;
+    if !(z > 0.0 && z.is_finite()) {
+        return Err(format!("anomaly threshold must be a positive number, got {s}"));
+    }
+    Ok(z)
+}
+
+/// 某个时间桶的基线统计
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bucket {
+    /// 已学到的数据时长 (秒，封顶 MEMORY)
+    pub n: f64,
+    pub mean: f64,
+    pub var: f64,
+}
+
+impl Bucket {
+    /// 以 `weight` 秒的权重学习一个样本
+    fn learn(&mut self, x: f64, weight: f64) {
+        if weight <= 0.0 {
+            return;
+        }
+        self.n = (self.n + weight).min(MEMORY);
+        let a = weight / self.n;
+        let d = x - self.mean;
+        self.mean += a * d;
+        self.var = (1.0 - a) * (self.var + a * d * d);
+    }
+
+    fn std(&self) -> f64 {
+        self.var.sqrt().max(self.mean * 0.1).max(MIN_STD)
+    }
+
+    fn zscore(&self, x: f64) -> Option<f64> {
+        (self.n >= MIN_HISTORY).then(|| (x - self.mean) / self.std())
+    }
+}
+
+/// 一个设备方向的 24 小时基线
+pub type Baseline = [Bucket; BUCKETS];
+
+/// 一次异常区间
+#[derive(Clone, Debug)]
+pub struct Anomaly {
+    pub device: String,
+    pub incoming: bool,
+    /// 开始 / 结束时的 App tick，用于在图上定位
+    pub start_tick: u64,
+    pub end_tick: Option<u64>,
+    pub started_at: DateTime<Local>,
+    /// 偏离最大时的速率、基线均值和 z 值
+    pub value: f64,
+    pub expected: f64,
+    pub z: f64,
+}
+
+/// 异常开始 / 结束事件
+pub struct AnomalyEvent {
+    pub anomaly: Anomaly,
+    pub started: bool,
+}
+
+#[derive(Default)]
+struct Track {
+    baseline: Baseline,
+    /// 上一个样本的时间，用来算本样本覆盖的时长
+    last: Option<DateTime<Local>>,
+    streak: u32,
+    /// 正在进行中的异常在 periods 中的下标
+    open: Option<usize>,
+}
+
+/// 异常检测器
+pub struct AnomalyDetector {
+    threshold: f64,
+    tracks: HashMap<(String, bool), Track>,
+    pub periods: VecDeque<Anomaly>,
+}
+
+impl AnomalyDetector {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            tracks: HashMap::new(),
+            periods: VecDeque::new(),
+        }
+    }
+
+    /// 用已保存的基线初始化
+    pub fn load(&mut self, baselines: &BTreeMap<(String, bool), Baseline>) {
+        for (key, baseline) in baselines {
+            self.tracks.entry(key.clone()).or_default().baseline = *baseline;
+        }
+    }
+
+    /// 导出当前基线 (用于持久化)
+    pub fn baselines(&self) -> BTreeMap<(String, bool), Baseline> {
+        self.tracks
+            .iter()
+            .map(|(k, t)| (k.clone(), t.baseline))
+            .collect()
+    }
+
+    /// 喂入一个速率样本，返回异常开始 / 结束事件
+    ///
+    /// `now` 为样本的时间 (回放时为录制时的时间)，用于选择时间桶
+    pub fn observe(&mut self, device: &str, incoming: bool, value: f64, now: DateTime<Local>, tick: u64) -> Option<AnomalyEvent> {
+        let track = self.tracks.entry((device.to_string(), incoming)).or_default();
+        let step = track
+            .last
+            .replace(now)
+            .map(|last| ((now - last).num_milliseconds() as f64 / 1000.0).clamp(0.0, MAX_STEP))
+            .unwrap_or_default();
+        let bucket = &mut track.baseline[now.hour() as usize % BUCKETS];
+
+        let z = bucket.zscore(value);
+        let anomalous = z.is_some_and(|z| z.abs() >= self.threshold);
+
+        if !anomalous {
+            bucket.learn(value, step);
+            track.streak = 0;
+            let idx = track.open.take()?;
+            let period = self.periods.get_mut(idx)?;
+            period.end_tick = Some(tick);
+            return Some(AnomalyEvent {
+                anomaly: period.clone(),
+                started: false,
+            });
+        }
+
+        bucket.learn(value, step * ANOMALY_WEIGHT);
+        let z = z.unwrap_or_default();
+        track.streak += 1;
+        if let Some(idx) = track.open {
+            // 进行中: 记录偏离最大的样本
+            if let Some(period) = self.periods.get_mut(idx) {
+                if z.abs() > period.z.abs() {
+                    period.value = value;
+                    period.expected = bucket.mean;
+                    period.z = z;
+                }
+            }
+            return None;
+        }
+        if track.streak < MIN_STREAK {
+            return None;
+        }
+
+        let anomaly = Anomaly {
+            device: device.to_string(),
+            incoming,
+            start_tick: tick.saturating_sub(MIN_STREAK as u64 - 1),
+            end_tick: None,
+            started_at: now,
+            value,
+            expected: bucket.mean,
+            z,
+        };
+        if self.periods.len() >= MAX_PERIODS {
+            // 丢弃最旧的区间，并修正其他 track 中指向 periods 的下标
+            self.periods.pop_front();
+            for t in self.tracks.values_mut() {
+                t.open = t.open.and_then(|i| i.checked_sub(1));
+            }
+        }
+        self.periods.push_back(anomaly.clone());
+        if let Some(track) = self.tracks.get_mut(&(device.to_string(), incoming)) {
+            track.open = Some(self.periods.len() - 1);
+        }
+        Some(AnomalyEvent {
+            anomaly,
+            started: true,
+        })
+    }
+
+    /// 清空异常区间和进行中的状态 (回放往回跳转时使用)，保留已学到的基线
+    pub fn reset(&mut self) {
+        self.periods.clear();
+        for track in self.tracks.values_mut() {
+            track.last = None;
+            track.streak = 0;
+            track.open = None;
+        }
+    }
+
+    /// 设备某方向正在进行的异常
+    pub fn active(&self, device: &str, incoming: bool) -> Option<&Anomaly> {
+        let track = self.tracks.get(&(device.to_string(), incoming))?;
+        self.periods.get(track.open?)
+    }
+
+    /// 图形每一列是否处于异常区间 (第 0 个元素对应最新一列)
+    pub fn marks(&self, device: &str, incoming: bool, now_tick: u64, width: usize) -> Vec<bool> {
+        let mut marks = vec![false; width];
+        for p in self.periods.iter().filter(|p| p.device == device && p.incoming == incoming) {
+            let end = p.end_tick.unwrap_or(now_tick);
+            for (age, mark) in marks.iter_mut().enumerate() {
+                let tick = now_tick.saturating_sub(age as u64);
+                if tick >= p.start_tick && tick <= end {
+                    *mark = true;
+                }
+            }
+        }
+        marks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    /// 从整点开始，每 0.5 秒喂一个样本，返回每个样本产生的事件
+    fn feed(detector: &mut AnomalyDetector, values: impl IntoIterator<Item = f64>) -> Vec<(usize, bool)> {
+        let start = Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        values
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, v)| {
+                let now = start + Duration::milliseconds(500 * i as i64);
+                detector.observe("eth0", true, v, now, i as u64).map(|e| (i, e.started))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn short_spike_does_not_move_baseline() {
+        let mut detector = AnomalyDetector::new(4.0);
+        // 10 分钟 1 MB/s，10 秒 10 MB/s，再回到 1 MB/s
+        let values = [1e6; 1200].into_iter().chain([1e7; 20]).chain([1e6; 100]);
+        assert_eq!(feed(&mut detector, values), [(1202, true), (1220, false)]);
+        let mean = detector.baselines()[&("eth0".to_string(), true)][9].mean;
+        // 全权重学习会把均值拉高约 15%，降权后不到 2%
+        assert!((mean - 1e6).abs() < 2e4, "mean drifted to {mean}");
+    }
+
+    #[test]
+    fn sustained_shift_becomes_normal() {
+        let mut detector = AnomalyDetector::new(4.0);
+        // 10 分钟 1 MB/s，之后一直是 10 MB/s: 应在这一小时内被接受
+        let values = [1e6; 1200].into_iter().chain([1e7; 5800]);
+        let events = feed(&mut detector, values);
+        assert_eq!(events.len(), 2, "{events:?}");
+        assert!(events[0].1 && !events[1].1);
+    }
+
+    #[test]
+    fn weight_follows_time_not_sample_count() {
+        // 同样 2 分钟的数据，0.5 秒间隔和 5 秒间隔学到的时长相同
+        let start = Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        let learned = |step_ms: i64| {
+            let mut detector = AnomalyDetector::new(4.0);
+            for i in 0..=120_000 / step_ms {
+                detector.observe("eth0", true, 1e6, start + Duration::milliseconds(step_ms * i), i as u64);
+            }
+            detector.baselines()[&("eth0".to_string(), true)][9].n
+        };
+        assert_eq!(learned(500), 120.0);
+        assert_eq!(learned(5000), 120.0);
+    }
+}
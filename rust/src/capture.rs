@@ -0,0 +1,341 @@
+//! 抓包来源 (--filter，仅 Linux)
+//!
+//! 用 AF_PACKET 套接字捕获网卡上的包 (需要 root 或 CAP_NET_RAW)，按每个 `--filter`
+//! 表达式分别计数。每个表达式显示为一个虚拟设备，和物理网卡一样有完整的统计和图形，
+//! 可以只看某个服务的带宽:
+//!
+//! ```text
+//! winload --filter 'tcp port 443'
+//! winload --filter 'web=tcp port 80 or 443' --filter 'dns=port 53' --capture-iface eth0
+//! ```
+//!
+//! 计数的是网络层长度 (不含链路层头)，所以会比网卡计数器略小。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::collector::{DeviceInfo, LinkState, Snapshot};
+use crate::decode::Packet;
+use crate::filter::Filter;
+use crate::flows::{FlowDeltas, FlowKey};
+use crate::protocols::Breakdown;
+use crate::source::{self, Source};
+
+// ─── 虚拟设备 ──────────────────────────────────────────────
+
+/// 一个 --filter 表达式及其累计计数
+pub struct Tap {
+    pub name: String,
+    pub filter: Filter,
+    bytes_recv: AtomicU64,
+    bytes_sent: AtomicU64,
+    packets_recv: AtomicU64,
+    packets_sent: AtomicU64,
+}
+
+impl Tap {
+    pub fn count(&self, packet: &Packet) {
+        if !self.filter.matches(packet) {
+            return;
+        }
+        let (bytes, packets) = if packet.outgoing {
+            (&self.bytes_sent, &self.packets_sent)
+        } else {
+            (&self.bytes_recv, &self.packets_recv)
+        };
+        bytes.fetch_add(packet.len as u64, Ordering::Relaxed);
+        packets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 当前累计值，顺序同 Snapshot: rx / tx 字节、包、错误 (错误恒为 0)
+    pub fn counters(&self) -> [u64; 6] {
+        [
+            self.bytes_recv.load(Ordering::Relaxed),
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.packets_recv.load(Ordering::Relaxed),
+            self.packets_sent.load(Ordering::Relaxed),
+            0,
+            0,
+        ]
+    }
+}
+
+/// 解析所有 `[NAME=]EXPR`；名称缺省为表达式本身
+pub fn parse_taps(filters: &[String]) -> Result<Vec<Tap>, String> {
+    let mut taps: Vec<Tap> = Vec::new();
+    for f in filters {
+        let (name, expr) = source::parse_target(f);
+        let filter = Filter::parse(expr).map_err(|e| format!("Invalid filter '{expr}': {e}"))?;
+        let name = name.unwrap_or(expr).to_string();
+        if taps.iter().any(|t| t.name == name) {
+            return Err(format!("Two filters are named '{name}': use NAME=EXPR to tell them apart"));
+        }
+        taps.push(Tap {
+            name,
+            filter,
+            bytes_recv: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            packets_recv: AtomicU64::new(0),
+            packets_sent: AtomicU64::new(0),
+        });
+    }
+    Ok(taps)
+}
+
+/// 捕获线程和 App 共享的状态
+struct Shared {
+    taps: Vec<Tap>,
+    /// App 上次取走以来各连接的字节数
+    flows: Mutex<FlowDeltas>,
+    /// App 上次取走以来各协议的字节数
+    protocols: Mutex<Breakdown>,
+    /// 捕获线程退出时的错误
+    error: Mutex<Option<String>>,
+}
+
+impl Shared {
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn count(&self, packet: &Packet) {
+        for tap in &self.taps {
+            tap.count(packet);
+        }
+        if let (Some(key), Ok(mut flows)) = (FlowKey::of(packet), self.flows.lock()) {
+            flows.entry(key).or_default()[packet.outgoing as usize] += packet.len as u64;
+        }
+        if let Ok(mut protocols) = self.protocols.lock() {
+            protocols.add(packet);
+        }
+    }
+}
+
+/// 在另一个来源的设备之外加上抓包得到的虚拟设备
+pub struct Captured {
+    inner: Box<dyn Source>,
+    shared: Arc<Shared>,
+    /// 抓包的网卡，`any` 为所有网卡
+    iface: String,
+    start: Instant,
+}
+
+/// 开始在 `iface` (None 为所有网卡) 上抓包，每个 `[NAME=]EXPR` 成为一个虚拟设备，同时按连接统计流量
+///
+/// 打开套接字失败 (权限不足等) 时直接返回错误。
+pub fn attach(inner: Box<dyn Source>, iface: Option<&str>, filters: &[String]) -> Result<Box<dyn Source>, String> {
+    let taps = parse_taps(filters)?;
+    let shared = Arc::new(Shared {
+        taps,
+        flows: Mutex::new(HashMap::new()),
+        protocols: Mutex::new(Breakdown::default()),
+        error: Mutex::new(None),
+    });
+    platform::start(iface, shared.clone())?;
+    Ok(Box::new(Captured {
+        inner,
+        shared,
+        iface: iface.unwrap_or("any").to_string(),
+        start: Instant::now(),
+    }))
+}
+
+impl Source for Captured {
+    fn devices(&self) -> Vec<DeviceInfo> {
+        let mut devices = self.inner.devices();
+        devices.extend(self.shared.taps.iter().map(|tap| {
+            let expr = tap.filter.to_string();
+            DeviceInfo {
+                addrs: if tap.name == expr { vec![self.iface.clone()] } else { vec![expr, self.iface.clone()] },
+                name: tap.name.clone(),
+            }
+        }));
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+        devices
+    }
+
+    fn refresh_list(&mut self) {
+        self.inner.refresh_list();
+    }
+
+    fn collect(&mut self) -> HashMap<String, Snapshot> {
+        let mut snapshots = self.inner.collect();
+        let elapsed_secs = self.start.elapsed().as_secs_f64();
+        for tap in &self.shared.taps {
+            let [bytes_recv, bytes_sent, packets_recv, packets_sent, errors_recv, errors_sent] = tap.counters();
+            snapshots.insert(
+                tap.name.clone(),
+                Snapshot {
+                    elapsed_secs,
+                    bytes_recv,
+                    bytes_sent,
+                    packets_recv,
+                    packets_sent,
+                    errors_recv,
+                    errors_sent,
+                },
+            );
+        }
+        snapshots
+    }
+
+    fn link_state(&self, name: &str) -> Option<LinkState> {
+        self.inner.link_state(name)
+    }
+
+    fn owners(&self) -> HashMap<String, String> {
+        self.inner.owners()
+    }
+
+    fn take_flows(&mut self) -> FlowDeltas {
+        self.shared.flows.lock().map(|mut f| std::mem::take(&mut *f)).unwrap_or_default()
+    }
+
+    fn take_protocols(&mut self) -> Breakdown {
+        self.shared.protocols.lock().map(|mut p| std::mem::take(&mut *p)).unwrap_or_default()
+    }
+
+    fn capturing(&self) -> bool {
+        true
+    }
+
+    fn take_errors(&mut self) -> Vec<(String, String)> {
+        let mut errors = self.inner.take_errors();
+        if let Some(e) = self.shared.error.lock().ok().and_then(|mut e| e.take()) {
+            errors.push(("capture".to_string(), e));
+        }
+        errors
+    }
+
+    fn connected(&self) -> bool {
+        self.inner.connected()
+    }
+
+    fn label(&self) -> Option<String> {
+        self.inner.label()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+//  Linux 实现 (AF_PACKET)
+// ═══════════════════════════════════════════════════════════
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::ffi::CString;
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::thread;
+
+    /// 每个包只读取前面这么多字节，足够解析到传输层端口
+    const SNAPLEN: usize = 256;
+    /// 接收缓冲区，突发流量时减少内核丢包
+    const RCVBUF: libc::c_int = 4 << 20;
+
+    /// 打开 AF_PACKET 套接字并启动捕获线程
+    pub fn start(iface: Option<&str>, shared: Arc<Shared>) -> Result<(), String> {
+        let protocol = (libc::ETH_P_ALL as u16).to_be();
+        // SOCK_DGRAM: 内核去掉链路层头，直接给出网络层数据，协议类型在 sll_protocol 中
+        // SAFETY: 普通的系统调用，返回的 fd 立即交给 OwnedFd 管理
+        let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, protocol as libc::c_int) };
+        if fd < 0 {
+            let e = io::Error::last_os_error();
+            return Err(if e.kind() == io::ErrorKind::PermissionDenied {
+                format!("packet capture needs root (or CAP_NET_RAW): {e}")
+            } else {
+                format!("cannot open packet socket: {e}")
+            });
+        }
+        // SAFETY: fd 是刚创建的有效套接字，没有别的所有者
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        if let Some(name) = iface {
+            let c_name = CString::new(name).map_err(|_| format!("bad interface name '{name}'"))?;
+            // SAFETY: c_name 是以 NUL 结尾的字符串
+            let index = unsafe { libc::if_nametoindex(c_name.as_ptr()) };
+            if index == 0 {
+                return Err(format!("No such interface '{name}'"));
+            }
+            // SAFETY: sockaddr_ll 全零是合法值
+            let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+            addr.sll_family = libc::AF_PACKET as u16;
+            addr.sll_protocol = protocol;
+            addr.sll_ifindex = index as i32;
+            // SAFETY: addr 的大小与传入的长度一致
+            let ret = unsafe {
+                libc::bind(
+                    socket.as_raw_fd(),
+                    &addr as *const libc::sockaddr_ll as *const libc::sockaddr,
+                    mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+                )
+            };
+            if ret != 0 {
+                return Err(format!("cannot capture on {name}: {}", io::Error::last_os_error()));
+            }
+        }
+        // SAFETY: 选项值是一个 c_int，失败时保持内核默认值
+        unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                &RCVBUF as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+        }
+
+        thread::Builder::new()
+            .name("capture".to_string())
+            .spawn(move || {
+                if let Err(e) = capture_loop(&socket, &shared) {
+                    if let Ok(mut slot) = shared.error.lock() {
+                        *slot = Some(e);
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn capture thread: {e}"))?;
+        Ok(())
+    }
+
+    fn capture_loop(socket: &OwnedFd, shared: &Shared) -> Result<(), String> {
+        let mut buf = [0u8; SNAPLEN];
+        loop {
+            // SAFETY: sockaddr_ll 全零是合法值
+            let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+            let mut addr_len = mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+            // MSG_TRUNC: 只复制前 SNAPLEN 字节，但返回包的完整长度
+            // SAFETY: buf 和 addr 在调用期间有效，长度与传入的一致
+            let n = unsafe {
+                libc::recvfrom(
+                    socket.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_TRUNC,
+                    &mut addr as *mut libc::sockaddr_ll as *mut libc::sockaddr,
+                    &mut addr_len,
+                )
+            };
+            if n < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e.to_string());
+            }
+            let len = n as usize;
+            let outgoing = addr.sll_pkttype == libc::PACKET_OUTGOING;
+            let packet = Packet::decode(u16::from_be(addr.sll_protocol), &buf[..len.min(SNAPLEN)], len as u32, outgoing);
+            shared.count(&packet);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::*;
+
+    pub fn start(_iface: Option<&str>, _shared: Arc<Shared>) -> Result<(), String> {
+        Err("--filter is only supported on Linux".to_string())
+    }
+}
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 /// 单次采样快照
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Snapshot {
     /// 自程序启动以来的秒数
     pub elapsed_secs: f64,
@@ -14,6 +14,12 @@ pub struct Snapshot {
     pub bytes_recv: u64,
     /// 累计发送字节数
     pub bytes_sent: u64,
+    /// 累计接收 / 发送包数
+    pub packets_recv: u64,
+    pub packets_sent: u64,
+    /// 累计接收 / 发送错误数
+    pub errors_recv: u64,
+    pub errors_sent: u64,
 }
 
 /// 网卡设备信息
@@ -25,10 +31,64 @@ pub struct DeviceInfo {
     pub addrs: Vec<String>,
 }
 
+/// 网卡链路状态 (目前仅 Linux 可用，来自 /sys/class/net)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkState {
+    /// operstate: up / down / unknown / dormant / lowerlayerdown ...
+    pub oper: String,
+    /// 协商速率 (Mbit/s)，链路断开或虚拟网卡上没有
+    pub speed_mbps: Option<u64>,
+}
+
+impl LinkState {
+    /// 链路是否可用 (unknown 常见于 lo、tun 等虚拟网卡，视为可用)
+    pub fn is_up(&self) -> bool {
+        matches!(self.oper.as_str(), "up" | "unknown")
+    }
+}
+
+/// 读取网卡的链路状态；不支持的平台或读取失败时返回 None
+#[cfg(target_os = "linux")]
+pub fn link_state(name: &str) -> Option<LinkState> {
+    let base = std::path::Path::new("/sys/class/net").join(name);
+    let oper = std::fs::read_to_string(base.join("operstate")).ok()?.trim().to_string();
+    // 链路断开时读取 speed 会返回 EINVAL，部分驱动给出 -1
+    let speed_mbps = std::fs::read_to_string(base.join("speed"))
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|&s| s > 0)
+        .map(|s| s as u64);
+    Some(LinkState { oper, speed_mbps })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn link_state(_name: &str) -> Option<LinkState> {
+    None
+}
+
+/// 解析 Linux `/proc/net/dev` 格式: 两行表头，之后每行
+/// `name: rx_bytes rx_packets rx_errs ... tx_bytes tx_packets tx_errs ...`
+///
+/// 返回 (网卡名, [rx 字节, tx 字节, rx 包, tx 包, rx 错误, tx 错误])，格式不对的行忽略。
+pub fn parse_proc_net_dev(text: &str) -> Vec<(String, [u64; 6])> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<u64> = rest.split_whitespace().map(|f| f.parse().ok()).collect::<Option<_>>()?;
+            if fields.len() < 11 {
+                return None;
+            }
+            Some((name.trim().to_string(), [fields[0], fields[8], fields[1], fields[9], fields[2], fields[10]]))
+        })
+        .collect()
+}
+
 /// 网络流量采集器
 pub struct Collector {
     networks: Networks,
     start: Instant,
+    /// veth 网卡的属主
+    owners: crate::netns::OwnerCache,
 }
 
 impl Collector {
@@ -36,9 +96,15 @@ impl Collector {
         Self {
             networks: Networks::new_with_refreshed_list(),
             start: Instant::now(),
+            owners: Default::default(),
         }
     }
 
+    /// 重新扫描网卡列表，发现新增 / 移除的网卡 (已有网卡的计数器不受影响)
+    pub fn refresh_list(&mut self) {
+        self.networks.refresh_list();
+    }
+
     /// 获取自启动以来的秒数
     pub fn elapsed_secs(&self) -> f64 {
         self.start.elapsed().as_secs_f64()
@@ -67,9 +133,15 @@ impl Collector {
             println!();
         }
 
-        println!("Filtered devices (IPv4 only, used in UI): {}\n", self.devices().len());
-        for dev in self.devices() {
-            println!("  - {} [{}]", dev.name, dev.addrs.join(", "));
+        let devices = self.devices();
+        let names: Vec<String> = devices.iter().map(|d| d.name.clone()).collect();
+        let owners = crate::netns::veth_owners(&names);
+        println!("Filtered devices (IPv4 only, used in UI): {}\n", devices.len());
+        for dev in devices {
+            match owners.get(&dev.name) {
+                Some(owner) => println!("  - {} [{}] -> {}", dev.name, dev.addrs.join(", "), owner),
+                None => println!("  - {} [{}]", dev.name, dev.addrs.join(", ")),
+            }
         }
 
         // Windows loopback 说明
@@ -120,6 +192,12 @@ impl Collector {
         devs
     }
 
+    /// veth 网卡的属主 (容器名或进程)，在后台扫描，见 netns::OwnerCache
+    pub fn owners(&self) -> HashMap<String, String> {
+        let names: Vec<String> = self.devices().into_iter().map(|d| d.name).collect();
+        self.owners.get(&names)
+    }
+
     /// 采集一次所有网卡的当前累计数据
     pub fn collect(&mut self) -> HashMap<String, Snapshot> {
         // refresh() 只刷新已有接口的数据，不重建列表，计数器不会丢失
@@ -136,6 +214,10 @@ impl Collector {
                         elapsed_secs: elapsed,
                         bytes_recv: data.total_received(),
                         bytes_sent: data.total_transmitted(),
+                        packets_recv: data.total_packets_received(),
+                        packets_sent: data.total_packets_transmitted(),
+                        errors_recv: data.total_errors_on_received(),
+                        errors_sent: data.total_errors_on_transmitted(),
                     },
                 )
             })
@@ -151,6 +233,10 @@ impl Collector {
                         elapsed_secs: elapsed,
                         bytes_recv: data.total_received(),
                         bytes_sent: data.total_transmitted(),
+                        packets_recv: data.total_packets_received(),
+                        packets_sent: data.total_packets_transmitted(),
+                        errors_recv: data.total_errors_on_received(),
+                        errors_sent: data.total_errors_on_transmitted(),
                     },
                 )
             })
@@ -168,8 +254,7 @@ impl Collector {
                     "Loopback Pseudo-Interface 1".to_string(),
                     Snapshot {
                         elapsed_secs: elapsed,
-                        bytes_recv: 0,
-                        bytes_sent: 0,
+                        ..Default::default()
                     },
                 );
             }
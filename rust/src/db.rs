@@ -0,0 +1,320 @@
+//! 持久化流量数据库 (类似 vnstat)
+//!
+//! 按网卡把收发字节数累加到 小时 / 天 / 月 三级桶里，跨运行、跨重启保留。
+//! 每个网卡记住最后一次看到的原始计数器，计数器回退 (重启 / 网卡重建)
+//! 时把新计数器整体视为增量，所以不会丢数据也不会出现负数。
+//!
+//! 文件格式为纯文本，字段之间用制表符分隔 (网卡名可能带空格)，第一行是版本号:
+//!
+//! ```text
+//! winload-db  1
+//! iface  eth0  <last_rx>  <last_tx>
+//! h      eth0  2026-10-19 14  <rx>  <tx>
+//! d      eth0  2026-10-19     <rx>  <tx>
+//! m      eth0  2026-10        <rx>  <tx>
+//! b      eth0  in  14  <n>  <mean>  <var>
+//! ```
+//!
+//! `b` 行是异常检测 (--anomaly) 学到的每小时速率基线，版本 2 起加入。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::anomaly::{Baseline, BUCKETS};
+
+const MAGIC: &str = "winload-db";
+const VERSION: u32 = 2;
+
+/// 各级桶保留的条目数
+const KEEP_HOURS: usize = 72;
+const KEEP_DAYS: usize = 62;
+const KEEP_MONTHS: usize = 24;
+
+/// 某个时间桶内的收发字节数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl Totals {
+    fn add(&mut self, rx: u64, tx: u64) {
+        self.rx = self.rx.saturating_add(rx);
+        self.tx = self.tx.saturating_add(tx);
+    }
+}
+
+/// 单个网卡的持久化记录
+#[derive(Clone, Debug, Default)]
+pub struct IfaceRecord {
+    /// 最后一次看到的原始计数器 (rx, tx)
+    last: Option<(u64, u64)>,
+    /// key: "YYYY-MM-DD HH"
+    pub hours: BTreeMap<String, Totals>,
+    /// key: "YYYY-MM-DD"
+    pub days: BTreeMap<String, Totals>,
+    /// key: "YYYY-MM"
+    pub months: BTreeMap<String, Totals>,
+}
+
+/// 流量数据库
+pub struct TrafficDb {
+    path: PathBuf,
+    pub ifaces: BTreeMap<String, IfaceRecord>,
+    /// 异常检测基线，key: (设备, 是否为收方向)
+    baselines: BTreeMap<(String, bool), Baseline>,
+    dirty: bool,
+}
+
+/// 计数器增量；计数器回退时把当前值整体视为增量
+pub fn counter_delta(last: u64, current: u64) -> u64 {
+    if current >= last {
+        current - last
+    } else {
+        current
+    }
+}
+
+fn prune(map: &mut BTreeMap<String, Totals>, keep: usize) {
+    while map.len() > keep {
+        let oldest = map.keys().next().cloned();
+        if let Some(k) = oldest {
+            map.remove(&k);
+        }
+    }
+}
+
+/// 默认数据库路径
+///
+/// - Windows: `%APPDATA%\winload\traffic.db`
+/// - 其他:    `$XDG_DATA_HOME/winload/traffic.db` 或 `~/.local/share/winload/traffic.db`
+pub fn default_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join("winload").join("traffic.db"))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share"))
+            })?;
+        Some(base.join("winload").join("traffic.db"))
+    }
+}
+
+impl TrafficDb {
+    /// 打开数据库；文件不存在时返回空库 (首次保存时创建)
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut db = Self {
+            path: path.to_path_buf(),
+            ifaces: BTreeMap::new(),
+            baselines: BTreeMap::new(),
+            dirty: false,
+        };
+        match fs::read_to_string(path) {
+            Ok(text) => db.parse(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Cannot read {}: {e}", path.display())),
+        }
+        Ok(db)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn parse(&mut self, text: &str) -> Result<(), String> {
+        let path = self.path.display().to_string();
+        let bad = |n: usize| format!("{path}: malformed line {}", n + 1);
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, first)) => {
+                let mut parts = first.split('\t');
+                if parts.next() != Some(MAGIC) {
+                    return Err(format!("{path}: not a winload database"));
+                }
+                let version: u32 = parts.next().and_then(|v| v.parse().ok()).ok_or_else(|| bad(0))?;
+                if version > VERSION {
+                    return Err(format!(
+                        "{path}: database version {version} is newer than supported ({VERSION})"
+                    ));
+                }
+            }
+            None => return Ok(()),
+        }
+
+        for (n, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let f: Vec<&str> = line.split('\t').collect();
+            let num = |i: usize| f.get(i).and_then(|v| v.parse::<u64>().ok()).ok_or_else(|| bad(n));
+            match f[0] {
+                "iface" if f.len() == 4 => {
+                    let rec = self.ifaces.entry(f[1].to_string()).or_default();
+                    rec.last = Some((num(2)?, num(3)?));
+                }
+                "h" | "d" | "m" if f.len() == 5 => {
+                    let rec = self.ifaces.entry(f[1].to_string()).or_default();
+                    let map = match f[0] {
+                        "h" => &mut rec.hours,
+                        "d" => &mut rec.days,
+                        _ => &mut rec.months,
+                    };
+                    map.insert(f[2].to_string(), Totals { rx: num(3)?, tx: num(4)? });
+                }
+                "b" if f.len() == 7 => {
+                    let float = |i: usize| f[i].parse::<f64>().map_err(|_| bad(n));
+                    let incoming = match f[2] {
+                        "in" => true,
+                        "out" => false,
+                        _ => return Err(bad(n)),
+                    };
+                    let hour = num(3)? as usize;
+                    if hour >= BUCKETS {
+                        return Err(bad(n));
+                    }
+                    let baseline = self.baselines.entry((f[1].to_string(), incoming)).or_default();
+                    let bucket = &mut baseline[hour];
+                    bucket.n = float(4)?;
+                    bucket.mean = float(5)?;
+                    bucket.var = float(6)?;
+                }
+                _ => return Err(bad(n)),
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录某网卡的最新原始计数器，把增量计入当前时间所在的桶
+    pub fn record(&mut self, name: &str, rx: u64, tx: u64, now: DateTime<Local>) {
+        let rec = self.ifaces.entry(name.to_string()).or_default();
+        let (drx, dtx) = match rec.last {
+            Some((lrx, ltx)) => (counter_delta(lrx, rx), counter_delta(ltx, tx)),
+            // 第一次见到该网卡: 只建立基准，不计入开机以来的历史流量
+            None => (0, 0),
+        };
+        rec.last = Some((rx, tx));
+        self.dirty = true;
+
+        if drx == 0 && dtx == 0 {
+            return;
+        }
+        rec.hours.entry(now.format("%Y-%m-%d %H").to_string()).or_default().add(drx, dtx);
+        rec.days.entry(now.format("%Y-%m-%d").to_string()).or_default().add(drx, dtx);
+        rec.months.entry(now.format("%Y-%m").to_string()).or_default().add(drx, dtx);
+        prune(&mut rec.hours, KEEP_HOURS);
+        prune(&mut rec.days, KEEP_DAYS);
+        prune(&mut rec.months, KEEP_MONTHS);
+    }
+
+    /// 已保存的异常检测基线
+    pub fn baselines(&self) -> &BTreeMap<(String, bool), Baseline> {
+        &self.baselines
+    }
+
+    /// 更新异常检测基线 (下次保存时写入)
+    pub fn set_baselines(&mut self, baselines: BTreeMap<(String, bool), Baseline>) {
+        self.baselines = baselines;
+        self.dirty = true;
+    }
+
+    /// 写回磁盘 (先写临时文件再 rename，避免中途退出损坏数据库)
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+
+        let mut out = String::new();
+        out.push_str(&format!("{MAGIC}\t{VERSION}\n"));
+        for (name, rec) in &self.ifaces {
+            if let Some((rx, tx)) = rec.last {
+                out.push_str(&format!("iface\t{name}\t{rx}\t{tx}\n"));
+            }
+            for (tag, map) in [("h", &rec.hours), ("d", &rec.days), ("m", &rec.months)] {
+                for (key, v) in map {
+                    out.push_str(&format!("{tag}\t{name}\t{key}\t{}\t{}\n", v.rx, v.tx));
+                }
+            }
+        }
+        for ((name, incoming), baseline) in &self.baselines {
+            let dir = if *incoming { "in" } else { "out" };
+            for (hour, b) in baseline.iter().enumerate().filter(|(_, b)| b.n > 0.0) {
+                out.push_str(&format!("b\t{name}\t{dir}\t{hour}\t{}\t{}\t{}\n", b.n, b.mean, b.var));
+            }
+        }
+
+        let tmp = self.path.with_extension("db.tmp");
+        {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(out.as_bytes())?;
+            f.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+// ─── report 子命令 ────────────────────────────────────────
+
+use crate::stats::format_bytes;
+
+fn print_table(title: &str, map: &BTreeMap<String, Totals>, limit: usize) {
+    if map.is_empty() {
+        return;
+    }
+    println!("  {:<16}{:>16}{:>16}{:>16}", title, "rx", "tx", "total");
+    let skip = map.len().saturating_sub(limit);
+    for (key, v) in map.iter().skip(skip) {
+        println!(
+            "  {:<16}{:>16}{:>16}{:>16}",
+            key,
+            format_bytes(v.rx),
+            format_bytes(v.tx),
+            format_bytes(v.rx.saturating_add(v.tx)),
+        );
+    }
+    println!();
+}
+
+/// 打印各网卡的 小时 / 天 / 月 统计表
+pub fn print_report(db: &TrafficDb, device: Option<&str>) {
+    println!("\nTraffic database: {}\n", db.path().display());
+
+    let filter = device.map(|d| d.to_lowercase());
+    let mut shown = 0;
+    for (name, rec) in &db.ifaces {
+        if let Some(ref f) = filter {
+            if !name.to_lowercase().contains(f) {
+                continue;
+            }
+        }
+        if rec.hours.is_empty() && rec.days.is_empty() && rec.months.is_empty() {
+            continue;
+        }
+        shown += 1;
+        println!("{name}");
+        println!("{}", "-".repeat(name.chars().count().max(8)));
+        print_table("hour", &rec.hours, 24);
+        print_table("day", &rec.days, 31);
+        print_table("month", &rec.months, 12);
+    }
+
+    if shown == 0 {
+        println!("(no traffic recorded yet — run winload with --db to start collecting)");
+    }
+}
@@ -0,0 +1,313 @@
+//! 抓包数据解码 (与平台无关)
+//!
+//! 实时抓包 (AF_PACKET、Npcap) 和抓包文件 (--read) 共用: 去掉链路层头，解析网络层和传输层头部，
+//! 得到过滤和计数需要的地址、协议和端口，并判断包的方向。
+//!
+//! 支持的链路类型: DLT_NULL / DLT_LOOP (BSD 和 Npcap 的回环)、以太网、Linux cooked (SLL / SLL2)、
+//! 裸 IP。方向优先取链路层给出的 (SLL 的包类型、pcapng 的标志)，没有时按本机地址判断。
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+// ─── 链路层 ────────────────────────────────────────────────
+
+/// pcap 链路类型 (LINKTYPE_*)
+pub const LINKTYPE_NULL: u16 = 0;
+pub const LINKTYPE_ETHERNET: u16 = 1;
+pub const LINKTYPE_RAW: u16 = 101;
+pub const LINKTYPE_LOOP: u16 = 108;
+pub const LINKTYPE_LINUX_SLL: u16 = 113;
+pub const LINKTYPE_IPV4: u16 = 228;
+pub const LINKTYPE_IPV6: u16 = 229;
+pub const LINKTYPE_LINUX_SLL2: u16 = 276;
+
+/// Linux cooked 头的包类型: 本机发出
+const PACKET_OUTGOING: u16 = 4;
+
+/// 链路层头给出的信息
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link {
+    /// 网络层协议 (以太网类型)
+    pub ethertype: u16,
+    /// 网络层数据在帧中的偏移
+    pub offset: usize,
+    /// 链路层给出的方向 (true 为发出)；以太网、裸 IP 等没有
+    pub outgoing: Option<bool>,
+}
+
+/// 解析链路层头；不认识的链路类型、被截断或不是 IP / ARP 的 DLT_NULL 返回 None
+pub fn link_layer(linktype: u16, data: &[u8]) -> Option<Link> {
+    let be16 = |at: usize| data.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let link = |ethertype, offset, outgoing| Some(Link { ethertype, offset, outgoing });
+    match linktype {
+        // 4 字节地址族: DLT_NULL 为抓包主机的字节序，DLT_LOOP 为网络字节序
+        LINKTYPE_NULL | LINKTYPE_LOOP => {
+            let b: [u8; 4] = data.get(..4)?.try_into().ok()?;
+            let family = if linktype == LINKTYPE_LOOP {
+                u32::from_be_bytes(b)
+            } else if b[0] == 0 && b[1] == 0 {
+                // 地址族都小于 256，前两个字节为 0 说明是大端
+                u32::from_be_bytes(b)
+            } else {
+                u32::from_le_bytes(b)
+            };
+            match family {
+                2 => link(0x0800, 4, None),
+                // IPv6 的 AF_INET6 各平台不同: BSD 24、FreeBSD 28、macOS 30、Windows 23
+                10 | 23 | 24 | 28 | 30 => link(0x86dd, 4, None),
+                _ => None,
+            }
+        }
+        LINKTYPE_ETHERNET => link(be16(12)?, 14, None),
+        // 包类型 (2)、ARPHRD (2)、地址长度 (2)、地址 (8)、协议 (2)
+        LINKTYPE_LINUX_SLL => link(be16(14)?, 16, Some(be16(0)? == PACKET_OUTGOING)),
+        // 协议 (2)、保留 (2)、ifindex (4)、ARPHRD (2)、包类型 (1)、地址长度 (1)、地址 (8)
+        LINKTYPE_LINUX_SLL2 => link(be16(0)?, 20, Some(*data.get(10)? as u16 == PACKET_OUTGOING)),
+        // 裸 IP: 按版本号区分；12 和 14 是一些平台上裸 IP 的 DLT 值
+        LINKTYPE_RAW | 12 | 14 => match data.first()? >> 4 {
+            4 => link(0x0800, 0, None),
+            6 => link(0x86dd, 0, None),
+            _ => None,
+        },
+        LINKTYPE_IPV4 => link(0x0800, 0, None),
+        LINKTYPE_IPV6 => link(0x86dd, 0, None),
+        _ => None,
+    }
+}
+
+/// 链路类型的名称，用于汇总报告
+pub fn linktype_name(linktype: u16) -> String {
+    match linktype {
+        LINKTYPE_NULL | LINKTYPE_LOOP => "BSD loopback".to_string(),
+        LINKTYPE_ETHERNET => "Ethernet".to_string(),
+        LINKTYPE_LINUX_SLL => "Linux cooked".to_string(),
+        LINKTYPE_LINUX_SLL2 => "Linux cooked v2".to_string(),
+        LINKTYPE_RAW | 12 | 14 | LINKTYPE_IPV4 | LINKTYPE_IPV6 => "raw IP".to_string(),
+        other => format!("linktype {other}"),
+    }
+}
+
+// ─── 网络层 / 传输层 ───────────────────────────────────────
+
+/// 网络层协议
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Network {
+    Ipv4,
+    Ipv6,
+    Arp,
+    Other,
+}
+
+/// 解码后的包头信息，供过滤和计数使用
+#[derive(Clone, Debug)]
+pub struct Packet {
+    /// 网络层长度 (字节)
+    pub len: u32,
+    /// 本机发出的包
+    pub outgoing: bool,
+    pub network: Network,
+    /// 源 / 目的地址 (ARP 为发送方 / 目标的协议地址)
+    pub src: Option<IpAddr>,
+    pub dst: Option<IpAddr>,
+    /// IP 协议号 (IPv6 为跳过扩展头之后的 next header)
+    pub protocol: Option<u8>,
+    /// TCP / UDP / SCTP 端口；分片的后续片段没有
+    pub src_port: Option<u16>,
+    pub dst_port: Option<u16>,
+}
+
+impl Packet {
+    /// 解码网络层数据；`ethertype` 为链路层给出的协议类型，`data` 可能被截断
+    pub fn decode(ethertype: u16, data: &[u8], len: u32, outgoing: bool) -> Self {
+        let mut p = Self {
+            len,
+            outgoing,
+            network: Network::Other,
+            src: None,
+            dst: None,
+            protocol: None,
+            src_port: None,
+            dst_port: None,
+        };
+        match ethertype {
+            0x0800 => p.decode_ipv4(data),
+            0x86dd => p.decode_ipv6(data),
+            0x0806 => p.decode_arp(data),
+            // 没被网卡剥掉的 VLAN 标签
+            0x8100 | 0x88a8 if data.len() >= 4 => {
+                return Self::decode(u16::from_be_bytes([data[2], data[3]]), &data[4..], len, outgoing);
+            }
+            _ => {}
+        }
+        p
+    }
+
+    fn decode_ipv4(&mut self, data: &[u8]) {
+        self.network = Network::Ipv4;
+        if data.len() < 20 {
+            return;
+        }
+        self.src = Some(IpAddr::from(Ipv4Addr::new(data[12], data[13], data[14], data[15])));
+        self.dst = Some(IpAddr::from(Ipv4Addr::new(data[16], data[17], data[18], data[19])));
+        self.protocol = Some(data[9]);
+        let header_len = (data[0] & 0x0f) as usize * 4;
+        let fragment_offset = u16::from_be_bytes([data[6], data[7]]) & 0x1fff;
+        if fragment_offset == 0 {
+            self.decode_ports(data.get(header_len..).unwrap_or_default());
+        }
+    }
+
+    fn decode_ipv6(&mut self, data: &[u8]) {
+        self.network = Network::Ipv6;
+        if data.len() < 40 {
+            return;
+        }
+        let addr = |at: usize| {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&data[at..at + 16]);
+            IpAddr::from(Ipv6Addr::from(octets))
+        };
+        self.src = Some(addr(8));
+        self.dst = Some(addr(24));
+        let mut next = data[6];
+        let mut offset = 40;
+        // 跳过扩展头: hop-by-hop、routing、destination options、fragment、AH
+        loop {
+            let Some(header) = data.get(offset..offset + 8) else {
+                self.protocol = Some(next);
+                return;
+            };
+            match next {
+                0 | 43 | 60 => offset += (header[1] as usize + 1) * 8,
+                51 => offset += (header[1] as usize + 2) * 4,
+                44 => {
+                    if u16::from_be_bytes([header[2], header[3]]) & 0xfff8 != 0 {
+                        self.protocol = Some(header[0]);
+                        return;
+                    }
+                    offset += 8;
+                }
+                _ => break,
+            }
+            next = header[0];
+        }
+        self.protocol = Some(next);
+        self.decode_ports(data.get(offset..).unwrap_or_default());
+    }
+
+    fn decode_arp(&mut self, data: &[u8]) {
+        self.network = Network::Arp;
+        // 以太网 + IPv4 的 ARP: 硬件地址 6 字节，协议地址 4 字节
+        if data.len() < 28 || data[4] != 6 || data[5] != 4 {
+            return;
+        }
+        self.src = Some(IpAddr::from(Ipv4Addr::new(data[14], data[15], data[16], data[17])));
+        self.dst = Some(IpAddr::from(Ipv4Addr::new(data[24], data[25], data[26], data[27])));
+    }
+
+    fn decode_ports(&mut self, l4: &[u8]) {
+        // TCP、UDP、SCTP 的前 4 字节都是源端口和目的端口
+        if matches!(self.protocol, Some(6 | 17 | 132)) && l4.len() >= 4 {
+            self.src_port = Some(u16::from_be_bytes([l4[0], l4[1]]));
+            self.dst_port = Some(u16::from_be_bytes([l4[2], l4[3]]));
+        }
+    }
+}
+
+// ─── 整帧解码 ──────────────────────────────────────────────
+
+impl Packet {
+    /// 解码一整帧 (带链路层头)；`frame_len` 为原始帧长度，`data` 可能被截断
+    ///
+    /// 包长度记为网络层长度。方向优先用 `hint` (pcapng 标志等)，其次用链路层给出的，
+    /// 再按 `local` 判断，都判断不了时记为接收。链路层无法解析时返回 None。
+    pub fn from_frame(linktype: u16, data: &[u8], frame_len: u32, hint: Option<bool>, local: &LocalAddrs) -> Option<Self> {
+        let link = link_layer(linktype, data)?;
+        let len = frame_len.saturating_sub(link.offset as u32);
+        let mut packet = Self::decode(link.ethertype, data.get(link.offset..).unwrap_or_default(), len, false);
+        packet.outgoing = hint.or(link.outgoing).or_else(|| local.direction(&packet)).unwrap_or(false);
+        Some(packet)
+    }
+}
+
+// ─── 方向 ──────────────────────────────────────────────────
+
+/// 本机地址，用于链路层不给出方向时判断包的方向
+#[derive(Clone, Debug, Default)]
+pub struct LocalAddrs(Vec<IpAddr>);
+
+impl LocalAddrs {
+    /// 本机所有网卡的地址 (Npcap 回环捕获使用)
+    #[cfg_attr(not(all(target_os = "windows", feature = "npcap")), allow(dead_code))]
+    pub fn host() -> Self {
+        let networks = sysinfo::Networks::new_with_refreshed_list();
+        Self(networks.values().flat_map(|data| data.ip_networks().iter().map(|n| n.addr)).collect())
+    }
+
+    pub fn push(&mut self, addr: IpAddr) {
+        if !self.0.contains(&addr) {
+            self.0.push(addr);
+        }
+    }
+
+    /// 回环地址总是本机的
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_loopback() || self.0.contains(&addr)
+    }
+
+    /// 按地址判断方向 (true 为发出)；两端都不是本机 (转发、旁路抓包) 时返回 None
+    ///
+    /// 两端都是本机 (回环) 时，每个包既是发出也是收到；按服务端的视角，
+    /// 从较小的端口发往较大的端口 (服务端的响应) 记为发出，其余记为收到。
+    pub fn direction(&self, packet: &Packet) -> Option<bool> {
+        let src = self.contains(packet.src?);
+        let dst = packet.dst.is_some_and(|d| self.contains(d));
+        match (src, dst) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (true, true) => match (packet.src_port, packet.dst_port) {
+                (Some(s), Some(d)) => Some(s < d),
+                _ => Some(false),
+            },
+            (false, false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_family_in_either_byte_order() {
+        let ipv4 = |family: [u8; 4]| link_layer(LINKTYPE_NULL, &[&family[..], &[0x45; 20]].concat());
+        assert_eq!(ipv4([2, 0, 0, 0]).map(|l| (l.ethertype, l.offset)), Some((0x0800, 4)));
+        assert_eq!(ipv4([0, 0, 0, 30]).map(|l| l.ethertype), Some(0x86dd));
+        assert_eq!(link_layer(LINKTYPE_LOOP, &[0, 0, 0, 2]).map(|l| l.ethertype), Some(0x0800));
+        // 不是 IP 的地址族
+        assert_eq!(ipv4([7, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn truncated_or_unknown_link_layer() {
+        assert_eq!(link_layer(LINKTYPE_ETHERNET, &[0; 13]), None);
+        assert_eq!(link_layer(LINKTYPE_LINUX_SLL2, &[0; 10]), None);
+        assert_eq!(link_layer(LINKTYPE_RAW, &[0x50]), None);
+        assert_eq!(link_layer(127, &[0; 64]), None);
+    }
+
+    #[test]
+    fn direction_from_local_addresses() {
+        let mut local = LocalAddrs::default();
+        local.push("192.168.1.10".parse().unwrap());
+        let packet = |src: &str, dst: &str| Packet {
+            src: Some(src.parse().unwrap()),
+            dst: Some(dst.parse().unwrap()),
+            ..Packet::decode(0, &[], 0, false)
+        };
+        assert_eq!(local.direction(&packet("192.168.1.10", "1.1.1.1")), Some(true));
+        assert_eq!(local.direction(&packet("1.1.1.1", "192.168.1.10")), Some(false));
+        assert_eq!(local.direction(&packet("10.0.0.1", "10.0.0.2")), None);
+        // 回环地址总是本机的；没有端口时记为收到
+        assert_eq!(local.direction(&packet("127.0.0.1", "127.0.0.1")), Some(false));
+    }
+}
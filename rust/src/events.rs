@@ -0,0 +1,92 @@
+//! 事件日志
+//!
+//! 保存运行期间发生的值得留意的事情，供事件视图 (`e`) 滚动查看:
+//! 网卡出现 / 消失、链路 up / down 和速率变化、计数器回退、
+//! 告警触发 / 恢复、流量异常、用户标记，以及后台线程 (回环捕获、告警投递) 的错误。
+//! 只保留最近 MAX_EVENTS 条。
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+
+/// 保留的事件条数
+const MAX_EVENTS: usize = 1000;
+
+/// 事件类别
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// 新出现的网卡
+    DeviceAdded,
+    /// 网卡消失
+    DeviceRemoved,
+    /// 链路恢复
+    LinkUp,
+    /// 链路断开
+    LinkDown,
+    /// 协商速率变化
+    LinkSpeed,
+    /// 计数器回退
+    CounterReset,
+    /// 告警触发
+    AlertFiring,
+    /// 告警恢复
+    AlertCleared,
+    /// 速率偏离学习到的基线
+    AnomalyStart,
+    /// 速率回到基线范围
+    AnomalyEnd,
+    /// 后台线程报告的错误
+    Error,
+    /// 用户标记 (m / M)
+    Marker,
+}
+
+/// 一条事件
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub time: DateTime<Local>,
+    pub kind: EventKind,
+    pub device: String,
+    pub message: String,
+}
+
+/// 事件日志 (环形缓冲)
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<Event>,
+    /// 累计写入的条数 (含已被丢弃的)，用于在滚动时保持视图位置
+    pushed: u64,
+}
+
+impl EventLog {
+    /// 写入一条事件；`time` 为事件发生的时间 (回放时为录制时的时间)
+    pub fn push(&mut self, time: DateTime<Local>, kind: EventKind, device: &str, message: String) {
+        if self.events.len() >= MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(Event {
+            time,
+            kind,
+            device: device.to_string(),
+            message,
+        });
+        self.pushed += 1;
+    }
+
+    pub fn pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// 按时间倒序 (最新在前)
+    pub fn latest(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter().rev()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
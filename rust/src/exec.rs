@@ -0,0 +1,229 @@
+//! 外部命令数据源 (--exec)
+//!
+//! 每个采样间隔运行一次用户提供的命令 (Unix 上为 `sh -c`，Windows 上为 `cmd /C`)，
+//! 把标准输出解析为计数器，这样任何能从 shell 访问的主机都可以监控，无需安装 agent:
+//!
+//! ```text
+//! winload --exec 'ssh router cat /proc/net/dev'
+//! winload --exec 'kubectl exec pod -- cat /proc/net/dev'
+//! ```
+//!
+//! 支持两种输出格式，按内容自动识别:
+//!
+//! - Linux `/proc/net/dev`
+//! - JSON: `{"interfaces": [{"name": "eth0", "rx_bytes": 1, "tx_bytes": 2}]}`，
+//!   可选字段 `rx_packets`、`tx_packets`、`rx_errors`、`tx_errors`、`addrs` (字符串数组)、
+//!   `oper` (up / down ...)、`speed_mbps`
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::collector::{self, DeviceInfo, LinkState, Snapshot};
+use crate::source::{self, Feed, FeedState, Named};
+
+/// 单次运行的超时，超过后结束子进程并报错
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 启动所有 `--exec [NAME=]COMMAND`，等待第一次运行完成，返回 (前缀, 来源)
+///
+/// 命令没有可用的名称，多个来源时需要用 NAME 指定前缀，见 source::resolve。
+pub fn start_all(commands: &[String], interval_ms: u64, start: Instant, multi: bool) -> Result<Vec<Named>, String> {
+    let feeds = commands
+        .iter()
+        .map(|c| {
+            let (name, command) = source::parse_target(c);
+            let command_owned = command.to_string();
+            let feed = Feed::spawn(command, move |state| poll(&command_owned, interval_ms, start, state));
+            (name, feed)
+        })
+        .collect();
+    source::resolve(feeds, COMMAND_TIMEOUT + Duration::from_secs(1), multi)
+}
+
+/// 按间隔运行命令，直到出错
+fn poll(command: &str, interval_ms: u64, start: Instant, state: &Mutex<FeedState>) -> Result<(), String> {
+    let interval = Duration::from_millis(interval_ms);
+    loop {
+        let began = Instant::now();
+        let output = run(command)?;
+        let elapsed_secs = start.elapsed().as_secs_f64();
+        let mut ifaces = parse(&output)?;
+        if ifaces.is_empty() {
+            return Err("no interfaces in command output".to_string());
+        }
+        ifaces.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+
+        {
+            let Ok(mut s) = state.lock() else {
+                return Ok(());
+            };
+            s.snapshots = ifaces
+                .iter()
+                .map(|i| {
+                    let [bytes_recv, bytes_sent, packets_recv, packets_sent, errors_recv, errors_sent] = i.counters;
+                    let snap = Snapshot {
+                        elapsed_secs,
+                        bytes_recv,
+                        bytes_sent,
+                        packets_recv,
+                        packets_sent,
+                        errors_recv,
+                        errors_sent,
+                    };
+                    (i.info.name.clone(), snap)
+                })
+                .collect();
+            s.links = ifaces
+                .iter()
+                .filter_map(|i| i.link.clone().map(|l| (i.info.name.clone(), l)))
+                .collect();
+            s.devices = ifaces.into_iter().map(|i| i.info).collect();
+            s.mark_connected();
+        }
+        thread::sleep(interval.saturating_sub(began.elapsed()));
+    }
+}
+
+/// 通过 shell 运行命令并返回标准输出；非零退出时以标准错误的第一行作为错误信息
+fn run(command: &str) -> Result<String, String> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run command: {e}"))?;
+
+    // 在线程中读取输出，避免管道写满时子进程阻塞
+    let mut stdout = child.stdout.take().ok_or("no stdout")?;
+    let mut stderr = child.stderr.take().ok_or("no stderr")?;
+    let out_reader = thread::spawn(move || {
+        let mut buf = String::new();
+        stdout.read_to_string(&mut buf).map(|_| buf)
+    });
+    let err_reader = thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf);
+        buf
+    });
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("command timed out after {}s", COMMAND_TIMEOUT.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let stdout = out_reader
+        .join()
+        .map_err(|_| "cannot read command output".to_string())?
+        .map_err(|e| format!("cannot read command output: {e}"))?;
+    let stderr = err_reader.join().unwrap_or_default();
+    if !status.success() {
+        let detail = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+        return Err(match status.code() {
+            Some(code) => format!("command exited with status {code}: {detail}"),
+            None => format!("command was killed: {detail}"),
+        });
+    }
+    Ok(stdout)
+}
+
+// ─── 输出解析 ──────────────────────────────────────────────
+
+/// 从输出中解析出的一个接口
+struct Iface {
+    info: DeviceInfo,
+    /// rx / tx 字节、包、错误
+    counters: [u64; 6],
+    link: Option<LinkState>,
+}
+
+fn parse(output: &str) -> Result<Vec<Iface>, String> {
+    if output.trim_start().starts_with('{') {
+        parse_json(output)
+    } else {
+        parse_proc_net_dev(output)
+    }
+}
+
+fn parse_proc_net_dev(output: &str) -> Result<Vec<Iface>, String> {
+    let ifaces: Vec<Iface> = collector::parse_proc_net_dev(output)
+        .into_iter()
+        .map(|(name, counters)| Iface {
+            info: DeviceInfo { name, addrs: Vec::new() },
+            counters,
+            link: None,
+        })
+        .collect();
+    if ifaces.is_empty() {
+        return Err("output is neither /proc/net/dev nor JSON".to_string());
+    }
+    Ok(ifaces)
+}
+
+fn parse_json(output: &str) -> Result<Vec<Iface>, String> {
+    let doc: Value = serde_json::from_str(output).map_err(|e| format!("invalid JSON output: {e}"))?;
+    let list = doc
+        .get("interfaces")
+        .and_then(Value::as_array)
+        .ok_or("JSON output has no \"interfaces\" array")?;
+    let mut names = HashSet::new();
+    list.iter()
+        .map(|entry| {
+            let name = entry
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("interface without \"name\"")?;
+            if !names.insert(name) {
+                return Err(format!("duplicate interface \"{name}\""));
+            }
+            let field = |key: &str| entry.get(key).and_then(Value::as_u64);
+            let required = |key: &str| field(key).ok_or_else(|| format!("interface \"{name}\" has no \"{key}\""));
+            Ok(Iface {
+                info: DeviceInfo {
+                    name: name.to_string(),
+                    addrs: entry
+                        .get("addrs")
+                        .and_then(Value::as_array)
+                        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                        .unwrap_or_default(),
+                },
+                counters: [
+                    required("rx_bytes")?,
+                    required("tx_bytes")?,
+                    field("rx_packets").unwrap_or(0),
+                    field("tx_packets").unwrap_or(0),
+                    field("rx_errors").unwrap_or(0),
+                    field("tx_errors").unwrap_or(0),
+                ],
+                link: entry.get("oper").and_then(Value::as_str).map(|oper| LinkState {
+                    oper: oper.to_lowercase(),
+                    speed_mbps: field("speed_mbps"),
+                }),
+            })
+        })
+        .collect()
+}
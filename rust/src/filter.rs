@@ -0,0 +1,586 @@
+//! 抓包过滤表达式 (--filter)，tcpdump / pcap-filter 语法的子集
+//!
+//! 支持的原语:
+//!
+//! - 协议: `ip` `ip6` `arp` `tcp` `udp` `icmp` `icmp6` `sctp`
+//! - `[src|dst|src or dst|src and dst] host ADDR | net CIDR | port PORT | portrange A-B`，
+//!   可加协议前缀，如 `tcp dst port 443`、`ip6 net fd00::/8`；主机名在解析时查询一次
+//! - `less N` / `greater N` (按包长)、`inbound` / `outbound`
+//!
+//! 用 `not` / `!`、`and` / `&&`、`or` / `||` 和括号组合。与 tcpdump 一样，`and` 和 `or`
+//! 优先级相同，从左到右结合；省略限定词时沿用前一个原语的，如 `port 80 or 443`。
+//!
+//! 表达式由 winload 按解码后的包头求值，不编译成内核 BPF 程序。
+
+use std::fmt;
+use std::net::{IpAddr, ToSocketAddrs};
+
+use crate::decode::{Network, Packet};
+
+/// IP 协议号
+const IPPROTO_ICMP: u8 = 1;
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+const IPPROTO_ICMPV6: u8 = 58;
+const IPPROTO_SCTP: u8 = 132;
+
+/// 常用服务名 → 端口
+const SERVICES: &[(&str, u16)] = &[
+    ("ftp-data", 20),
+    ("ftp", 21),
+    ("ssh", 22),
+    ("telnet", 23),
+    ("smtp", 25),
+    ("domain", 53),
+    ("dns", 53),
+    ("http", 80),
+    ("pop3", 110),
+    ("ntp", 123),
+    ("imap", 143),
+    ("snmp", 161),
+    ("ldap", 389),
+    ("https", 443),
+    ("smtps", 465),
+    ("submission", 587),
+    ("imaps", 993),
+    ("pop3s", 995),
+    ("mysql", 3306),
+    ("rdp", 3389),
+    ("postgresql", 5432),
+    ("redis", 6379),
+    ("http-alt", 8080),
+];
+
+/// 解析后的过滤表达式
+#[derive(Clone, Debug)]
+pub struct Filter {
+    text: String,
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text);
+        if tokens.is_empty() {
+            return Err("empty filter expression".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0, last: None };
+        let expr = parser.expr()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(format!("unexpected '{tok}' in filter '{text}'"));
+        }
+        Ok(Self {
+            text: text.trim().to_string(),
+            expr,
+        })
+    }
+
+    pub fn matches(&self, packet: &Packet) -> bool {
+        self.expr.matches(packet)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// ─── 表达式 ────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Proto {
+    Ip,
+    Ip6,
+    Arp,
+    Tcp,
+    Udp,
+    Icmp,
+    Icmp6,
+    Sctp,
+}
+
+impl Proto {
+    fn from_word(word: &str) -> Option<Self> {
+        Some(match word {
+            "ip" => Self::Ip,
+            "ip6" => Self::Ip6,
+            "arp" => Self::Arp,
+            "tcp" => Self::Tcp,
+            "udp" => Self::Udp,
+            "icmp" => Self::Icmp,
+            "icmp6" => Self::Icmp6,
+            "sctp" => Self::Sctp,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, p: &Packet) -> bool {
+        match self {
+            Self::Ip => p.network == Network::Ipv4,
+            Self::Ip6 => p.network == Network::Ipv6,
+            Self::Arp => p.network == Network::Arp,
+            Self::Tcp => p.protocol == Some(IPPROTO_TCP),
+            Self::Udp => p.protocol == Some(IPPROTO_UDP),
+            Self::Icmp => p.network == Network::Ipv4 && p.protocol == Some(IPPROTO_ICMP),
+            Self::Icmp6 => p.network == Network::Ipv6 && p.protocol == Some(IPPROTO_ICMPV6),
+            Self::Sctp => p.protocol == Some(IPPROTO_SCTP),
+        }
+    }
+
+    /// 是否是有端口的传输层协议
+    fn has_ports(self) -> bool {
+        matches!(self, Self::Tcp | Self::Udp | Self::Sctp)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Dir {
+    Src,
+    Dst,
+    SrcOrDst,
+    SrcAndDst,
+}
+
+impl Dir {
+    fn test<T>(self, src: Option<T>, dst: Option<T>, f: impl Fn(T) -> bool) -> bool {
+        match self {
+            Self::Src => src.is_some_and(&f),
+            Self::Dst => dst.is_some_and(&f),
+            Self::SrcOrDst => src.is_some_and(&f) || dst.is_some_and(&f),
+            Self::SrcAndDst => src.is_some_and(&f) && dst.is_some_and(&f),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Host,
+    Net,
+    Port,
+    PortRange,
+}
+
+impl Kind {
+    fn from_word(word: &str) -> Option<Self> {
+        Some(match word {
+            "host" => Self::Host,
+            "net" => Self::Net,
+            "port" => Self::Port,
+            "portrange" => Self::PortRange,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Proto(Proto),
+    Host(Option<Proto>, Dir, Vec<IpAddr>),
+    Net(Option<Proto>, Dir, IpAddr, u8),
+    Port(Option<Proto>, Dir, u16, u16),
+    Less(u32),
+    Greater(u32),
+    Inbound,
+    Outbound,
+}
+
+impl Expr {
+    fn matches(&self, p: &Packet) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(p) && b.matches(p),
+            Self::Or(a, b) => a.matches(p) || b.matches(p),
+            Self::Not(e) => !e.matches(p),
+            Self::Proto(proto) => proto.matches(p),
+            Self::Host(proto, dir, addrs) => {
+                proto.is_none_or(|pr| pr.matches(p)) && dir.test(p.src, p.dst, |a| addrs.contains(&a))
+            }
+            Self::Net(proto, dir, net, len) => {
+                proto.is_none_or(|pr| pr.matches(p)) && dir.test(p.src, p.dst, |a| in_net(a, *net, *len))
+            }
+            Self::Port(proto, dir, lo, hi) => {
+                let proto_ok = match proto {
+                    Some(pr) if pr.has_ports() => pr.matches(p),
+                    Some(pr) => pr.matches(p) && has_ports(p),
+                    None => has_ports(p),
+                };
+                proto_ok && dir.test(p.src_port, p.dst_port, |port| (*lo..=*hi).contains(&port))
+            }
+            Self::Less(n) => p.len <= *n,
+            Self::Greater(n) => p.len >= *n,
+            Self::Inbound => !p.outgoing,
+            Self::Outbound => p.outgoing,
+        }
+    }
+}
+
+fn has_ports(p: &Packet) -> bool {
+    matches!(p.protocol, Some(IPPROTO_TCP | IPPROTO_UDP | IPPROTO_SCTP))
+}
+
+/// 地址是否在 net/len 网段内 (地址族不同时为 false)
+fn in_net(addr: IpAddr, net: IpAddr, len: u8) -> bool {
+    match (addr, net) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => {
+            let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+            u32::from(a) & mask == u32::from(n) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(n)) => {
+            let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+            u128::from(a) & mask == u128::from(n) & mask
+        }
+        _ => false,
+    }
+}
+
+// ─── 解析 ──────────────────────────────────────────────────
+
+/// 按空白、括号和 `!` 切分
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' || c == '!' {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+    /// 前一个原语的限定词，省略限定词时沿用
+    last: Option<(Option<Proto>, Dir, Kind)>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn value(&mut self, after: &str) -> Result<String, String> {
+        self.next().ok_or_else(|| format!("missing value after '{after}'"))
+    }
+
+    /// expr := term { (and | or) term }
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            match self.peek() {
+                Some("and" | "&&") => {
+                    self.pos += 1;
+                    left = Expr::And(Box::new(left), Box::new(self.term()?));
+                }
+                Some("or" | "||") => {
+                    self.pos += 1;
+                    left = Expr::Or(Box::new(left), Box::new(self.term()?));
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    /// term := (not | !) term | '(' expr ')' | primitive
+    fn term(&mut self) -> Result<Expr, String> {
+        let tok = self.next().ok_or("unexpected end of filter expression")?;
+        match tok.as_str() {
+            "not" | "!" => Ok(Expr::Not(Box::new(self.term()?))),
+            "(" => {
+                let e = self.expr()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(e),
+                    _ => Err("missing ')' in filter expression".to_string()),
+                }
+            }
+            ")" | "and" | "&&" | "or" | "||" => Err(format!("unexpected '{tok}' in filter expression")),
+            _ => self.primitive(tok),
+        }
+    }
+
+    fn primitive(&mut self, first: String) -> Result<Expr, String> {
+        match first.as_str() {
+            "less" | "greater" => {
+                let n = self.value(&first)?;
+                let n: u32 = n.parse().map_err(|_| format!("bad length '{n}' after '{first}'"))?;
+                return Ok(if first == "less" { Expr::Less(n) } else { Expr::Greater(n) });
+            }
+            "inbound" => return Ok(Expr::Inbound),
+            "outbound" => return Ok(Expr::Outbound),
+            _ => {}
+        }
+
+        let mut tok = first;
+        let mut qualified = false;
+        let proto = Proto::from_word(&tok);
+        if let Some(p) = proto {
+            // 后面没有方向或类型时是单独的协议原语
+            match self.peek() {
+                Some("src" | "dst" | "host" | "net" | "port" | "portrange") => tok = self.value(&tok)?,
+                _ => return Ok(Expr::Proto(p)),
+            }
+            qualified = true;
+        }
+        let dir = match tok.as_str() {
+            "src" | "dst" => {
+                let mut dir = if tok == "src" { Dir::Src } else { Dir::Dst };
+                // src or dst / src and dst
+                if tok == "src" && self.tokens.get(self.pos + 1).map(String::as_str) == Some("dst") {
+                    let compound = match self.peek() {
+                        Some("or" | "||") => Some(Dir::SrcOrDst),
+                        Some("and" | "&&") => Some(Dir::SrcAndDst),
+                        _ => None,
+                    };
+                    if let Some(d) = compound {
+                        dir = d;
+                        self.pos += 2;
+                    }
+                }
+                tok = self.value(&tok)?;
+                qualified = true;
+                Some(dir)
+            }
+            _ => None,
+        };
+        let kind = Kind::from_word(&tok);
+        if kind.is_some() {
+            tok = self.value(&tok)?;
+        }
+
+        // 完全没有限定词时沿用前一个原语的限定词，否则缺省为 host
+        let (proto, dir, kind) = match (qualified || kind.is_some(), self.last) {
+            (false, Some(last)) => last,
+            _ => (proto, dir.unwrap_or(Dir::SrcOrDst), kind.unwrap_or(Kind::Host)),
+        };
+        self.last = Some((proto, dir, kind));
+        let value = tok;
+        match kind {
+            Kind::Host => Ok(Expr::Host(proto, dir, parse_host(&value)?)),
+            Kind::Net => {
+                let (net, len) = parse_net(&value)?;
+                Ok(Expr::Net(proto, dir, net, len))
+            }
+            Kind::Port => {
+                let port = parse_port(&value)?;
+                Ok(Expr::Port(proto, dir, port, port))
+            }
+            Kind::PortRange => {
+                let (a, b) = value
+                    .split_once('-')
+                    .ok_or_else(|| format!("bad port range '{value}', expected A-B"))?;
+                let (lo, hi) = (parse_port(a)?, parse_port(b)?);
+                Ok(Expr::Port(proto, dir, lo.min(hi), lo.max(hi)))
+            }
+        }
+    }
+}
+
+fn parse_host(value: &str) -> Result<Vec<IpAddr>, String> {
+    if let Ok(addr) = value.parse::<IpAddr>() {
+        return Ok(vec![addr]);
+    }
+    if value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("'{value}' is not a host (use 'port {value}' for a port)"));
+    }
+    let mut addrs: Vec<IpAddr> = (value, 0)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve host '{value}': {e}"))?
+        .map(|a| a.ip())
+        .collect();
+    addrs.dedup();
+    Ok(addrs)
+}
+
+/// `ADDR/LEN`、`ADDR` 或省略尾部的 IPv4 网段 (`10.1` 即 10.1.0.0/16)
+fn parse_net(value: &str) -> Result<(IpAddr, u8), String> {
+    let bad = || format!("bad network '{value}', expected ADDR/LEN");
+    if let Some((addr, len)) = value.split_once('/') {
+        let addr: IpAddr = addr.parse().map_err(|_| bad())?;
+        let len: u8 = len.parse().map_err(|_| bad())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if len > max {
+            return Err(bad());
+        }
+        return Ok((addr, len));
+    }
+    if let Ok(addr) = value.parse::<IpAddr>() {
+        return Ok((addr, if addr.is_ipv4() { 32 } else { 128 }));
+    }
+    let octets: Vec<u8> = value.split('.').map(|o| o.parse().ok()).collect::<Option<_>>().ok_or_else(bad)?;
+    if octets.is_empty() || octets.len() > 3 {
+        return Err(bad());
+    }
+    let mut full = [0u8; 4];
+    full[..octets.len()].copy_from_slice(&octets);
+    Ok((IpAddr::from(full), octets.len() as u8 * 8))
+}
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| SERVICES.iter().find(|(name, _)| *name == value).map(|&(_, port)| port))
+        .ok_or_else(|| format!("unknown port '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    /// 入站的 IP 包，地址写成 `ADDR:PORT`
+    fn packet(protocol: u8, src: &str, dst: &str) -> Packet {
+        let (src, dst): (SocketAddr, SocketAddr) = (src.parse().unwrap(), dst.parse().unwrap());
+        Packet {
+            len: 100,
+            outgoing: false,
+            network: if src.is_ipv4() { Network::Ipv4 } else { Network::Ipv6 },
+            src: Some(src.ip()),
+            dst: Some(dst.ip()),
+            protocol: Some(protocol),
+            src_port: Some(src.port()),
+            dst_port: Some(dst.port()),
+        }
+    }
+
+    fn tcp(src: &str, dst: &str) -> Packet {
+        packet(IPPROTO_TCP, src, dst)
+    }
+
+    fn udp(src: &str, dst: &str) -> Packet {
+        packet(IPPROTO_UDP, src, dst)
+    }
+
+    fn matches(filter: &str, p: &Packet) -> bool {
+        Filter::parse(filter).unwrap().matches(p)
+    }
+
+    #[test]
+    fn and_or_have_equal_precedence_left_to_right() {
+        let web = tcp("10.0.0.1:40000", "10.0.0.2:80");
+        let dns = udp("10.0.0.1:40000", "10.0.0.2:53");
+        // (tcp or udp) and port 53，而不是 tcp or (udp and port 53)
+        assert!(!matches("tcp or udp and port 53", &web));
+        assert!(matches("tcp or udp and port 53", &dns));
+        // (port 53 and udp) or tcp
+        assert!(matches("port 53 and udp or tcp", &web));
+        assert!(matches("port 53 && udp || tcp", &web));
+        // not 比 and / or 优先
+        assert!(matches("not tcp and port 53", &dns));
+        assert!(!matches("not tcp and port 53", &web));
+        assert!(!matches("!(tcp or udp)", &web));
+    }
+
+    #[test]
+    fn omitted_qualifiers_are_inherited() {
+        let https = tcp("10.0.0.1:40000", "10.0.0.2:443");
+        assert!(matches("port 80 or 443", &https));
+        assert!(!matches("port 80 or 22", &https));
+        // `tcp dst port` 整体沿用
+        assert!(matches("tcp dst port 80 or 443", &https));
+        assert!(!matches("tcp dst port 80 or 443", &udp("10.0.0.1:40000", "10.0.0.2:443")));
+        assert!(!matches("tcp dst port 80 or 443", &tcp("10.0.0.2:443", "10.0.0.1:40000")));
+        assert!(matches("host 10.0.0.9 or 10.0.0.2", &https));
+        // 带了限定词的原语不沿用: 这里的 40000 是端口，不是前一个原语的主机
+        assert!(matches("host 10.0.0.9 or port 40000", &https));
+        // 没有前一个原语时缺省为 host
+        assert!(matches("10.0.0.1", &https));
+    }
+
+    #[test]
+    fn src_or_dst_and_src_and_dst() {
+        let outbound = tcp("10.0.0.1:40000", "192.0.2.7:443");
+        let internal = tcp("10.0.0.1:40000", "10.9.9.9:443");
+        assert!(matches("src or dst host 192.0.2.7", &outbound));
+        assert!(matches("src || dst host 10.0.0.1", &outbound));
+        assert!(!matches("src and dst net 10.0.0.0/8", &outbound));
+        assert!(matches("src and dst net 10.0.0.0/8", &internal));
+        assert!(matches("src net 10.0.0.0/8 and dst host 192.0.2.7", &outbound));
+        assert!(!matches("dst host 10.0.0.1", &outbound));
+        assert!(matches("tcp src port 40000", &outbound));
+    }
+
+    #[test]
+    fn partial_and_ipv6_networks() {
+        let p = |dst: &str| tcp("192.0.2.1:40000", dst);
+        assert!(matches("dst net 10.1", &p("10.1.2.3:80")));
+        assert!(!matches("dst net 10.1", &p("10.2.0.1:80")));
+        assert!(matches("dst net 192.168.1", &p("192.168.1.200:80")));
+        assert!(!matches("dst net 192.168.1", &p("192.168.2.1:80")));
+        assert!(matches("net 10", &p("10.200.0.1:80")));
+        assert!(matches("net 0.0.0.0/0", &p("203.0.113.5:80")));
+        // 地址族不同时不匹配
+        let v6 = tcp("[fd00::1]:40000", "[2001:db8::1]:443");
+        assert!(matches("ip6 net fd00::/8", &v6));
+        assert!(!matches("net 10", &v6));
+        assert!(!matches("ip net 0.0.0.0/0", &v6));
+    }
+
+    #[test]
+    fn portrange() {
+        let x11 = |port: u16| tcp("10.0.0.1:40000", &format!("10.0.0.2:{port}"));
+        assert!(matches("portrange 6000-6010", &x11(6000)));
+        assert!(matches("portrange 6000-6010", &x11(6010)));
+        assert!(!matches("portrange 6000-6010", &x11(6011)));
+        // 上下限颠倒、服务名都可以
+        assert!(matches("portrange 6010-6000", &x11(6005)));
+        assert!(matches("dst portrange ssh-http", &x11(22)));
+        assert!(!matches("udp portrange 1-65535", &x11(80)));
+        // 没有端口的协议
+        let ping = Packet {
+            protocol: Some(IPPROTO_ICMP),
+            src_port: None,
+            dst_port: None,
+            ..x11(0)
+        };
+        assert!(!matches("portrange 0-65535", &ping));
+        assert!(matches("icmp", &ping));
+    }
+
+    #[test]
+    fn less_and_greater_are_inclusive() {
+        let p = |len: u32| Packet { len, ..tcp("10.0.0.1:1", "10.0.0.2:2") };
+        assert!(matches("less 100", &p(100)));
+        assert!(!matches("less 100", &p(101)));
+        assert!(matches("greater 100", &p(100)));
+        assert!(!matches("greater 100", &p(99)));
+        assert!(matches("greater 64 and less 1500", &p(576)));
+    }
+
+    #[test]
+    fn error_messages() {
+        let err = |filter: &str| Filter::parse(filter).unwrap_err();
+        assert_eq!(err("  "), "empty filter expression");
+        assert_eq!(err("port"), "missing value after 'port'");
+        assert_eq!(err("port foo"), "unknown port 'foo'");
+        assert_eq!(err("host 80"), "'80' is not a host (use 'port 80' for a port)");
+        assert_eq!(err("net 10.0.0.0/33"), "bad network '10.0.0.0/33', expected ADDR/LEN");
+        assert_eq!(err("net 1.2.3.4.5"), "bad network '1.2.3.4.5', expected ADDR/LEN");
+        assert_eq!(err("portrange 10"), "bad port range '10', expected A-B");
+        assert_eq!(err("less x"), "bad length 'x' after 'less'");
+        assert_eq!(err("(tcp"), "missing ')' in filter expression");
+        assert_eq!(err("tcp )"), "unexpected ')' in filter 'tcp )'");
+        assert_eq!(err("tcp and"), "unexpected end of filter expression");
+        assert_eq!(err("and tcp"), "unexpected 'and' in filter expression");
+        assert_eq!(Filter::parse("  tcp port 80 ").unwrap().to_string(), "tcp port 80");
+    }
+}
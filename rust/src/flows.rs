@@ -0,0 +1,194 @@
+//! 连接流量表 (iftop 风格)
+//!
+//! 抓包线程按 (协议, 本机地址:端口, 对端地址:端口) 累计每个连接的收发字节，App 每个采样间隔
+//! 取出增量，计算 2 秒 / 10 秒 / 40 秒的平均速率和累计字节，回答 "是谁在占带宽"。
+//! 40 秒内没有流量的连接从表中移除。
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::decode::{Network, Packet};
+
+/// 平均速率的时间窗口 (秒)，同 iftop
+pub const WINDOWS: [f64; 3] = [2.0, 10.0, 40.0];
+
+/// 一个连接，按本机的视角区分两端
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    pub network: Network,
+    /// IP 协议号；ARP 没有
+    pub protocol: Option<u8>,
+    pub local: Endpoint,
+    pub remote: Endpoint,
+}
+
+/// 地址和端口 (没有端口的协议为 None)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Endpoint {
+    pub addr: IpAddr,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.addr, self.port) {
+            (IpAddr::V6(addr), Some(port)) => write!(f, "[{addr}]:{port}"),
+            (addr, Some(port)) => write!(f, "{addr}:{port}"),
+            (addr, None) => write!(f, "{addr}"),
+        }
+    }
+}
+
+impl FlowKey {
+    /// 包所属的连接；没有地址的包 (非 IP / ARP、被截断) 返回 None
+    pub fn of(packet: &Packet) -> Option<Self> {
+        let src = Endpoint {
+            addr: packet.src?,
+            port: packet.src_port,
+        };
+        let dst = Endpoint {
+            addr: packet.dst?,
+            port: packet.dst_port,
+        };
+        let (local, remote) = if packet.outgoing { (src, dst) } else { (dst, src) };
+        Some(Self {
+            network: packet.network,
+            protocol: packet.protocol,
+            local,
+            remote,
+        })
+    }
+
+    /// 协议名称，用于显示
+    pub fn protocol_name(&self) -> String {
+        match (self.network, self.protocol) {
+            (Network::Arp, _) => "ARP".to_string(),
+            (_, Some(6)) => "TCP".to_string(),
+            (_, Some(17)) => "UDP".to_string(),
+            (Network::Ipv4, Some(1)) => "ICMP".to_string(),
+            (Network::Ipv6, Some(58)) => "ICMPv6".to_string(),
+            (_, Some(132)) => "SCTP".to_string(),
+            (_, Some(p)) => format!("IP/{p}"),
+            (_, None) => "?".to_string(),
+        }
+    }
+}
+
+/// 抓包线程交给 App 的增量: 连接 → [收, 发] 字节
+pub type FlowDeltas = HashMap<FlowKey, [u64; 2]>;
+
+/// 表中的一个连接
+pub struct Flow {
+    pub key: FlowKey,
+    /// 累计 [收, 发] 字节
+    pub total: [u64; 2],
+    /// 各窗口的平均速率 [收, 发] (字节/秒)，顺序同 WINDOWS
+    pub rates: [[f64; 2]; 3],
+    /// 最近 40 秒的 (时间, [收, 发] 增量)
+    history: VecDeque<(f64, [u64; 2])>,
+}
+
+/// 排序依据
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    Avg2s,
+    Avg10s,
+    Avg40s,
+    Total,
+}
+
+impl SortBy {
+    /// 依次切换
+    pub fn next(self) -> Self {
+        match self {
+            Self::Avg2s => Self::Avg10s,
+            Self::Avg10s => Self::Avg40s,
+            Self::Avg40s => Self::Total,
+            Self::Total => Self::Avg2s,
+        }
+    }
+}
+
+/// 所有活跃连接
+pub struct FlowTable {
+    flows: HashMap<FlowKey, Flow>,
+    pub sort: SortBy,
+}
+
+impl FlowTable {
+    pub fn new() -> Self {
+        Self {
+            flows: HashMap::new(),
+            sort: SortBy::Avg2s,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+
+    /// 清空所有连接 (回放往回跳转时)，保留排序依据
+    pub fn clear(&mut self) {
+        self.flows.clear();
+    }
+
+    /// 加入一个间隔的增量并重新计算平均速率；`now` 为自启动以来的秒数
+    ///
+    /// 启动不到一个窗口时按已经过的时间平均，避免刚开始时速率偏低。
+    pub fn update(&mut self, deltas: FlowDeltas, now: f64) {
+        for (key, delta) in deltas {
+            let flow = self.flows.entry(key.clone()).or_insert_with(|| Flow {
+                key,
+                total: [0; 2],
+                rates: [[0.0; 2]; 3],
+                history: VecDeque::new(),
+            });
+            flow.total[0] += delta[0];
+            flow.total[1] += delta[1];
+            flow.history.push_back((now, delta));
+        }
+
+        let longest = WINDOWS[WINDOWS.len() - 1];
+        self.flows.retain(|_, flow| {
+            while flow.history.front().is_some_and(|&(t, _)| t <= now - longest) {
+                flow.history.pop_front();
+            }
+            !flow.history.is_empty()
+        });
+
+        for flow in self.flows.values_mut() {
+            for (w, &window) in WINDOWS.iter().enumerate() {
+                let mut sum = [0u64; 2];
+                for &(_, delta) in flow.history.iter().filter(|&&(t, _)| t > now - window) {
+                    sum[0] += delta[0];
+                    sum[1] += delta[1];
+                }
+                let secs = window.min(now).max(f64::EPSILON);
+                flow.rates[w] = [sum[0] as f64 / secs, sum[1] as f64 / secs];
+            }
+        }
+    }
+
+    /// 按当前排序依据 (收发之和) 从大到小排列
+    pub fn sorted(&self) -> Vec<&Flow> {
+        let key = |flow: &Flow| match self.sort {
+            SortBy::Avg2s => flow.rates[0][0] + flow.rates[0][1],
+            SortBy::Avg10s => flow.rates[1][0] + flow.rates[1][1],
+            SortBy::Avg40s => flow.rates[2][0] + flow.rates[2][1],
+            SortBy::Total => (flow.total[0] + flow.total[1]) as f64,
+        };
+        let mut flows: Vec<&Flow> = self.flows.values().collect();
+        // 速率相同时按地址排，避免每次刷新顺序跳动
+        flows.sort_by(|a, b| {
+            key(b)
+                .total_cmp(&key(a))
+                .then_with(|| (a.key.local, a.key.remote).cmp(&(b.key.local, b.key.remote)))
+        });
+        flows
+    }
+}
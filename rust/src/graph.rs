@@ -2,6 +2,8 @@
 //! 仿 nload 的柱状图效果
 //! - ASCII 模式 (默认): 使用 4 级字符: ' ', '.', '|', '#'
 //! - Unicode 模式 (-U): 使用 Unicode block 字符: ' ', '·', '░', '▓', '█'
+//! - 用户标记 (m 键) 画成贯穿空白处的竖线: ASCII 为 ':'，Unicode 为 '│'
+//! - 堆叠图 (协议分布): 字符同上，每格另外给出它属于哪一层，由调用方着色
 
 use std::collections::VecDeque;
 
@@ -24,6 +26,7 @@ pub fn next_power_of_2_scaled(value: f64) -> f64 {
 /// - `height`:  图形高度（字符行数）
 /// - `max_value`: 缩放上限，0.0 表示自动
 /// - `unicode`:  true 使用 Unicode block 字符，false 使用 ASCII 字符
+/// - `markers`:  标记所在的样本下标 (0 = 最新，与 history 相同)，在空白处画竖线
 ///
 /// 返回 `height` 行的字符串列表，每行 `width` 个字符
 pub fn render_graph(
@@ -32,6 +35,7 @@ pub fn render_graph(
     height: usize,
     max_value: f64,
     unicode: bool,
+    markers: &[usize],
 ) -> Vec<String> {
     if width == 0 || height == 0 {
         return vec![];
@@ -48,21 +52,8 @@ pub fn render_graph(
     // 补齐不足 width 的部分
     values.resize(width, 0.0);
 
-    // 自动缩放
-    let max_val = if max_value <= 0.0 {
-        let peak = values.iter().cloned().fold(0.0_f64, f64::max);
-        next_power_of_2_scaled(peak)
-    } else {
-        max_value
-    };
-    let max_val = if max_val <= 0.0 { 2048.0 } else { max_val };
-
-    // 字符集: (full, high, low, dot)
-    let (ch_full, ch_high, ch_low, ch_dot) = if unicode {
-        ('█', '▓', '░', '·')
-    } else {
-        ('#', '|', '.', '.')
-    };
+    let max_val = scale(&values, max_value);
+    let ch_marker = marker_char(unicode);
 
     // 逐行渲染 (第 0 行 = 最顶部)
     let mut lines = Vec::with_capacity(height);
@@ -77,18 +68,9 @@ pub fn render_graph(
             let traffic_per_line = max_val / height as f64;
 
             if value <= lower_limit {
-                chars.push(' ');
+                chars.push(if markers.contains(&val_idx) { ch_marker } else { ' ' });
             } else {
-                let rest = value - lower_limit;
-                if rest >= traffic_per_line {
-                    chars.push(ch_full);
-                } else if rest >= traffic_per_line * 0.7 {
-                    chars.push(ch_high);
-                } else if rest >= traffic_per_line * 0.3 {
-                    chars.push(ch_low);
-                } else {
-                    chars.push(ch_dot);
-                }
+                chars.push(cell_char(value - lower_limit, traffic_per_line, unicode));
             }
         }
         lines.push(chars);
@@ -96,6 +78,103 @@ pub fn render_graph(
     lines
 }
 
+/// 渲染堆叠柱状图
+///
+/// - `history`: 每个样本各层的值 (front = 最新)，第 0 层在最下面
+/// - 其余参数同 `render_graph`，按各层之和缩放
+///
+/// 返回 `height` 行、每行 `width` 格的 (字符, 层)；空白格为 None。
+/// 一格的层取该格已填充部分的中点所在的层，字符的选取同 `render_graph`。
+pub fn render_stacked_graph(
+    history: &VecDeque<Vec<f64>>,
+    width: usize,
+    height: usize,
+    max_value: f64,
+    unicode: bool,
+) -> Vec<Vec<Option<(char, usize)>>> {
+    if width == 0 || height == 0 {
+        return vec![];
+    }
+
+    // 每列各层的累计高度 (第 i 项为第 0..=i 层之和)
+    let mut columns: Vec<Vec<f64>> = history
+        .iter()
+        .take(width)
+        .map(|layers| {
+            layers
+                .iter()
+                .scan(0.0, |sum, v| {
+                    *sum += v.max(0.0);
+                    Some(*sum)
+                })
+                .collect()
+        })
+        .collect();
+    columns.resize(width, Vec::new());
+
+    let totals: Vec<f64> = columns.iter().map(|c| c.last().copied().unwrap_or(0.0)).collect();
+    let max_val = scale(&totals, max_value);
+
+    let mut lines = Vec::with_capacity(height);
+    for row in 0..height {
+        let mut cells = Vec::with_capacity(width);
+        for col in 0..width {
+            // columns[0] 是最新值，显示在最右边
+            let stack = &columns[width - 1 - col];
+            let value = stack.last().copied().unwrap_or(0.0);
+
+            let lower_limit = max_val * (height - row - 1) as f64 / height as f64;
+            let traffic_per_line = max_val / height as f64;
+
+            if value <= lower_limit {
+                cells.push(None);
+                continue;
+            }
+            let rest = value - lower_limit;
+            let middle = lower_limit + rest.min(traffic_per_line) / 2.0;
+            let layer = stack.iter().position(|&top| top > middle).unwrap_or(stack.len() - 1);
+            cells.push(Some((cell_char(rest, traffic_per_line, unicode), layer)));
+        }
+        lines.push(cells);
+    }
+    lines
+}
+
+/// 缩放上限: `max_value` 为 0 时按峰值自动取整到 2 的幂
+fn scale(values: &[f64], max_value: f64) -> f64 {
+    let max_val = if max_value <= 0.0 {
+        let peak = values.iter().cloned().fold(0.0_f64, f64::max);
+        next_power_of_2_scaled(peak)
+    } else {
+        max_value
+    };
+    if max_val <= 0.0 { 2048.0 } else { max_val }
+}
+
+/// 一格的字符，`rest` 为超出该格下沿的值，`traffic_per_line` 为一格代表的值
+fn cell_char(rest: f64, traffic_per_line: f64, unicode: bool) -> char {
+    // 字符集: (full, high, low, dot)
+    let (ch_full, ch_high, ch_low, ch_dot) = if unicode {
+        ('█', '▓', '░', '·')
+    } else {
+        ('#', '|', '.', '.')
+    };
+    if rest >= traffic_per_line {
+        ch_full
+    } else if rest >= traffic_per_line * 0.7 {
+        ch_high
+    } else if rest >= traffic_per_line * 0.3 {
+        ch_low
+    } else {
+        ch_dot
+    }
+}
+
+/// 标记竖线使用的字符
+pub fn marker_char(unicode: bool) -> char {
+    if unicode { '│' } else { ':' }
+}
+
 /// 返回带单位选择的图形缩放标签
 pub fn get_graph_scale_label_unit(max_value: f64, unit: crate::Unit) -> String {
     use crate::stats::format_speed_unit;
@@ -0,0 +1,134 @@
+//! 网卡组
+//!
+//! 把多块网卡合并成一个虚拟设备 (如 `uplinks=wwan0+wwan1`)，
+//! 组的计数器累计各成员计数器的增量，可以像普通网卡一样查看、设置配额。
+//! 成员出现 / 消失 (如插拔 LTE 模块) 不会让组的计数器跳变或回退。
+//! 成员可以含 `*` 通配符，多主机模式下可以跨主机分组 (如 `edge=edge*/eth0`)。
+
+use std::collections::HashMap;
+
+use crate::collector::Snapshot;
+use crate::db::counter_delta;
+
+/// 网卡组定义
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<String>,
+    /// 组的累计计数器 (顺序同 Snapshot: rx / tx 字节、包、错误)，只增不减
+    totals: Option<[u64; 6]>,
+    /// 各成员上次的计数器；成员消失后保留，重新出现时按增量接上
+    last: HashMap<String, [u64; 6]>,
+}
+
+/// 解析组定义: `NAME=DEV1+DEV2+...` (成员为完整网卡名或含 `*` 的模式)
+pub fn parse_group(s: &str) -> Result<Group, String> {
+    let (name, members) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=DEV1+DEV2, got: {s}"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("empty group name".to_string());
+    }
+    let members: Vec<String> = members
+        .split('+')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    if members.is_empty() {
+        return Err(format!("group '{name}' has no members"));
+    }
+    Ok(Group {
+        name: name.to_string(),
+        members,
+        totals: None,
+        last: HashMap::new(),
+    })
+}
+
+impl Group {
+    /// 网卡是否属于该组
+    pub fn contains(&self, name: &str) -> bool {
+        self.members.iter().any(|m| matches(m, name))
+    }
+
+    /// 累计成员的增量，得到组的快照；没有任何成员在线时返回 None
+    ///
+    /// 第一次调用时组的计数器为在线成员之和；之后新出现的成员从出现时开始计，
+    /// 不把它开机以来的字节数一次性算进来，成员计数器回退时按 counter_delta 处理。
+    pub fn aggregate(&mut self, snapshots: &HashMap<String, Snapshot>) -> Option<Snapshot> {
+        let mut found = false;
+        let mut elapsed_secs = 0.0_f64;
+        let mut sum = [0u64; 6];
+        let mut delta = [0u64; 6];
+        let members: Vec<(&String, &Snapshot)> = snapshots.iter().filter(|(name, _)| self.contains(name)).collect();
+        for (name, snap) in members {
+            found = true;
+            elapsed_secs = elapsed_secs.max(snap.elapsed_secs);
+            let current = [
+                snap.bytes_recv,
+                snap.bytes_sent,
+                snap.packets_recv,
+                snap.packets_sent,
+                snap.errors_recv,
+                snap.errors_sent,
+            ];
+            let last = self.last.insert(name.clone(), current);
+            for i in 0..6 {
+                sum[i] = sum[i].saturating_add(current[i]);
+                if let Some(last) = last {
+                    delta[i] = delta[i].saturating_add(counter_delta(last[i], current[i]));
+                }
+            }
+        }
+        if !found {
+            return None;
+        }
+
+        let totals = match self.totals {
+            Some(mut totals) => {
+                for i in 0..6 {
+                    totals[i] = totals[i].saturating_add(delta[i]);
+                }
+                totals
+            }
+            None => sum,
+        };
+        self.totals = Some(totals);
+        let [bytes_recv, bytes_sent, packets_recv, packets_sent, errors_recv, errors_sent] = totals;
+        Some(Snapshot {
+            bytes_recv,
+            bytes_sent,
+            packets_recv,
+            packets_sent,
+            errors_recv,
+            errors_sent,
+            elapsed_secs,
+        })
+    }
+
+    /// 清空累计状态 (回放往回跳转时使用)
+    pub fn reset(&mut self) {
+        self.totals = None;
+        self.last.clear();
+    }
+}
+
+/// 成员模式匹配: `*` 匹配任意长度 (可为空) 的字符，其余字符须完全相同
+fn matches(pattern: &str, name: &str) -> bool {
+    let Some((head, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(mut remaining) = name.strip_prefix(head) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let tail = parts.pop().unwrap_or_default();
+    for part in parts {
+        match remaining.find(part) {
+            Some(i) => remaining = &remaining[i + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= tail.len() && remaining.ends_with(tail)
+}
@@ -0,0 +1,383 @@
+//! 极简 HTTP/1.1 客户端和服务端
+//!
+//! 客户端只用于 webhook 推送、抓取 node_exporter 等少量请求，服务端只用于 /metrics 这类只读端点，
+//! 避免为此引入完整的 HTTP 库。
+//! 客户端的 https:// 请求交给系统的 curl 发送 (Windows 10 起自带)，不自己实现 TLS。
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// 服务端读取请求头的超时和大小上限
+const SERVER_READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_HEAD: usize = 8192;
+
+/// 解析后的 http:// 或 https:// URL
+#[derive(Clone, Debug)]
+pub struct Url {
+    /// https:// (通过 curl 发送)
+    pub tls: bool,
+    pub host: String,
+    pub port: u16,
+    /// 含查询字符串的路径，至少为 "/"
+    pub path: String,
+}
+
+impl Url {
+    pub fn scheme(&self) -> &'static str {
+        if self.tls {
+            "https"
+        } else {
+            "http"
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "{}://[{}]:{}{}", self.scheme(), self.host, self.port, self.path)
+        } else {
+            write!(f, "{}://{}:{}{}", self.scheme(), self.host, self.port, self.path)
+        }
+    }
+}
+
+/// 解析 `http[s]://host[:port][/path]`
+pub fn parse_url(url: &str) -> Result<Url, String> {
+    let (tls, rest) = match (url.strip_prefix("http://"), url.strip_prefix("https://")) {
+        (Some(rest), _) => (false, rest),
+        (_, Some(rest)) => (true, rest),
+        _ => return Err(format!("only http:// and https:// URLs are supported, got: {url}")),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(format!("missing host in URL: {url}"));
+    }
+    // [v6addr]:port 或 host:port
+    let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+        let (h, p) = v6.split_once(']').ok_or_else(|| format!("bad IPv6 host in URL: {url}"))?;
+        (h.to_string(), p.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((h, p)) => (h.to_string(), Some(p)),
+            None => (authority.to_string(), None),
+        }
+    };
+    let port = match port {
+        Some(p) => p.parse().map_err(|e| format!("bad port in URL {url}: {e}"))?,
+        None if tls => 443,
+        None => 80,
+    };
+    Ok(Url {
+        tls,
+        host,
+        port,
+        path: path.to_string(),
+    })
+}
+
+/// HTTP 响应
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// 发送一个请求并读取完整响应 (Connection: close)
+pub fn request(
+    method: &str,
+    url: &Url,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    timeout: Duration,
+) -> Result<Response, String> {
+    if url.tls {
+        return curl(method, url, headers, body, timeout);
+    }
+    let addr = (url.host.as_str(), url.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve {}: {e}", url.host))?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {}", url.host))?;
+    let mut stream =
+        TcpStream::connect_timeout(&addr, timeout).map_err(|e| format!("connect {addr}: {e}"))?;
+    stream.set_read_timeout(Some(timeout)).ok();
+    stream.set_write_timeout(Some(timeout)).ok();
+
+    let host_header = if url.host.contains(':') {
+        format!("[{}]:{}", url.host, url.port)
+    } else {
+        format!("{}:{}", url.host, url.port)
+    };
+    let mut req = format!(
+        "{method} {} HTTP/1.1\r\nHost: {host_header}\r\nUser-Agent: winload/{}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        url.path,
+        env!("CARGO_PKG_VERSION"),
+        body.len(),
+    );
+    for (k, v) in headers {
+        req.push_str(&format!("{k}: {v}\r\n"));
+    }
+    req.push_str("\r\n");
+
+    stream
+        .write_all(req.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| format!("send to {addr}: {e}"))?;
+
+    let mut raw = Vec::new();
+    stream
+        .read_to_end(&mut raw)
+        .map_err(|e| format!("read from {addr}: {e}"))?;
+
+    let head_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| format!("malformed HTTP response from {addr}"))?;
+    let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("malformed HTTP status line from {addr}"))?;
+
+    let mut body = raw[head_end + 4..].to_vec();
+    let chunked = head
+        .lines()
+        .any(|l| l.to_ascii_lowercase().starts_with("transfer-encoding:") && l.to_ascii_lowercase().contains("chunked"));
+    if chunked {
+        body = dechunk(&body);
+    }
+    Ok(Response { status, body })
+}
+
+/// 用 curl 发送 https 请求
+///
+/// URL 和请求头里可能有凭据 (如 INFLUX_TOKEN)，写进只有本用户可读的临时配置文件，
+/// 不放在命令行参数里被其他用户从进程列表看到；请求体从 stdin 传入。
+fn curl(method: &str, url: &Url, headers: &[(&str, &str)], body: &[u8], timeout: Duration) -> Result<Response, String> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace(['\r', '\n'], " "));
+    let mut config = format!(
+        "url = {}\nrequest = {}\nuser-agent = {}\ngloboff\nsilent\nshow-error\nmax-time = {}\nwrite-out = \"\\n%{{http_code}}\"\n",
+        quote(&url.to_string()),
+        quote(method),
+        quote(&format!("winload/{}", env!("CARGO_PKG_VERSION"))),
+        timeout.as_secs_f64(),
+    );
+    // 不等 100 Continue；调用方没给 Content-Type 时也不要 curl 默认的表单类型
+    config.push_str("header = \"Expect:\"\n");
+    if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("content-type")) {
+        config.push_str("header = \"Content-Type:\"\n");
+    }
+    for (k, v) in headers {
+        config.push_str(&format!("header = {}\n", quote(&format!("{k}: {v}"))));
+    }
+    if method != "GET" {
+        config.push_str("data-binary = \"@-\"\n");
+    }
+    let config = TempFile::create(config.as_bytes())?;
+
+    let mut child = Command::new("curl")
+        .arg("--config")
+        .arg(&config.0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("https:// needs curl in PATH: {e}"))?;
+    // 另起线程写请求体，避免与读取响应互相等待
+    let writer = child.stdin.take().map(|mut stdin| {
+        let body = body.to_vec();
+        thread::spawn(move || {
+            let _ = stdin.write_all(&body);
+        })
+    });
+    let output = child.wait_with_output().map_err(|e| format!("curl: {e}"))?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if err.is_empty() { format!("curl exited with {}", output.status) } else { err });
+    }
+
+    // 输出为 响应体 + "\n" + 状态码
+    let mut out = output.stdout;
+    let split = out.iter().rposition(|&b| b == b'\n').ok_or("malformed curl output")?;
+    let status = std::str::from_utf8(&out[split + 1..])
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .ok_or("malformed curl output")?;
+    out.truncate(split);
+    Ok(Response { status, body: out })
+}
+
+/// 只有本用户可读写的临时文件，离开作用域时删除
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(content: &[u8]) -> Result<Self, String> {
+        let mut nonce = [0u8; 8];
+        getrandom::getrandom(&mut nonce).map_err(|e| e.to_string())?;
+        let path = std::env::temp_dir().join(format!("winload-{}-{:016x}", std::process::id(), u64::from_ne_bytes(nonce)));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).map_err(|e| format!("cannot create {}: {e}", path.display()))?;
+        let temp = Self(path);
+        file.write_all(content).map_err(|e| format!("cannot write {}: {e}", temp.0.display()))?;
+        Ok(temp)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// 解码 chunked 传输编码
+fn dechunk(mut data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(line_end) = data.windows(2).position(|w| w == b"\r\n") {
+        let size_str = String::from_utf8_lossy(&data[..line_end]);
+        let size = usize::from_str_radix(size_str.split(';').next().unwrap_or("").trim(), 16).unwrap_or(0);
+        data = &data[line_end + 2..];
+        if size == 0 || data.len() < size {
+            break;
+        }
+        out.extend_from_slice(&data[..size]);
+        data = data.get(size + 2..).unwrap_or(&[]);
+    }
+    out
+}
+
+/// POST 请求，非 2xx 状态视为错误
+pub fn post(url: &Url, headers: &[(&str, &str)], body: &[u8], timeout: Duration) -> Result<(), String> {
+    let resp = request("POST", url, headers, body, timeout)?;
+    check_status(url, &resp)
+}
+
+/// GET 请求，返回响应体；非 2xx 状态视为错误
+pub fn get(url: &Url, headers: &[(&str, &str)], timeout: Duration) -> Result<Vec<u8>, String> {
+    let resp = request("GET", url, headers, &[], timeout)?;
+    check_status(url, &resp)?;
+    Ok(resp.body)
+}
+
+fn check_status(url: &Url, resp: &Response) -> Result<(), String> {
+    if (200..300).contains(&resp.status) {
+        Ok(())
+    } else {
+        let detail = String::from_utf8_lossy(&resp.body[..resp.body.len().min(200)]).trim().to_string();
+        Err(format!("{url} returned HTTP {} {detail}", resp.status))
+    }
+}
+
+// ─── 服务端 ────────────────────────────────────────────────
+
+/// 收到的请求 (只解析请求行，忽略头部和请求体)
+pub struct Request {
+    pub method: String,
+    /// 不含查询字符串的路径
+    pub path: String,
+}
+
+/// 在 `addr` 上监听，每个连接在单独的线程里交给 `handler` 处理
+///
+/// `handler` 拿到连接本身，可以用 [`respond`] 回复，也可以持续写入 (如 SSE)。
+/// 返回实际监听的地址 (端口为 0 时由系统分配)。
+pub fn serve<F>(addr: &str, handler: F) -> Result<SocketAddr, String>
+where
+    F: Fn(Request, TcpStream) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(addr).map_err(|e| format!("cannot listen on {addr}: {e}"))?;
+    let local = listener.local_addr().map_err(|e| format!("cannot listen on {addr}: {e}"))?;
+    let handler = Arc::new(handler);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let handler = handler.clone();
+            thread::spawn(move || match read_request(&mut stream) {
+                Ok(req) => handler(req, stream),
+                Err(_) => {
+                    let _ = respond(&mut stream, 400, "text/plain", b"bad request\n");
+                }
+            });
+        }
+    });
+    Ok(local)
+}
+
+/// 读取请求头，解析出请求行
+fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(SERVER_READ_TIMEOUT))?;
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !raw.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf)?;
+        if n == 0 || raw.len() + n > MAX_REQUEST_HEAD {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&raw);
+    let mut parts = head.split("\r\n").next().unwrap_or("").split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(io::ErrorKind::InvalidData.into());
+    };
+    let path = target.split('?').next().unwrap_or(target);
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// 写出一个完整响应并关闭连接
+pub fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &[u8]) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "",
+    };
+    let head = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_schemes_and_ports() {
+        let u = parse_url("https://hook.local/alert?x=1").unwrap();
+        assert!(u.tls);
+        assert_eq!((u.host.as_str(), u.port, u.path.as_str()), ("hook.local", 443, "/alert?x=1"));
+        assert_eq!(u.to_string(), "https://hook.local:443/alert?x=1");
+
+        let u = parse_url("http://[::1]:9100").unwrap();
+        assert!(!u.tls);
+        assert_eq!((u.host.as_str(), u.port, u.path.as_str()), ("::1", 9100, "/"));
+        assert_eq!(u.to_string(), "http://[::1]:9100/");
+
+        assert_eq!(parse_url("http://h").unwrap().port, 80);
+        assert!(parse_url("ftp://h/").unwrap_err().contains("http:// and https://"));
+        assert!(parse_url("https:///x").unwrap_err().contains("missing host"));
+    }
+}
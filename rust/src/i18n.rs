@@ -61,6 +61,38 @@ fn t_en_us(key: &str) -> &'static str {
         "help_hide_separator" => "Hide separator line (the row of equals signs between header and panels)",
         "help_no_color" => "Disable all TUI colors (monochrome mode). Press 'c' to toggle at runtime",
         "help_npcap" => "[Windows only] Use Npcap to capture loopback traffic (recommended)\nRequires Npcap installed: https://npcap.com/#download",
+        "help_group" => "Combine devices into a virtual group device, e.g. uplinks=wwan0+wwan1.\nMembers may contain * wildcards, e.g. edge=*/eth0 (repeatable)",
+        "help_quota" => "Data cap per device or group: TARGET=SIZE[/day|/week|/month][@RESET_DAY]\ne.g. wwan0=500G/month@1 (repeatable)",
+        "help_alert" => "Threshold alert rule (repeatable), e.g. \"eth0 in > 800M for 10s\",\n\"out < 1K for 2m clear > 10K\", \"wwan0 quota > 90%\"\nAppend \"=> bell, syslog, webhook:URL, exec:CMD\" to deliver notifications",
+        "help_anomaly" => "Detect traffic anomalies against a learned per-hour baseline;\nZ is the deviation threshold in standard deviations (default 4).\nThe baseline is kept in the --db database when enabled",
+        "help_record" => "Record every raw sample to FILE for later --replay",
+        "help_replay" => "Replay a file written by --record instead of monitoring live traffic",
+        "help_read" => "Analyze a pcap / pcapng capture file (timeline + summary) instead of monitoring live traffic",
+        "help_output" => "Print one record per interval per device to stdout (jsonl or csv) instead of starting the TUI",
+        "help_headless" => "Run without the TUI (e.g. only serving --prometheus-listen)",
+        "help_prometheus_listen" => "Serve Prometheus metrics on ADDR (e.g. 127.0.0.1:9100) at /metrics",
+        "help_web" => "Serve a live web dashboard on ADDR (e.g. 127.0.0.1:8080)",
+        "help_sink" => "Push statistics every interval to influx:URL, influx-udp:ADDR, graphite:ADDR, statsd:ADDR or otlp:URL (repeatable)",
+        "help_connect" => "Monitor remote hosts running `winload agent` instead of this machine.\nRepeat for several hosts; devices are then named NAME/DEVICE",
+        "help_snmp" => "Poll a router or switch over SNMP (IF-MIB) instead of this machine.\nRepeatable, and can be combined with --connect",
+        "help_exec" => "Run a command every interval and read counters from its output\n(/proc/net/dev or JSON), e.g. 'ssh router cat /proc/net/dev' (repeatable)",
+        "help_node_exporter" => "Scrape a Prometheus node_exporter (HOST[:PORT], default port 9100,\nor a full http:// URL) instead of this machine (repeatable)",
+        "help_netns" => "Monitor another network namespace: a name from `ip netns`,\na path such as /proc/PID/ns/net, or a PID (Linux, repeatable)",
+        "help_all_netns" => "Monitor every network namespace, prefixing devices with the\nnamespace name (Linux)",
+        "help_filter" => "Capture packets matching a tcpdump-style expression and show them\nas a virtual device, e.g. 'tcp port 443' or 'web=port 80 or 443'\n(Linux, needs root, repeatable)",
+        "help_capture" => "Capture packets for the flow table (press f) and protocol breakdown\n(press p) without adding --filter devices (Linux, needs root)",
+        "help_capture_iface" => "Interface to capture on for --filter / --capture (default: all interfaces; with --read, an interface in the file)",
+        "help_snmp_community" => "SNMPv2c community",
+        "help_snmp_user" => "Use SNMPv3 with this user instead of v2c",
+        "help_snmp_auth" => "SNMPv3 authentication protocol.\nPassphrase: $WINLOAD_SNMP_AUTH_PASS",
+        "help_snmp_priv" => "SNMPv3 privacy (encryption) protocol.\nPassphrase: $WINLOAD_SNMP_PRIV_PASS",
+        "help_key_file" => "Shared key file for agent / --connect authentication.\nDefault: $WINLOAD_KEY (at least 16 characters)",
+        "help_db" => "Record traffic totals into a persistent database (hourly/daily/monthly).\nOptional PATH, default: platform data directory",
+        "help_report" => "Print hourly/daily/monthly totals from the traffic database",
+        "help_report_db" => "Database path. Default: platform data directory",
+        "help_report_device" => "Only show devices matching this name (partial match)",
+        "help_agent" => "Serve this machine's counters to `winload --connect` clients",
+        "help_agent_listen" => "Address to listen on",
         "help_lang" => "Display language: en-us (default), zh-cn, zh-tw",
         // -- TUI --
         "device" => "Device",
@@ -79,13 +111,75 @@ fn t_en_us(key: &str) -> &'static str {
         "stat_min_emoji" => "\u{1f4cf}  Min",
         "stat_max_emoji" => "\u{1f680}  Max",
         "stat_ttl_emoji" => "\u{1f4e6}  Ttl",
-        "help_bar" => " \u{2190}/\u{2192} Switch Device | q Quit",
-        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} Switch Device | \u{1f6aa} q Quit",
-        "help_bar_win" => " \u{2190}/\u{2192} Switch Device | q Quit | Loopback: --npcap",
-        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} Switch Device | \u{1f6aa} q Quit | \u{1f4a1} Loopback: --npcap",
+        "help_bar" => " \u{2190}/\u{2192} Switch Device | a Alerts | e Events | m/M Mark | q Quit",
+        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} Switch Device | \u{1f6a8} a Alerts | \u{1f4dc} e Events | \u{1f4cc} m/M Mark | \u{1f6aa} q Quit",
+        "help_bar_win" => " \u{2190}/\u{2192} Switch Device | a Alerts | e Events | m/M Mark | q Quit | Loopback: --npcap",
+        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} Switch Device | \u{1f6a8} a Alerts | \u{1f4dc} e Events | \u{1f4cc} m/M Mark | \u{1f6aa} q Quit | \u{1f4a1} Loopback: --npcap",
         "terminal_too_small" => "Terminal too small!",
         "terminal_too_small_emoji" => "\u{1f62d} Terminal too small! \u{1f4cc}",
         "loopback_warning" => " \u{26a0} Loopback: use --npcap (npcap.com)",
+        "alert_banner" => " \u{26a0} ALERT",
+        "alert_more" => "more",
+        "alerts_title" => "Alerts",
+        "alerts_title_emoji" => "\u{1f6a8} Alerts",
+        "alerts_firing" => "firing",
+        "alerts_rules" => "rules",
+        "alerts_none" => "No alert rules configured. Add rules with --alert, e.g. --alert \"eth0 in > 800M for 10s\"",
+        "alerts_no_data" => "(no matching device)",
+        "alerts_notify_error" => "Last delivery error",
+        "alert_state_ok" => "OK",
+        "alert_state_pending" => "PENDING",
+        "alert_state_firing" => "FIRING",
+        "alert_state_recovering" => "RECOVERING",
+        "anomaly_banner" => " \u{26a1} Anomaly",
+        "anomaly_expected" => "expected",
+        "anomaly_ended" => "back to baseline",
+        "anomaly_peak" => "peak",
+        "events_title" => "Events",
+        "events_title_emoji" => "\u{1f4dc} Events",
+        "events_none" => "No events yet",
+        "event_anomaly" => "ANOMALY",
+        "event_normal" => "NORMAL",
+        "events_scroll_hint" => "\u{2191}/\u{2193} PgUp/PgDn scroll",
+        "flows_title" => "Flows",
+        "flows_title_emoji" => "\u{1f500} Flows",
+        "flows_none" => "No traffic captured yet",
+        "flows_no_capture" => "Not capturing packets: run with --capture or --filter (Linux, needs root), --npcap (Windows loopback) or --read FILE",
+        "flows_no_capture_replay" => "Recordings hold only interface counters; open a capture file with --read to see connections and protocols",
+        "flows_sort" => "sort",
+        "flows_sort_2s" => "2s avg",
+        "flows_sort_10s" => "10s avg",
+        "flows_sort_40s" => "40s avg",
+        "flows_sort_total" => "total",
+        "flows_total" => "total",
+        "flows_hint" => "s sort  \u{2191}/\u{2193} PgUp/PgDn scroll",
+        "protocols_title" => "Protocols",
+        "protocols_title_emoji" => "\u{1f9ec} Protocols",
+        "protocols_total" => "total",
+        "protocols_share" => "share",
+        "protocols_other_ports" => "other ports",
+        "protocols_other" => "Other",
+        "event_added" => "NEW",
+        "event_removed" => "GONE",
+        "event_link_up" => "LINK UP",
+        "event_link_down" => "LINK DOWN",
+        "event_link_speed" => "SPEED",
+        "event_counter_reset" => "RESET",
+        "event_counter_reset_msg" => "counters went backwards, statistics restarted",
+        "event_cleared" => "CLEARED",
+        "event_error" => "ERROR",
+        "event_marker" => "MARK",
+        "marker_prompt" => "Marker label",
+        "marker_prompt_keys" => "Enter = add, Esc = cancel",
+        "replay_playing" => "\u{25b6} REPLAY",
+        "replay_paused" => "\u{23f8} PAUSED",
+        "replay_end" => "\u{25a0} END",
+        "replay_keys" => "Space pause, +/- speed, [ ] 10s, { } 60s",
+        "remote_disconnected" => "disconnected",
+        "quota" => "Quota",
+        "quota_emoji" => "\u{1f4b3} Quota",
+        "quota_left" => "left",
+        "quota_projected" => "projected",
         _ => "",
     }
 }
@@ -111,6 +205,38 @@ fn t_zh_cn(key: &str) -> &'static str {
         "help_hide_separator" => "隐藏分隔线（标题和面板之间的等号行）",
         "help_no_color" => "禁用所有 TUI 颜色（单色模式）。运行时按 'c' 切换",
         "help_npcap" => "[仅 Windows] 使用 Npcap 捕获回环流量（推荐）\n需要安装 Npcap：https://npcap.com/#download",
+        "help_group" => "把多块网卡合并为一个虚拟设备，如 uplinks=wwan0+wwan1。\n成员可以使用 * 通配符，如 edge=*/eth0（可重复）",
+        "help_quota" => "网卡或组的流量配额：TARGET=SIZE[/day|/week|/month][@重置日]\n如 wwan0=500G/month@1（可重复）",
+        "help_alert" => "阈值告警规则（可重复），如 \"eth0 in > 800M for 10s\"、\n\"out < 1K for 2m clear > 10K\"、\"wwan0 quota > 90%\"\n追加 \"=> bell, syslog, webhook:URL, exec:CMD\" 以投递通知",
+        "help_anomaly" => "按每小时学习到的速率基线检测流量异常；\nZ 为偏离阈值（标准差倍数，默认 4）。\n启用 --db 时基线保存在数据库中",
+        "help_record" => "把每次采集的原始数据录制到 FILE，供之后 --replay 回放",
+        "help_replay" => "回放 --record 录制的文件，而不是监控实时流量",
+        "help_read" => "离线分析 pcap / pcapng 抓包文件（时间线 + 汇总报告），而不是监控实时流量",
+        "help_output" => "不进入 TUI，每个间隔为每个设备向 stdout 打印一条记录 (jsonl 或 csv)",
+        "help_headless" => "不启动 TUI 运行 (例如只提供 --prometheus-listen)",
+        "help_prometheus_listen" => "在 ADDR (如 127.0.0.1:9100) 的 /metrics 上提供 Prometheus 指标",
+        "help_web" => "在 ADDR (如 127.0.0.1:8080) 上提供实时网页仪表盘",
+        "help_sink" => "每个间隔把统计推送到 influx:URL、influx-udp:ADDR、graphite:ADDR、statsd:ADDR 或 otlp:URL (可重复)",
+        "help_connect" => "监控运行 `winload agent` 的远程主机，而不是本机。\n可重复指定多台主机，此时网卡名为 NAME/网卡",
+        "help_snmp" => "通过 SNMP (IF-MIB) 轮询路由器或交换机，而不是本机。\n可重复，可与 --connect 同时使用",
+        "help_exec" => "每个采样间隔运行一次命令，从其输出（/proc/net/dev 或 JSON）读取计数器，\n如 'ssh router cat /proc/net/dev'（可重复）",
+        "help_node_exporter" => "抓取 Prometheus node_exporter（HOST[:PORT]，默认端口 9100，\n或完整的 http:// URL），而不是监控本机（可重复）",
+        "help_netns" => "监控其他网络命名空间：`ip netns` 中的名称、\n/proc/PID/ns/net 之类的路径或 PID（仅 Linux，可重复）",
+        "help_all_netns" => "监控所有网络命名空间，设备名以命名空间名称为前缀（仅 Linux）",
+        "help_filter" => "抓取匹配 tcpdump 风格表达式的包，显示为虚拟设备，\n如 'tcp port 443' 或 'web=port 80 or 443'（仅 Linux，需要 root，可重复）",
+        "help_capture" => "抓包统计各连接的流量（按 f 查看）和协议分布（按 p 查看），\n不添加 --filter 虚拟设备（仅 Linux，需要 root）",
+        "help_capture_iface" => "--filter / --capture 抓包的网卡（默认：所有网卡；配合 --read 时为文件中的接口）",
+        "help_snmp_community" => "SNMPv2c community 字符串",
+        "help_snmp_user" => "使用 SNMPv3 及该用户，而不是 v2c",
+        "help_snmp_auth" => "SNMPv3 认证协议。\n口令：$WINLOAD_SNMP_AUTH_PASS",
+        "help_snmp_priv" => "SNMPv3 加密协议。\n口令：$WINLOAD_SNMP_PRIV_PASS",
+        "help_key_file" => "agent / --connect 认证使用的共享密钥文件。\n默认：$WINLOAD_KEY（至少 16 个字符）",
+        "help_db" => "将流量累计写入持久化数据库（按小时/天/月）。\n可选 PATH，默认：系统数据目录",
+        "help_report" => "打印流量数据库中的小时/天/月统计",
+        "help_report_db" => "数据库路径。默认：系统数据目录",
+        "help_report_device" => "只显示名称匹配的网卡（支持部分匹配）",
+        "help_agent" => "向 `winload --connect` 客户端提供本机的流量计数器",
+        "help_agent_listen" => "监听地址",
         "help_lang" => "显示语言：en-us（默认），zh-cn，zh-tw",
         // -- TUI --
         "device" => "设备",
@@ -129,13 +255,75 @@ fn t_zh_cn(key: &str) -> &'static str {
         "stat_min_emoji" => "\u{1f4cf} 最小",
         "stat_max_emoji" => "\u{1f680} 最大",
         "stat_ttl_emoji" => "\u{1f4e6} 总计",
-        "help_bar" => " \u{2190}/\u{2192} 切换设备 | q 退出",
-        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切换设备 | \u{1f6aa} q 退出",
-        "help_bar_win" => " \u{2190}/\u{2192} 切换设备 | q 退出 | 回环: --npcap",
-        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切换设备 | \u{1f6aa} q 退出 | \u{1f4a1} 回环: --npcap",
+        "help_bar" => " \u{2190}/\u{2192} 切换设备 | a 告警 | e 事件 | m/M 标记 | q 退出",
+        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切换设备 | \u{1f6a8} a 告警 | \u{1f4dc} e 事件 | \u{1f4cc} m/M 标记 | \u{1f6aa} q 退出",
+        "help_bar_win" => " \u{2190}/\u{2192} 切换设备 | a 告警 | e 事件 | m/M 标记 | q 退出 | 回环: --npcap",
+        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切换设备 | \u{1f6a8} a 告警 | \u{1f4dc} e 事件 | \u{1f4cc} m/M 标记 | \u{1f6aa} q 退出 | \u{1f4a1} 回环: --npcap",
         "terminal_too_small" => "终端窗口太小！",
         "terminal_too_small_emoji" => "\u{1f62d} 终端窗口太小！\u{1f4cc}",
         "loopback_warning" => " \u{26a0} 回环设备：请使用 --npcap (npcap.com)",
+        "alert_banner" => " \u{26a0} 告警",
+        "alert_more" => "条更多",
+        "alerts_title" => "告警",
+        "alerts_title_emoji" => "\u{1f6a8} 告警",
+        "alerts_firing" => "触发中",
+        "alerts_rules" => "条规则",
+        "alerts_none" => "未配置告警规则。使用 --alert 添加，如 --alert \"eth0 in > 800M for 10s\"",
+        "alerts_no_data" => "（无匹配设备）",
+        "alerts_notify_error" => "最近一次投递失败",
+        "alert_state_ok" => "正常",
+        "alert_state_pending" => "等待",
+        "alert_state_firing" => "触发",
+        "alert_state_recovering" => "恢复中",
+        "anomaly_banner" => " \u{26a1} 流量异常",
+        "anomaly_expected" => "基线",
+        "anomaly_ended" => "恢复到基线",
+        "anomaly_peak" => "峰值",
+        "events_title" => "事件",
+        "events_title_emoji" => "\u{1f4dc} 事件",
+        "events_none" => "暂无事件",
+        "event_anomaly" => "异常",
+        "event_normal" => "恢复",
+        "events_scroll_hint" => "\u{2191}/\u{2193} PgUp/PgDn 滚动",
+        "flows_title" => "连接",
+        "flows_title_emoji" => "\u{1f500} 连接",
+        "flows_none" => "尚未抓到流量",
+        "flows_no_capture" => "未在抓包：请使用 --capture 或 --filter（仅 Linux，需要 root）、--npcap（Windows 回环）或 --read 文件运行",
+        "flows_no_capture_replay" => "录制文件只有网卡计数器；用 --read 打开抓包文件才能查看连接和协议",
+        "flows_sort" => "排序",
+        "flows_sort_2s" => "2 秒平均",
+        "flows_sort_10s" => "10 秒平均",
+        "flows_sort_40s" => "40 秒平均",
+        "flows_sort_total" => "累计",
+        "flows_total" => "累计",
+        "flows_hint" => "s 排序  \u{2191}/\u{2193} PgUp/PgDn 滚动",
+        "protocols_title" => "协议分布",
+        "protocols_title_emoji" => "\u{1f9ec} 协议分布",
+        "protocols_total" => "合计",
+        "protocols_share" => "占比",
+        "protocols_other_ports" => "其他端口",
+        "protocols_other" => "其他",
+        "event_added" => "新网卡",
+        "event_removed" => "已移除",
+        "event_link_up" => "链路恢复",
+        "event_link_down" => "链路断开",
+        "event_link_speed" => "速率变化",
+        "event_counter_reset" => "计数回退",
+        "event_counter_reset_msg" => "计数器回退，统计已重新开始",
+        "event_cleared" => "已恢复",
+        "event_error" => "错误",
+        "event_marker" => "标记",
+        "marker_prompt" => "标记标签",
+        "marker_prompt_keys" => "Enter 确认，Esc 取消",
+        "replay_playing" => "\u{25b6} 回放",
+        "replay_paused" => "\u{23f8} 已暂停",
+        "replay_end" => "\u{25a0} 结束",
+        "replay_keys" => "空格 暂停, +/- 变速, [ ] 10秒, { } 60秒",
+        "remote_disconnected" => "已断开",
+        "quota" => "配额",
+        "quota_emoji" => "\u{1f4b3} 配额",
+        "quota_left" => "剩余",
+        "quota_projected" => "预计",
         _ => t_en_us(key),
     }
 }
@@ -161,6 +349,38 @@ fn t_zh_tw(key: &str) -> &'static str {
         "help_hide_separator" => "隱藏分隔線（標題和面板之間的等號行）",
         "help_no_color" => "停用所有 TUI 顏色（單色模式）。執行時按 'c' 切換",
         "help_npcap" => "[僅 Windows] 使用 Npcap 擷取回環流量（建議）\n需要安裝 Npcap：https://npcap.com/#download",
+        "help_group" => "把多塊網路卡合併為一個虛擬裝置，如 uplinks=wwan0+wwan1。\n成員可以使用 * 萬用字元，如 edge=*/eth0（可重複）",
+        "help_quota" => "網路卡或群組的流量配額：TARGET=SIZE[/day|/week|/month][@重置日]\n如 wwan0=500G/month@1（可重複）",
+        "help_alert" => "閾值告警規則（可重複），如 \"eth0 in > 800M for 10s\"、\n\"out < 1K for 2m clear > 10K\"、\"wwan0 quota > 90%\"\n追加 \"=> bell, syslog, webhook:URL, exec:CMD\" 以投遞通知",
+        "help_anomaly" => "依每小時學習到的速率基線偵測流量異常；\nZ 為偏離閾值（標準差倍數，預設 4）。\n啟用 --db 時基線保存在資料庫中",
+        "help_record" => "把每次擷取的原始資料錄製到 FILE，供之後 --replay 重播",
+        "help_replay" => "重播 --record 錄製的檔案，而不是監控即時流量",
+        "help_read" => "離線分析 pcap / pcapng 封包檔（時間軸 + 彙總報告），而不是監控即時流量",
+        "help_output" => "不進入 TUI，每個間隔為每個裝置向 stdout 列印一筆記錄 (jsonl 或 csv)",
+        "help_headless" => "不啟動 TUI 執行 (例如只提供 --prometheus-listen)",
+        "help_prometheus_listen" => "在 ADDR (如 127.0.0.1:9100) 的 /metrics 上提供 Prometheus 指標",
+        "help_web" => "在 ADDR (如 127.0.0.1:8080) 上提供即時網頁儀表板",
+        "help_sink" => "每個間隔把統計推送到 influx:URL、influx-udp:ADDR、graphite:ADDR、statsd:ADDR 或 otlp:URL (可重複)",
+        "help_connect" => "監控執行 `winload agent` 的遠端主機，而非本機。\n可重複指定多台主機，此時網路卡名稱為 NAME/網路卡",
+        "help_snmp" => "透過 SNMP (IF-MIB) 輪詢路由器或交換器，而非本機。\n可重複，可與 --connect 同時使用",
+        "help_exec" => "每個取樣間隔執行一次命令，從其輸出（/proc/net/dev 或 JSON）讀取計數器，\n如 'ssh router cat /proc/net/dev'（可重複）",
+        "help_node_exporter" => "抓取 Prometheus node_exporter（HOST[:PORT]，預設連接埠 9100，\n或完整的 http:// URL），而非監控本機（可重複）",
+        "help_netns" => "監控其他網路命名空間：`ip netns` 中的名稱、\n/proc/PID/ns/net 之類的路徑或 PID（僅 Linux，可重複）",
+        "help_all_netns" => "監控所有網路命名空間，裝置名稱以命名空間名稱為前綴（僅 Linux）",
+        "help_filter" => "擷取符合 tcpdump 風格運算式的封包，顯示為虛擬裝置，\n如 'tcp port 443' 或 'web=port 80 or 443'（僅 Linux，需要 root，可重複）",
+        "help_capture" => "擷取封包統計各連線的流量（按 f 檢視）和協定分佈（按 p 檢視），\n不新增 --filter 虛擬裝置（僅 Linux，需要 root）",
+        "help_capture_iface" => "--filter / --capture 擷取封包的網卡（預設：所有網卡；搭配 --read 時為檔案中的介面）",
+        "help_snmp_community" => "SNMPv2c community 字串",
+        "help_snmp_user" => "使用 SNMPv3 及該使用者，而非 v2c",
+        "help_snmp_auth" => "SNMPv3 認證協定。\n密碼：$WINLOAD_SNMP_AUTH_PASS",
+        "help_snmp_priv" => "SNMPv3 加密協定。\n密碼：$WINLOAD_SNMP_PRIV_PASS",
+        "help_key_file" => "agent / --connect 認證使用的共用金鑰檔案。\n預設：$WINLOAD_KEY（至少 16 個字元）",
+        "help_db" => "將流量累計寫入持久化資料庫（按小時/天/月）。\n可選 PATH，預設：系統資料目錄",
+        "help_report" => "列印流量資料庫中的小時/天/月統計",
+        "help_report_db" => "資料庫路徑。預設：系統資料目錄",
+        "help_report_device" => "只顯示名稱符合的網路卡（支援部分比對）",
+        "help_agent" => "向 `winload --connect` 用戶端提供本機的流量計數器",
+        "help_agent_listen" => "監聽位址",
         "help_lang" => "顯示語言：en-us（預設），zh-cn，zh-tw",
         // -- TUI --
         "device" => "裝置",
@@ -179,13 +399,75 @@ fn t_zh_tw(key: &str) -> &'static str {
         "stat_min_emoji" => "\u{1f4cf} 最小",
         "stat_max_emoji" => "\u{1f680} 最大",
         "stat_ttl_emoji" => "\u{1f4e6} 總計",
-        "help_bar" => " \u{2190}/\u{2192} 切換裝置 | q 退出",
-        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切換裝置 | \u{1f6aa} q 退出",
-        "help_bar_win" => " \u{2190}/\u{2192} 切換裝置 | q 退出 | 回環: --npcap",
-        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切換裝置 | \u{1f6aa} q 退出 | \u{1f4a1} 回環: --npcap",
+        "help_bar" => " \u{2190}/\u{2192} 切換裝置 | a 告警 | e 事件 | m/M 標記 | q 退出",
+        "help_bar_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切換裝置 | \u{1f6a8} a 告警 | \u{1f4dc} e 事件 | \u{1f4cc} m/M 標記 | \u{1f6aa} q 退出",
+        "help_bar_win" => " \u{2190}/\u{2192} 切換裝置 | a 告警 | e 事件 | m/M 標記 | q 退出 | 回環: --npcap",
+        "help_bar_win_emoji" => " \u{2b05}\u{fe0f}/\u{27a1}\u{fe0f} 切換裝置 | \u{1f6a8} a 告警 | \u{1f4dc} e 事件 | \u{1f4cc} m/M 標記 | \u{1f6aa} q 退出 | \u{1f4a1} 回環: --npcap",
         "terminal_too_small" => "終端視窗太小！",
         "terminal_too_small_emoji" => "\u{1f62d} 終端視窗太小！\u{1f4cc}",
         "loopback_warning" => " \u{26a0} 回環裝置：請使用 --npcap (npcap.com)",
+        "alert_banner" => " \u{26a0} 告警",
+        "alert_more" => "則更多",
+        "alerts_title" => "告警",
+        "alerts_title_emoji" => "\u{1f6a8} 告警",
+        "alerts_firing" => "觸發中",
+        "alerts_rules" => "條規則",
+        "alerts_none" => "未設定告警規則。使用 --alert 新增，如 --alert \"eth0 in > 800M for 10s\"",
+        "alerts_no_data" => "（無符合裝置）",
+        "alerts_notify_error" => "最近一次投遞失敗",
+        "alert_state_ok" => "正常",
+        "alert_state_pending" => "等待",
+        "alert_state_firing" => "觸發",
+        "alert_state_recovering" => "恢復中",
+        "anomaly_banner" => " \u{26a1} 流量異常",
+        "anomaly_expected" => "基線",
+        "anomaly_ended" => "恢復到基線",
+        "anomaly_peak" => "峰值",
+        "events_title" => "事件",
+        "events_title_emoji" => "\u{1f4dc} 事件",
+        "events_none" => "暫無事件",
+        "event_anomaly" => "異常",
+        "event_normal" => "恢復",
+        "events_scroll_hint" => "\u{2191}/\u{2193} PgUp/PgDn 捲動",
+        "flows_title" => "連線",
+        "flows_title_emoji" => "\u{1f500} 連線",
+        "flows_none" => "尚未擷取到流量",
+        "flows_no_capture" => "未在擷取封包：請使用 --capture 或 --filter（僅 Linux，需要 root）、--npcap（Windows 回環）或 --read 檔案執行",
+        "flows_no_capture_replay" => "錄製檔只有網卡計數器；用 --read 開啟封包擷取檔才能檢視連線和協定",
+        "flows_sort" => "排序",
+        "flows_sort_2s" => "2 秒平均",
+        "flows_sort_10s" => "10 秒平均",
+        "flows_sort_40s" => "40 秒平均",
+        "flows_sort_total" => "累計",
+        "flows_total" => "累計",
+        "flows_hint" => "s 排序  \u{2191}/\u{2193} PgUp/PgDn 捲動",
+        "protocols_title" => "協定分佈",
+        "protocols_title_emoji" => "\u{1f9ec} 協定分佈",
+        "protocols_total" => "合計",
+        "protocols_share" => "佔比",
+        "protocols_other_ports" => "其他連接埠",
+        "protocols_other" => "其他",
+        "event_added" => "新網卡",
+        "event_removed" => "已移除",
+        "event_link_up" => "鏈路恢復",
+        "event_link_down" => "鏈路斷開",
+        "event_link_speed" => "速率變化",
+        "event_counter_reset" => "計數回退",
+        "event_counter_reset_msg" => "計數器回退，統計已重新開始",
+        "event_cleared" => "已恢復",
+        "event_error" => "錯誤",
+        "event_marker" => "標記",
+        "marker_prompt" => "標記標籤",
+        "marker_prompt_keys" => "Enter 確認，Esc 取消",
+        "replay_playing" => "\u{25b6} 重播",
+        "replay_paused" => "\u{23f8} 已暫停",
+        "replay_end" => "\u{25a0} 結束",
+        "replay_keys" => "空白 暫停, +/- 變速, [ ] 10秒, { } 60秒",
+        "remote_disconnected" => "已中斷",
+        "quota" => "配額",
+        "quota_emoji" => "\u{1f4b3} 配額",
+        "quota_left" => "剩餘",
+        "quota_projected" => "預計",
         _ => t_en_us(key),
     }
 }
@@ -5,7 +5,10 @@
 //! 此模块仅在 Windows 平台编译。非 Windows 平台下提供空实现。
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+use crate::flows::FlowDeltas;
+use crate::protocols::Breakdown;
 
 /// Npcap 下载地址 (仅 Windows)
 #[cfg(target_os = "windows")]
@@ -16,6 +19,12 @@ pub const NPCAP_URL: &str = "https://npcap.com/#download";
 pub struct LoopbackCounters {
     pub bytes_recv: Arc<AtomicU64>,
     pub bytes_sent: Arc<AtomicU64>,
+    /// 主线程上次取走以来各连接、各协议的字节数，供连接表和协议分布视图
+    pub flows: Arc<Mutex<FlowDeltas>>,
+    pub protocols: Arc<Mutex<Breakdown>>,
+    /// 捕获线程退出时的错误，由主线程取出写入事件日志
+    /// (直接 eprintln! 会打在 TUI 中间，并在下一次重绘时丢失)
+    pub error: Arc<Mutex<Option<String>>>,
 }
 
 impl LoopbackCounters {
@@ -23,6 +32,9 @@ impl LoopbackCounters {
         Self {
             bytes_recv: Arc::new(AtomicU64::new(0)),
             bytes_sent: Arc::new(AtomicU64::new(0)),
+            flows: Arc::new(Mutex::new(FlowDeltas::new())),
+            protocols: Arc::new(Mutex::new(Breakdown::default())),
+            error: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -33,6 +45,29 @@ impl LoopbackCounters {
     pub fn get_sent(&self) -> u64 {
         self.bytes_sent.load(Ordering::Relaxed)
     }
+
+    /// 取出上次调用以来各连接的字节数
+    pub fn take_flows(&self) -> FlowDeltas {
+        self.flows.lock().map(|mut f| std::mem::take(&mut *f)).unwrap_or_default()
+    }
+
+    /// 取出上次调用以来各协议的字节数
+    pub fn take_protocols(&self) -> Breakdown {
+        self.protocols.lock().map(|mut p| std::mem::take(&mut *p)).unwrap_or_default()
+    }
+
+    /// 记录捕获线程的错误
+    #[cfg(all(target_os = "windows", feature = "npcap"))]
+    pub fn set_error(&self, msg: String) {
+        if let Ok(mut slot) = self.error.lock() {
+            *slot = Some(msg);
+        }
+    }
+
+    /// 取出捕获线程的错误 (每个错误只返回一次)
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().ok()?.take()
+    }
 }
 
 /// 回环捕获模式
@@ -53,6 +88,11 @@ pub mod platform {
     use super::*;
     use std::thread;
 
+    #[cfg(feature = "npcap")]
+    use crate::decode::{LocalAddrs, Packet};
+    #[cfg(feature = "npcap")]
+    use crate::flows::FlowKey;
+
     /// 启动 Npcap 回环捕获线程
     ///
     /// 返回 Ok(info_msg) 成功时，后台线程会持续累加计数器。
@@ -110,7 +150,7 @@ pub mod platform {
             .name("npcap-loopback".to_string())
             .spawn(move || {
                 if let Err(e) = npcap_capture_loop(&dev_name, &counters) {
-                    eprintln!("[npcap] Capture error: {e}");
+                    counters.set_error(format!("[npcap] Capture error: {e}"));
                 }
             })
             .map_err(|e| format!("Failed to spawn npcap thread: {e}"))?;
@@ -123,36 +163,29 @@ pub mod platform {
         let mut cap = pcap::Capture::from_device(device_name)
             .map_err(|e| format!("Cannot open device: {e}"))?
             .promisc(false)
-            .snaplen(96) // 只需要 IP 头来获取包长度
+            .snaplen(128) // 只需要到传输层端口
             .timeout(100) // 100ms 超时，避免阻塞
             .open()
             .map_err(|e| format!("Cannot start capture: {e}"))?;
+        // Npcap loopback 使用 DLT_NULL: 4 字节地址族之后是完整的 IP 包
+        let linktype = cap.get_datalink().0 as u16;
+        let local = LocalAddrs::host();
 
         loop {
             match cap.next_packet() {
                 Ok(packet) => {
-                    // Npcap loopback 使用 DLT_NULL 格式:
-                    // 前 4 字节是地址族 (AF_INET=2, AF_INET6=23/30)
-                    // 之后是完整的 IP 包
-                    let data = packet.data;
-                    if data.len() < 4 {
+                    let Some(decoded) = Packet::from_frame(linktype, packet.data, packet.header.len, None, &local) else {
                         continue;
+                    };
+                    // 回环上每个包只捕获一次，按地址和端口判断方向，只计入一边
+                    let counter = if decoded.outgoing { &counters.bytes_sent } else { &counters.bytes_recv };
+                    counter.fetch_add(decoded.len as u64, Ordering::Relaxed);
+                    if let (Some(key), Ok(mut flows)) = (FlowKey::of(&decoded), counters.flows.lock()) {
+                        flows.entry(key).or_default()[decoded.outgoing as usize] += decoded.len as u64;
                     }
-
-                    // 获取 IP 包长度 (跳过 4 字节的 DLT_NULL 头)
-                    let ip_payload = &data[4..];
-                    let pkt_len = ip_payload.len() as u64;
-
-                    if ip_payload.is_empty() {
-                        continue;
+                    if let Ok(mut protocols) = counters.protocols.lock() {
+                        protocols.add(&decoded);
                     }
-
-                    // 判断是 incoming 还是 outgoing:
-                    // 对于 loopback，发送和接收的包都会被捕获。
-                    // 简单方法: 所有包同时计入 recv 和 sent (因为回环流量
-                    // 是自己发给自己的，收发对等)
-                    counters.bytes_recv.fetch_add(pkt_len, Ordering::Relaxed);
-                    counters.bytes_sent.fetch_add(pkt_len, Ordering::Relaxed);
                 }
                 Err(pcap::Error::TimeoutExpired) => {
                     // 正常超时，继续循环
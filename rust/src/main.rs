@@ -5,29 +5,104 @@
 //!     winload              # 监控所有活跃网卡
 //!     winload -t 200       # 设置刷新间隔 200ms
 //!     winload -d "Wi-Fi"   # 指定默认设备
+//!     winload --db         # 把流量累计写入持久化数据库
+//!     winload report       # 打印数据库中的 小时/天/月 统计
+//!     winload --quota wwan0=500G/month@1   # 流量配额
+//!     winload --alert "eth0 in > 800M for 10s"   # 阈值告警
+//!     winload --anomaly    # 按学习到的基线检测流量异常
+//!     winload --record incident.rec   # 录制会话
+//!     winload --replay incident.rec   # 回放录制的会话
+//!     winload --output jsonl   # 不进入 TUI，每个间隔向 stdout 打印记录
+//!     winload --headless --prometheus-listen 127.0.0.1:9100   # 提供 /metrics
+//!     winload --sink graphite:graphite.local:2003   # 推送指标到外部时序库
+//!     winload --web 127.0.0.1:8080   # 网页仪表盘
+//!     winload agent --listen 0.0.0.0:7681 --key-file key   # 在服务器上运行 agent
+//!     winload --connect server:7681 --key-file key          # 查看远程主机
+//!     winload --connect web1:7681 --connect web2:7681       # 同时查看多台主机
+//!     winload --snmp switch1 --snmp-community public        # 通过 SNMP 查看交换机端口
+//!     winload --exec 'ssh router cat /proc/net/dev'         # 从命令输出读取计数器
+//!     winload --node-exporter web1:9100                      # 抓取 node_exporter 指标
+//!     winload --netns 4242 / --all-netns                     # 容器等其他网络命名空间 (Linux)
+//!     winload --filter 'tcp port 443'                        # 抓包，只看某个服务的流量 (Linux)
+//!     winload --capture                                      # 抓包，按 f 查看各连接的流量 (Linux)
+//!     winload --read capture.pcapng                          # 离线分析 tcpdump 抓包文件
 //!
 //! 快捷键:
 //!     ←/→ 或 ↑/↓   切换网卡
+//!     a             告警列表
+//!     e             事件日志 (↑/↓ PgUp/PgDn Home/End 滚动)
+//!     f             连接流量表 (抓包时；s 切换排序)
+//!     p             协议分布 (抓包时)
+//!     m / M         在时间线上打标记 / 打带标签的标记
+//!     回放时: 空格 暂停/继续, +/- 变速, [ ] 跳转 10 秒, { } 跳转 60 秒
 //!     q / Esc       退出
 
+mod alert;
+mod anomaly;
+mod capture;
 mod collector;
+mod db;
+mod decode;
+mod events;
+mod exec;
+mod filter;
+mod flows;
 mod graph;
+mod group;
 mod i18n;
+mod http;
 mod loopback;
+mod netns;
+mod node_exporter;
+mod notify;
+mod otlp;
+mod output;
+mod pcap;
+mod prometheus;
+mod protocols;
+mod quota;
+mod recording;
+mod remote;
+mod snmp;
+mod sink;
+mod source;
 mod stats;
 mod ui;
+mod web;
 
+use std::collections::HashMap;
 use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use clap::{CommandFactory, FromArgMatches, Parser};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 
 use i18n::{Lang, t, set_lang};
 
-use collector::{Collector, DeviceInfo};
+use alert::{AlertEngine, Rule};
+use anomaly::AnomalyDetector;
+use collector::{Collector, DeviceInfo, LinkState, Snapshot};
+use db::TrafficDb;
+use events::{EventKind, EventLog};
+use flows::FlowTable;
+use group::Group;
+use quota::Quota;
 use loopback::{LoopbackCounters, LoopbackMode};
+use notify::Notifier;
+use output::{OutputFormat, RecordWriter};
+use prometheus::Exporter;
+use protocols::ProtocolStats;
+use recording::{Player, Recorder, Recording};
+use sink::{Sink, Sinks};
+use source::Source;
 use stats::StatisticsEngine;
+use web::WebServer;
+
+use chrono::{DateTime, Local};
 
 // ─── 单位枚举 ─────────────────────────────────────────────
 
@@ -55,7 +130,9 @@ pub fn parse_max_value(s: &str) -> Result<f64, String> {
     if s.is_empty() {
         return Err("empty value".to_string());
     }
-    let (num_str, multiplier) = if let Some(n) = s.strip_suffix('G').or_else(|| s.strip_suffix('g')) {
+    let (num_str, multiplier) = if let Some(n) = s.strip_suffix('T').or_else(|| s.strip_suffix('t')) {
+        (n, 1024.0 * 1024.0 * 1024.0 * 1024.0)
+    } else if let Some(n) = s.strip_suffix('G').or_else(|| s.strip_suffix('g')) {
         (n, 1024.0 * 1024.0 * 1024.0)
     } else if let Some(n) = s.strip_suffix('M').or_else(|| s.strip_suffix('m')) {
         (n, 1024.0 * 1024.0)
@@ -85,9 +162,10 @@ pub fn parse_hex_color(s: &str) -> Result<ratatui::style::Color, String> {
 /// Network Load Monitor — nload-like TUI tool for Windows/Linux/macOS
 #[derive(Parser)]
 #[command(name = "winload", version = concat!(env!("CARGO_PKG_VERSION"), " (Rust edition)"))]
+#[command(group(clap::ArgGroup::new("packets").multiple(true).args(["filters", "capture", "read"])))]
 struct Args {
     /// Refresh interval in milliseconds
-    #[arg(short = 't', long = "interval", default_value = "500")]
+    #[arg(short = 't', long = "interval", default_value = "500", value_parser = clap::value_parser!(u64).range(1..))]
     interval: u64,
 
     /// Average window in seconds
@@ -146,13 +224,205 @@ struct Args {
     #[arg(long = "npcap")]
     npcap: bool,
 
+    /// Combine devices into a virtual group device (NAME=DEV1+DEV2)
+    #[arg(long = "group", value_name = "NAME=DEV1+DEV2", value_parser = group::parse_group)]
+    groups: Vec<Group>,
+
+    /// Data cap per device or group (TARGET=SIZE[/PERIOD][@DAY])
+    #[arg(long = "quota", value_name = "SPEC", value_parser = quota::parse_quota)]
+    quotas: Vec<Quota>,
+
+    /// Threshold alert rule, e.g. "eth0 in > 800M for 10s => bell, syslog"
+    #[arg(long = "alert", value_name = "RULE", value_parser = alert::parse_rule)]
+    alerts: Vec<Rule>,
+
+    /// Detect traffic anomalies against a learned per-hour baseline (z-score threshold)
+    #[arg(long = "anomaly", value_name = "Z", num_args = 0..=1, default_missing_value = "4", value_parser = anomaly::parse_threshold)]
+    anomaly: Option<f64>,
+
+    /// Record every raw sample to a file for later --replay
+    #[arg(long = "record", value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Replay a file written by --record instead of monitoring live traffic
+    #[arg(long = "replay", value_name = "FILE", conflicts_with_all = ["record", "db", "npcap"])]
+    replay: Option<PathBuf>,
+
+    /// Analyze a pcap / pcapng capture file instead of monitoring live traffic
+    #[arg(
+        long = "read",
+        value_name = "FILE",
+        conflicts_with_all = ["replay", "record", "db", "npcap", "connect", "snmp", "exec", "node_exporter", "netns", "all_netns"]
+    )]
+    read: Option<PathBuf>,
+
+    /// Monitor remote hosts running `winload agent` instead of this machine (repeatable)
+    #[arg(long = "connect", value_name = "[NAME=]HOST:PORT", conflicts_with_all = ["replay", "npcap"])]
+    connect: Vec<String>,
+
+    /// Poll a router or switch over SNMP instead of this machine (repeatable)
+    #[arg(long = "snmp", value_name = "[NAME=]HOST[:PORT]", conflicts_with_all = ["replay", "npcap"])]
+    snmp: Vec<String>,
+
+    /// Run a command every interval and read counters from its output (repeatable)
+    #[arg(long = "exec", value_name = "[NAME=]COMMAND", conflicts_with_all = ["replay", "npcap"])]
+    exec: Vec<String>,
+
+    /// Scrape a Prometheus node_exporter instead of this machine (repeatable)
+    #[arg(long = "node-exporter", value_name = "[NAME=]HOST[:PORT]", conflicts_with_all = ["replay", "npcap"])]
+    node_exporter: Vec<String>,
+
+    /// Monitor another network namespace: a name from `ip netns`, a path or a PID (Linux, repeatable)
+    #[arg(long = "netns", value_name = "[NAME=]NETNS", conflicts_with_all = ["replay", "npcap"])]
+    netns: Vec<String>,
+
+    /// Monitor every network namespace, with the namespace name as device prefix (Linux)
+    #[arg(long = "all-netns", conflicts_with_all = ["netns", "replay", "npcap"])]
+    all_netns: bool,
+
+    /// Capture packets matching a filter expression as a virtual device, e.g. "tcp port 443" (Linux, repeatable)
+    #[arg(
+        long = "filter",
+        value_name = "[NAME=]EXPR",
+        conflicts_with_all = ["replay", "npcap", "connect", "snmp", "exec", "node_exporter", "netns", "all_netns"]
+    )]
+    filters: Vec<String>,
+
+    /// Capture packets for the flow table and protocol breakdown without adding --filter devices (Linux)
+    #[arg(
+        long = "capture",
+        conflicts_with_all = ["read", "replay", "npcap", "connect", "snmp", "exec", "node_exporter", "netns", "all_netns"]
+    )]
+    capture: bool,
+
+    /// Interface to capture on for --filter / --capture (default: all interfaces; with --read, an interface in the file)
+    #[arg(long = "capture-iface", value_name = "IFACE", requires = "packets")]
+    capture_iface: Option<String>,
+
+    /// SNMPv2c community
+    #[arg(long = "snmp-community", value_name = "STRING", default_value = "public")]
+    snmp_community: String,
+
+    /// Use SNMPv3 with this user instead of v2c
+    #[arg(long = "snmp-user", value_name = "NAME")]
+    snmp_user: Option<String>,
+
+    /// SNMPv3 authentication protocol (passphrase: $WINLOAD_SNMP_AUTH_PASS)
+    #[arg(long = "snmp-auth", value_name = "PROTO", value_enum, requires = "snmp_user")]
+    snmp_auth: Option<snmp::AuthProto>,
+
+    /// SNMPv3 privacy protocol (passphrase: $WINLOAD_SNMP_PRIV_PASS)
+    #[arg(long = "snmp-priv", value_name = "PROTO", value_enum, requires = "snmp_auth")]
+    snmp_priv: Option<snmp::PrivProto>,
+
+    /// Shared key file for agent / --connect authentication (default: $WINLOAD_KEY)
+    #[arg(long = "key-file", value_name = "PATH", global = true)]
+    key_file: Option<PathBuf>,
+
+    /// Print one record per interval per device to stdout instead of starting the TUI
+    #[arg(long = "output", value_name = "FORMAT", value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Run without the TUI (e.g. only serving --prometheus-listen)
+    #[arg(long = "headless")]
+    headless: bool,
+
+    /// Serve Prometheus metrics on ADDR (e.g. 127.0.0.1:9100) at /metrics
+    #[arg(long = "prometheus-listen", value_name = "ADDR")]
+    prometheus_listen: Option<String>,
+
+    /// Serve a live web dashboard on ADDR (e.g. 127.0.0.1:8080)
+    #[arg(long = "web", value_name = "ADDR")]
+    web: Option<String>,
+
+    /// Push statistics every interval to influx:URL, influx-udp:ADDR, graphite:ADDR, statsd:ADDR or otlp:URL
+    #[arg(long = "sink", value_name = "KIND:TARGET", value_parser = sink::parse_sink)]
+    sinks: Vec<Sink>,
+
+    /// Record traffic totals into a persistent database
+    #[arg(long = "db", value_name = "PATH", num_args = 0..=1)]
+    db: Option<Option<PathBuf>>,
+
     /// Display language
-    #[arg(long = "lang", value_enum, default_value = "en-us")]
+    #[arg(long = "lang", value_enum, default_value = "en-us", global = true)]
     lang: Lang,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print hourly/daily/monthly totals from the traffic database
+    Report {
+        /// Database path
+        #[arg(long = "db", value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// Only show devices matching this name (partial match)
+        #[arg(short = 'd', long = "device")]
+        device: Option<String>,
+    },
+    /// Serve this machine's counters to `winload --connect` clients
+    Agent {
+        /// Address to listen on
+        #[arg(long = "listen", value_name = "ADDR", default_value = "0.0.0.0:7681")]
+        listen: String,
+    },
+}
+
+/// 数据库落盘间隔
+const DB_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 重新扫描网卡列表和链路状态的间隔
+const LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 进入主循环前在 main 中打开的资源，出错时可以在启动 TUI 之前直接报告
+struct Resources {
+    source: Box<dyn Source>,
+    db: Option<TrafficDb>,
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+    exporter: Option<Exporter>,
+    web: Option<WebServer>,
+}
+
+/// 解析数据库路径：未指定时使用平台默认位置
+fn resolve_db_path(path: Option<PathBuf>) -> Result<PathBuf, String> {
+    path.or_else(db::default_path)
+        .ok_or_else(|| "Cannot determine default database path, please pass --db <PATH>".to_string())
 }
 
 // ─── App 状态 ──────────────────────────────────────────────
 
+/// 内容区显示的视图
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewMode {
+    /// 收发面板 (默认)
+    Traffic,
+    /// 告警列表
+    Alerts,
+    /// 事件日志
+    Events,
+    /// 连接流量表 (抓包时)
+    Flows,
+    /// 协议分布 (抓包时)
+    Protocols,
+}
+
+/// 标记标签的最大长度 (字符)
+const MAX_MARKER_LABEL: usize = 40;
+
+/// 用户在时间线上打的标记 (所有设备的图形共用)
+#[derive(Clone, Debug)]
+pub struct Marker {
+    /// 打标记时的 App tick，用于在图上定位
+    pub tick: u64,
+    pub time: DateTime<Local>,
+    /// 可选的标签，如 "started deploy"
+    pub label: String,
+}
+
 /// 单个网卡的视图状态
 pub struct DeviceView {
     pub info: DeviceInfo,
@@ -175,23 +445,114 @@ pub struct App {
     pub no_color: bool,
     pub loopback_mode: LoopbackMode,
     pub loopback_info: Option<String>,
+    pub quotas: Vec<Quota>,
+    pub alerts: AlertEngine,
+    /// 最近一次告警投递失败的信息
+    pub notify_error: Option<String>,
+    pub view_mode: ViewMode,
+    /// 异常检测 (--anomaly)
+    pub anomalies: Option<AnomalyDetector>,
+    pub events: EventLog,
+    /// 事件视图的滚动位置 (0 = 最新)
+    pub events_scroll: usize,
+    /// 抓包得到的连接流量表
+    pub flows: FlowTable,
+    /// 连接表的滚动位置 (0 = 第一行)
+    pub flows_scroll: usize,
+    /// 抓包得到的协议分布
+    pub protocols: ProtocolStats,
+    /// 来源在抓包 (--filter / --capture)，否则连接表和协议分布为空
+    pub capturing: bool,
+    /// update 次数，用于把异常区间等定位到图形的列
+    pub tick: u64,
+    /// 最近一次处理的样本的时间 (回放时为录制时的时间)
+    pub now: DateTime<Local>,
+    pub markers: Vec<Marker>,
+    /// 正在输入的标记标签 (按 M 打开)
+    pub prompt: Option<String>,
+    groups: Vec<Group>,
+    /// 新网卡的统计引擎参数
+    interval: u64,
+    average: u64,
+    /// 各物理网卡最近一次看到的链路状态
+    links: HashMap<String, LinkState>,
+    /// 设备属主标签 (veth 对端的容器或进程)
+    owners: HashMap<String, String>,
+    last_list_refresh: Instant,
+    notifier: Notifier,
     loopback_counters: Option<LoopbackCounters>,
-    collector: Collector,
+    source: Box<dyn Source>,
+    /// 时间起点，快照的 elapsed_secs 相对于它
+    start: Instant,
+    db: Option<TrafficDb>,
+    last_db_save: Instant,
+    recorder: Option<Recorder>,
+    /// 回放 (--replay)；存在时不采集实时数据
+    player: Option<Player>,
+    last_replay_step: Instant,
+    exporter: Option<Exporter>,
+    web: Option<WebServer>,
+    sinks: Sinks,
 }
 
 impl App {
-    fn new(args: &Args) -> Self {
-        let collector = Collector::new();
-        let devices = collector.devices();
+    fn new(args: &Args, res: Resources) -> Self {
+        let Resources {
+            source,
+            db,
+            recorder,
+            player,
+            exporter,
+            web,
+        } = res;
+        // 回放时网卡列表和采样间隔来自录制文件
+        let (devices, interval) = match player {
+            Some(ref p) => (p.rec.devices.clone(), p.rec.interval_ms),
+            None => (source.devices(), args.interval),
+        };
 
-        let views: Vec<DeviceView> = devices
+        let mut views: Vec<DeviceView> = devices
             .into_iter()
             .map(|info| DeviceView {
                 info,
-                engine: StatisticsEngine::new(args.interval, args.average),
+                engine: StatisticsEngine::new(interval, args.average),
             })
             .collect();
 
+        // 网卡组作为虚拟设备追加在物理网卡之后
+        for group in &args.groups {
+            views.push(DeviceView {
+                info: DeviceInfo {
+                    name: group.name.clone(),
+                    addrs: Vec::new(),
+                },
+                engine: StatisticsEngine::new(interval, args.average),
+            });
+        }
+
+        // 有数据库时，用其中本周期已记录的流量作为配额起始用量
+        let mut quotas = args.quotas.clone();
+        if let Some(ref db) = db {
+            for q in &mut quotas {
+                let members = args
+                    .groups
+                    .iter()
+                    .find(|g| g.name == q.target)
+                    .map(|g| db.ifaces.keys().filter(|name| g.contains(name)).cloned().collect())
+                    .unwrap_or_else(|| vec![q.target.clone()]);
+                q.seed_from_db(db, &members);
+            }
+        }
+
+        // 异常检测基线优先从数据库恢复，避免每次启动都重新学习
+        let anomalies = args.anomaly.map(|z| {
+            let mut detector = AnomalyDetector::new(z);
+            if let Some(ref db) = db {
+                detector.load(db.baselines());
+            }
+            detector
+        });
+
         // 如果指定了默认设备，定位到对应索引
         let mut current_idx = 0;
         if let Some(ref name) = args.device {
@@ -204,6 +565,13 @@ impl App {
             }
         }
 
+        let links = views
+            .iter()
+            .filter(|_| player.is_none())
+            .filter_map(|v| source.link_state(&v.info.name).map(|l| (v.info.name.clone(), l)))
+            .collect();
+        let owners = if player.is_none() { source.owners() } else { HashMap::new() };
+
         let loopback_mode = if args.npcap {
             LoopbackMode::Npcap
         } else {
@@ -225,21 +593,92 @@ impl App {
             no_color: args.no_color,
             loopback_mode,
             loopback_info: None,
+            quotas,
+            alerts: AlertEngine::new(args.alerts.clone()),
+            notify_error: None,
+            view_mode: ViewMode::Traffic,
+            anomalies,
+            events: EventLog::default(),
+            events_scroll: 0,
+            flows: FlowTable::new(),
+            flows_scroll: 0,
+            protocols: ProtocolStats::new(),
+            // 回放时只有抓包文件 (--read) 带有连接和协议数据
+            capturing: player.as_ref().map_or(source.capturing(), |p| p.rec.captured),
+            tick: 0,
+            now: Local::now(),
+            markers: Vec::new(),
+            prompt: None,
+            groups: args.groups.clone(),
+            interval,
+            average: args.average,
+            links,
+            owners,
+            last_list_refresh: Instant::now(),
+            notifier: Notifier::new(!args.headless && args.output.is_none()),
             loopback_counters: None,
-            collector,
+            source,
+            start: Instant::now(),
+            db,
+            last_db_save: Instant::now(),
+            recorder,
+            player,
+            last_replay_step: Instant::now(),
+            exporter,
+            web,
+            sinks: Sinks::start(&args.sinks),
         }
     }
 
+    /// 自启动以来的秒数
+    fn elapsed_secs(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// 远程数据源的说明 (主机名、连接状态)，本机为 None
+    pub fn source_label(&self) -> Option<String> {
+        self.source.label()
+    }
+
+    /// 回放状态 (非回放模式为 None)
+    pub fn player(&self) -> Option<&Player> {
+        self.player.as_ref()
+    }
+
+    /// 设备的属主标签 (veth 对端的容器或进程)
+    pub fn owner(&self, name: &str) -> Option<&str> {
+        self.owners.get(name).map(String::as_str)
+    }
+
     pub fn current_view(&self) -> Option<&DeviceView> {
         self.views.get(self.current_idx)
     }
 
+    /// 当前设备对应的配额 (如果配置了)
+    pub fn current_quota(&self) -> Option<&Quota> {
+        let view = self.current_view()?;
+        self.quotas.iter().find(|q| q.target == view.info.name)
+    }
+
     fn update(&mut self) {
-        let mut snapshots = self.collector.collect();
+        if self.player.is_some() {
+            self.update_replay();
+            return;
+        }
+
+        if self.last_list_refresh.elapsed() >= LIST_REFRESH_INTERVAL {
+            self.source.refresh_list();
+            self.sync_devices();
+            self.poll_links();
+            self.owners = self.source.owners();
+            self.last_list_refresh = Instant::now();
+        }
+
+        let mut snapshots = self.source.collect();
 
         // 如果启用了回环捕获，用实时计数器覆盖 loopback 的假数据
         if let Some(ref counters) = self.loopback_counters {
-            let elapsed = self.collector.elapsed_secs();
+            let elapsed = self.elapsed_secs();
             for (name, snap) in snapshots.iter_mut() {
                 if name.to_lowercase().contains("loopback") {
                     snap.bytes_recv = counters.get_recv();
@@ -249,11 +688,385 @@ impl App {
             }
         }
 
+        let now = Local::now();
+        let elapsed = self.elapsed_secs();
+        if self.capturing {
+            let mut flows = self.source.take_flows();
+            let mut protocols = self.source.take_protocols();
+            if let Some(ref counters) = self.loopback_counters {
+                for (key, [rx, tx]) in counters.take_flows() {
+                    let bytes = flows.entry(key).or_default();
+                    bytes[0] += rx;
+                    bytes[1] += tx;
+                }
+                protocols.merge(&counters.take_protocols());
+            }
+            self.flows.update(flows, elapsed);
+            self.protocols.update(protocols, elapsed);
+        }
+        for (source, err) in self.source.take_errors() {
+            self.events.push(now, EventKind::Error, &source, err);
+        }
+
+        if let Some(ref mut recorder) = self.recorder {
+            let devices: Vec<DeviceInfo> = self.views.iter().map(|v| v.info.clone()).collect();
+            if let Err(e) = recorder.write_frame(elapsed, &snapshots, &devices) {
+                // 写失败 (磁盘满等) 时停止录制，不影响监控
+                self.events.push(now, EventKind::Error, "record", format!("Recording stopped: {e}"));
+                self.recorder = None;
+            }
+        }
+
+        // 数据库只记录物理网卡，组在之后合成
+        if let Some(ref mut db) = self.db {
+            for (name, snap) in &snapshots {
+                db.record(name, snap.bytes_recv, snap.bytes_sent, now);
+            }
+            if self.last_db_save.elapsed() >= DB_SAVE_INTERVAL {
+                if let Some(ref detector) = self.anomalies {
+                    db.set_baselines(detector.baselines());
+                }
+                // 周期保存失败不打断 TUI，退出时的最终保存会报告错误
+                let _ = db.save();
+                self.last_db_save = Instant::now();
+            }
+        }
+
+        self.process(snapshots, elapsed, now);
+    }
+
+    /// 把一次采集 (实时或回放) 的快照送进统计、异常检测、配额和告警
+    ///
+    /// `elapsed` 为快照的单调时间 (秒)，`now` 为快照的墙上时间
+    fn process(&mut self, mut snapshots: HashMap<String, Snapshot>, elapsed: f64, now: DateTime<Local>) {
+        self.tick += 1;
+        self.now = now;
+        let pushed_before = self.events.pushed();
+
+        for group in &mut self.groups {
+            if let Some(snap) = group.aggregate(&snapshots) {
+                snapshots.insert(group.name.clone(), snap);
+            }
+        }
+
         for view in &mut self.views {
             if let Some(snap) = snapshots.get(&view.info.name) {
-                view.engine.update(snap.clone());
+                if view.engine.update(snap.clone()) {
+                    self.events.push(now, EventKind::CounterReset, &view.info.name, t("event_counter_reset_msg").to_string());
+                }
             }
         }
+
+        self.detect_anomalies();
+
+        for quota in &mut self.quotas {
+            if let Some(view) = self.views.iter().find(|v| v.info.name == quota.target) {
+                quota.update(&view.engine, now);
+            }
+        }
+
+        for event in self.alerts.evaluate(elapsed, now, &self.views, &self.quotas) {
+            let rule = &self.alerts.rules[event.rule];
+            // 回放的是过去的数据，不再投递通知
+            if self.player.is_none() {
+                self.notifier.dispatch(rule, &event);
+            }
+            let kind = if event.firing { EventKind::AlertFiring } else { EventKind::AlertCleared };
+            self.events.push(now, kind, &event.device, rule.text.clone());
+        }
+        for err in self.notifier.drain_errors() {
+            self.events.push(now, EventKind::Error, "notify", err.clone());
+            self.notify_error = Some(err);
+        }
+        for err in self.sinks.drain_errors() {
+            self.events.push(now, EventKind::Error, "sink", err);
+        }
+        if let Some(err) = self.loopback_counters.as_ref().and_then(|c| c.take_error()) {
+            self.events.push(now, EventKind::Error, "npcap", err);
+        }
+
+        // 正在往回翻看时，新事件不应把视图内容顶走
+        if self.events_scroll > 0 {
+            let added = (self.events.pushed() - pushed_before) as usize;
+            self.events_scroll = (self.events_scroll + added).min(self.events.len().saturating_sub(1));
+        }
+
+        if let Some(ref exporter) = self.exporter {
+            exporter.publish(self);
+        }
+        if let Some(ref web) = self.web {
+            web.publish(self);
+        }
+        // 回放的是过去的数据，不推送到外部
+        if self.player.is_none() {
+            self.sinks.publish(&self.views, now);
+        }
+    }
+
+    /// 回放: 按真实经过的时间推进播放位置，播放到期的帧
+    fn update_replay(&mut self) {
+        let real = self.last_replay_step.elapsed().as_secs_f64();
+        self.last_replay_step = Instant::now();
+        if let Some(ref mut player) = self.player {
+            let due = player.advance(real);
+            self.play_frames(due);
+        }
+    }
+
+    /// 依次处理录制文件中的若干帧，并还原期间打下的标记
+    fn play_frames(&mut self, frames: Range<usize>) {
+        for i in frames {
+            let Some(ref player) = self.player else {
+                return;
+            };
+            let rec = &player.rec;
+            let frame = &rec.frames[i];
+            let prev_t = i.checked_sub(1).map(|p| rec.frames[p].t);
+            let labels: Vec<String> = rec
+                .marks
+                .iter()
+                .filter(|(t, _)| prev_t.is_none_or(|p| *t > p) && *t <= frame.t)
+                .map(|(_, label)| label.clone())
+                .collect();
+            let snapshots = rec.snapshots(frame);
+            let (flows, protocols) = (frame.flows.clone(), frame.protocols);
+            let (t, now) = (frame.t, player.wall_time(frame.t));
+
+            if self.capturing {
+                self.flows.update(flows, t);
+                self.protocols.update(protocols, t);
+            }
+            self.process(snapshots, t, now);
+            for label in labels {
+                self.add_marker(label);
+            }
+        }
+    }
+
+    /// 回放跳转；往回跳时清空所有状态后从头重放到目标位置
+    fn seek(&mut self, delta: f64) {
+        let Some(ref mut player) = self.player else {
+            return;
+        };
+        if player.seek(delta) {
+            self.reset_state();
+        }
+        let due = self.player.as_mut().map(|p| p.due()).unwrap_or_default();
+        self.play_frames(due);
+    }
+
+    /// 清空统计、告警、事件和标记，回到刚启动时的状态
+    fn reset_state(&mut self) {
+        for view in &mut self.views {
+            view.engine = StatisticsEngine::new(self.interval, self.average);
+        }
+        for quota in &mut self.quotas {
+            quota.reset();
+        }
+        for group in &mut self.groups {
+            group.reset();
+        }
+        self.alerts = AlertEngine::new(std::mem::take(&mut self.alerts.rules));
+        if let Some(ref mut detector) = self.anomalies {
+            detector.reset();
+        }
+        self.flows.clear();
+        self.flows_scroll = 0;
+        self.protocols = ProtocolStats::new();
+        self.events = EventLog::default();
+        self.events_scroll = 0;
+        self.markers.clear();
+        self.tick = 0;
+    }
+
+    /// 按最新的网卡列表增删物理网卡视图，记录网卡出现 / 消失事件
+    fn sync_devices(&mut self) {
+        let current_name = self.current_view().map(|v| v.info.name.clone());
+        let devices = self.source.devices();
+        let is_group = |name: &str| self.groups.iter().any(|g| g.name == name);
+
+        let mut removed = Vec::new();
+        self.views.retain(|v| {
+            let keep = is_group(&v.info.name) || devices.iter().any(|d| d.name == v.info.name);
+            if !keep {
+                removed.push(v.info.name.clone());
+            }
+            keep
+        });
+        for name in removed {
+            self.links.remove(&name);
+            self.events.push(self.now, EventKind::DeviceRemoved, &name, String::new());
+        }
+
+        for dev in devices {
+            if let Some(view) = self.views.iter_mut().find(|v| v.info.name == dev.name) {
+                view.info.addrs = dev.addrs;
+                continue;
+            }
+            // 物理网卡按名称排序，组始终排在最后
+            let pos = self
+                .views
+                .iter()
+                .position(|v| is_group(&v.info.name) || v.info.name > dev.name)
+                .unwrap_or(self.views.len());
+            if let Some(link) = self.source.link_state(&dev.name) {
+                self.links.insert(dev.name.clone(), link);
+            }
+            self.events.push(self.now, EventKind::DeviceAdded, &dev.name, dev.addrs.join(", "));
+            self.views.insert(
+                pos,
+                DeviceView {
+                    info: dev,
+                    engine: StatisticsEngine::new(self.interval, self.average),
+                },
+            );
+        }
+
+        // 保持当前选中的网卡不变；它消失时停在原位置附近
+        self.current_idx = current_name
+            .and_then(|name| self.views.iter().position(|v| v.info.name == name))
+            .unwrap_or(self.current_idx)
+            .min(self.views.len().saturating_sub(1));
+    }
+
+    /// 检查物理网卡的链路状态，记录 up / down 和速率变化
+    fn poll_links(&mut self) {
+        for view in &self.views {
+            let name = &view.info.name;
+            let Some(link) = self.source.link_state(name) else {
+                continue;
+            };
+            let Some(prev) = self.links.insert(name.clone(), link.clone()) else {
+                continue;
+            };
+            let speed = |l: &LinkState| {
+                l.speed_mbps
+                    .map(|s| format!("{s} Mbit/s"))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            if prev.is_up() != link.is_up() {
+                let kind = if link.is_up() { EventKind::LinkUp } else { EventKind::LinkDown };
+                let msg = match link.speed_mbps {
+                    Some(_) => format!("{} → {}, {}", prev.oper, link.oper, speed(&link)),
+                    None => format!("{} → {}", prev.oper, link.oper),
+                };
+                self.events.push(self.now, kind, name, msg);
+            } else if link.is_up() && prev.speed_mbps != link.speed_mbps {
+                self.events.push(self.now, EventKind::LinkSpeed, name, format!("{} → {}", speed(&prev), speed(&link)));
+            }
+        }
+    }
+
+    /// 在当前时刻打一个标记，同时写入事件日志
+    fn add_marker(&mut self, label: String) {
+        if let Some(ref mut recorder) = self.recorder {
+            let _ = recorder.mark(self.start.elapsed().as_secs_f64(), &label);
+        }
+        let marker = Marker {
+            tick: self.tick,
+            time: self.now,
+            label,
+        };
+        self.events.push(self.now, EventKind::Marker, "*", marker.label.clone());
+        self.markers.push(marker);
+    }
+
+    /// 标签输入框的按键处理
+    fn prompt_key(&mut self, code: KeyCode) {
+        let Some(ref mut input) = self.prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => {
+                let label = input.trim().to_string();
+                self.prompt = None;
+                self.add_marker(label);
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if input.chars().count() < MAX_MARKER_LABEL => input.push(c),
+            _ => {}
+        }
+    }
+
+    /// 滚动事件视图，`delta` 为正表示往更早的事件翻
+    fn scroll_events(&mut self, delta: isize) {
+        let max = self.events.len().saturating_sub(1);
+        self.events_scroll = self.events_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// 滚动连接表，`delta` 为正表示往下翻
+    fn scroll_flows(&mut self, delta: isize) {
+        let max = self.flows.len().saturating_sub(1);
+        self.flows_scroll = self.flows_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// 把各设备的当前速率喂给异常检测器，异常开始 / 结束时写入事件日志
+    fn detect_anomalies(&mut self) {
+        let Some(ref mut detector) = self.anomalies else {
+            return;
+        };
+        for view in &self.views {
+            // 引擎至少有两个样本后 current 才有意义
+            if view.engine.incoming_history.is_empty() {
+                continue;
+            }
+            let name = &view.info.name;
+            for (incoming, value) in [(true, view.engine.incoming.current), (false, view.engine.outgoing.current)] {
+                let Some(event) = detector.observe(name, incoming, value, self.now, self.tick) else {
+                    continue;
+                };
+                let a = &event.anomaly;
+                let dir = if incoming { t("incoming") } else { t("outgoing") };
+                let (kind, message) = if event.started {
+                    let msg = format!(
+                        "{dir}: {} ({} ~{}, z = {:.1})",
+                        stats::format_speed_unit(a.value, self.unit),
+                        t("anomaly_expected"),
+                        stats::format_speed_unit(a.expected, self.unit),
+                        a.z,
+                    );
+                    (EventKind::AnomalyStart, msg)
+                } else {
+                    let msg = format!(
+                        "{dir}: {} ({} {})",
+                        t("anomaly_ended"),
+                        t("anomaly_peak"),
+                        stats::format_speed_unit(a.value, self.unit),
+                    );
+                    (EventKind::AnomalyEnd, msg)
+                };
+                self.events.push(self.now, kind, name, message);
+            }
+        }
+    }
+
+    /// 切换到指定视图；已处于该视图时回到收发面板
+    fn toggle_view(&mut self, mode: ViewMode) {
+        self.view_mode = if self.view_mode == mode {
+            ViewMode::Traffic
+        } else {
+            mode
+        };
+    }
+
+    /// 退出前把数据库和录制文件写回磁盘，并把推送队列中剩余的数据发出去
+    fn save_db(&mut self) -> io::Result<()> {
+        self.sinks.shutdown();
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.flush()?;
+        }
+        match self.db {
+            Some(ref mut db) => {
+                if let Some(ref detector) = self.anomalies {
+                    db.set_baselines(detector.baselines());
+                }
+                db.save()
+            }
+            None => Ok(()),
+        }
     }
 
     fn next_device(&mut self) {
@@ -271,28 +1084,84 @@ impl App {
 
 // ─── 主循环 ────────────────────────────────────────────────
 
-fn run(terminal: &mut ratatui::DefaultTerminal, args: Args) -> io::Result<()> {
-    let mut app = App::new(&args);
+fn run(terminal: &mut ratatui::DefaultTerminal, args: Args, res: Resources) -> io::Result<()> {
+    let mut app = App::new(&args, res);
+    let result = run_loop(terminal, &args, &mut app);
+    let saved = app.save_db();
+    result.and(saved)
+}
 
-    // 启动回环捕获 (如果指定了 --npcap 或 --etw)
-    if app.loopback_mode != LoopbackMode::None {
-        let counters = LoopbackCounters::new();
-        let result = match app.loopback_mode {
-            LoopbackMode::Npcap => loopback::platform::start_npcap(counters.clone()),
-            LoopbackMode::None => unreachable!(),
-        };
-        match result {
-            Ok(info_msg) => {
-                app.loopback_info = Some(info_msg);
-                app.loopback_counters = Some(counters);
-            }
-            Err(e) => {
-                // 恢复终端后打印错误
-                ratatui::restore();
-                eprintln!("Error: Failed to start loopback capture:\n{e}");
-                std::process::exit(1);
-            }
-        }
+/// 打开计数器来源: 默认为本机；--connect / --snmp / --exec / --node-exporter / --netns 时为远程主机、
+/// 网络设备、外部命令、node_exporter 和其他网络命名空间，等到第一次连接成功，认证失败等错误在启动 TUI 之前报告。
+/// 有 --filter 时再加上抓包得到的虚拟设备，--capture 时只抓包统计连接 (--read 时过滤的是文件中的包，不在这里抓包)。
+fn open_source(args: &Args) -> Result<Box<dyn Source>, String> {
+    let source = open_counters(args)?;
+    if (args.filters.is_empty() && !args.capture) || args.read.is_some() {
+        return Ok(source);
+    }
+    capture::attach(source, args.capture_iface.as_deref(), &args.filters)
+}
+
+fn open_counters(args: &Args) -> Result<Box<dyn Source>, String> {
+    let count = args.connect.len() + args.snmp.len() + args.exec.len() + args.node_exporter.len() + args.netns.len();
+    if count == 0 && !args.all_netns {
+        return Ok(Box::new(Collector::new()));
+    }
+    let start = Instant::now();
+    let multi = count > 1 || args.all_netns;
+    let mut sources = Vec::new();
+    if !args.connect.is_empty() {
+        let key = remote::load_key(args.key_file.as_deref())?;
+        sources.extend(remote::connect_all(&args.connect, key, args.interval, start, multi)?);
+    }
+    if !args.snmp.is_empty() {
+        let creds = snmp::credentials(
+            &args.snmp_community,
+            args.snmp_user.as_deref(),
+            args.snmp_auth,
+            args.snmp_priv,
+        )?;
+        sources.extend(snmp::connect_all(&args.snmp, &creds, args.interval, start, multi)?);
+    }
+    if !args.exec.is_empty() {
+        sources.extend(exec::start_all(&args.exec, args.interval, start, multi)?);
+    }
+    if !args.node_exporter.is_empty() {
+        sources.extend(node_exporter::connect_all(&args.node_exporter, args.interval, start, multi)?);
+    }
+    if !args.netns.is_empty() {
+        sources.extend(netns::connect_all(&args.netns, args.interval, start, multi)?);
+    }
+    if args.all_netns {
+        sources.extend(netns::discover_all(args.interval, start)?);
+    }
+    source::combine(sources)
+}
+
+/// 启动回环捕获 (如果指定了 --npcap)
+fn start_loopback(app: &mut App) -> Result<(), String> {
+    if app.loopback_mode == LoopbackMode::None {
+        return Ok(());
+    }
+    let counters = LoopbackCounters::new();
+    let result = match app.loopback_mode {
+        LoopbackMode::Npcap => loopback::platform::start_npcap(counters.clone()),
+        LoopbackMode::None => unreachable!(),
+    };
+    let info_msg = result?;
+    app.loopback_info = Some(info_msg);
+    app.loopback_counters = Some(counters);
+    // 回环上的包也进连接表和协议分布
+    app.capturing |= app.player.is_none();
+    Ok(())
+}
+
+fn run_loop(terminal: &mut ratatui::DefaultTerminal, args: &Args, app: &mut App) -> io::Result<()> {
+    if let Err(e) = start_loopback(app) {
+        // 恢复终端后打印错误
+        ratatui::restore();
+        eprintln!("Error: Failed to start loopback capture:\n{e}");
+        std::process::exit(1);
     }
 
     let tick_rate = Duration::from_millis(args.interval);
@@ -302,7 +1171,7 @@ fn run(terminal: &mut ratatui::DefaultTerminal, args: Args) -> io::Result<()> {
     app.update();
 
     loop {
-        terminal.draw(|frame| ui::draw(frame, &app))?;
+        terminal.draw(|frame| ui::draw(frame, app))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
@@ -312,7 +1181,10 @@ fn run(terminal: &mut ratatui::DefaultTerminal, args: Args) -> io::Result<()> {
             if let Event::Key(key) = event::read()? {
                 // Windows 下 crossterm 会产生 Press + Release，只处理 Press
                 if key.kind == KeyEventKind::Press {
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
                     match key.code {
+                        // 输入标记标签时，除 Ctrl+C 外的按键都交给输入框
+                        _ if app.prompt.is_some() && !ctrl_c => app.prompt_key(key.code),
                         KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                             return Ok(());
                         }
@@ -327,6 +1199,60 @@ fn run(terminal: &mut ratatui::DefaultTerminal, args: Args) -> io::Result<()> {
                         KeyCode::Char('c') => {
                             app.no_color = !app.no_color;
                         }
+                        KeyCode::Char('a') => {
+                            app.toggle_view(ViewMode::Alerts);
+                        }
+                        KeyCode::Char('e') => {
+                            app.toggle_view(ViewMode::Events);
+                        }
+                        KeyCode::Char('f') => {
+                            app.toggle_view(ViewMode::Flows);
+                        }
+                        KeyCode::Char('p') => {
+                            app.toggle_view(ViewMode::Protocols);
+                        }
+                        KeyCode::Char('s') if app.view_mode == ViewMode::Flows => {
+                            app.flows.sort = app.flows.sort.next();
+                        }
+                        KeyCode::Char('m') => {
+                            app.add_marker(String::new());
+                        }
+                        KeyCode::Char('M') => {
+                            app.prompt = Some(String::new());
+                        }
+                        // 回放控制
+                        KeyCode::Char(' ') if app.player.is_some() => {
+                            if let Some(ref mut p) = app.player {
+                                p.paused = !p.paused;
+                            }
+                        }
+                        KeyCode::Char('+') if app.player.is_some() => {
+                            if let Some(ref mut p) = app.player {
+                                p.faster();
+                            }
+                        }
+                        KeyCode::Char('-') if app.player.is_some() => {
+                            if let Some(ref mut p) = app.player {
+                                p.slower();
+                            }
+                        }
+                        KeyCode::Char('[') => app.seek(-10.0),
+                        KeyCode::Char(']') => app.seek(10.0),
+                        KeyCode::Char('{') => app.seek(-60.0),
+                        KeyCode::Char('}') => app.seek(60.0),
+                        // 事件视图和连接表中 ↑/↓ 用于滚动，←/→ 仍然切换网卡
+                        KeyCode::Up if app.view_mode == ViewMode::Events => app.scroll_events(-1),
+                        KeyCode::Down if app.view_mode == ViewMode::Events => app.scroll_events(1),
+                        KeyCode::PageUp if app.view_mode == ViewMode::Events => app.scroll_events(-10),
+                        KeyCode::PageDown if app.view_mode == ViewMode::Events => app.scroll_events(10),
+                        KeyCode::Home if app.view_mode == ViewMode::Events => app.events_scroll = 0,
+                        KeyCode::End if app.view_mode == ViewMode::Events => app.scroll_events(isize::MAX),
+                        KeyCode::Up if app.view_mode == ViewMode::Flows => app.scroll_flows(-1),
+                        KeyCode::Down if app.view_mode == ViewMode::Flows => app.scroll_flows(1),
+                        KeyCode::PageUp if app.view_mode == ViewMode::Flows => app.scroll_flows(-10),
+                        KeyCode::PageDown if app.view_mode == ViewMode::Flows => app.scroll_flows(10),
+                        KeyCode::Home if app.view_mode == ViewMode::Flows => app.flows_scroll = 0,
+                        KeyCode::End if app.view_mode == ViewMode::Flows => app.scroll_flows(isize::MAX),
                         KeyCode::Right | KeyCode::Down | KeyCode::Tab | KeyCode::Enter => {
                             app.next_device();
                         }
@@ -346,6 +1272,92 @@ fn run(terminal: &mut ratatui::DefaultTerminal, args: Args) -> io::Result<()> {
     }
 }
 
+// ─── 非交互输出 ────────────────────────────────────────────
+
+/// --output / --headless 模式: 不进入 TUI；指定了 --output 时每次采集后为每个设备打印一条记录
+///
+/// 实时模式下运行到 Ctrl-C；回放录制文件时不等待，直接把所有帧导出后退出。
+fn run_headless(args: Args, format: Option<OutputFormat>, res: Resources) -> io::Result<()> {
+    let mut app = App::new(&args, res);
+    if let Err(e) = start_loopback(&mut app) {
+        eprintln!("Error: Failed to start loopback capture:\n{e}");
+        std::process::exit(1);
+    }
+
+    // 进程被 Ctrl-C / SIGTERM 打断时也要保存数据库和录制文件
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+        if let Err(e) = ctrlc::set_handler(move || stop.store(true, Ordering::Relaxed)) {
+            eprintln!("Warning: cannot install Ctrl-C handler: {e}");
+        }
+    }
+
+    let mut writer = format.map(|f| RecordWriter::new(io::stdout().lock(), f));
+    let replay_frames = app.player().map(|p| p.rec.frames.len());
+    let result = match replay_frames {
+        Some(frames) => {
+            (0..frames)
+                .take_while(|_| !stop.load(Ordering::Relaxed))
+                .try_for_each(|i| {
+                    let marked = app.markers.len();
+                    app.play_frames(i..i + 1);
+                    match writer {
+                        Some(ref mut w) => write_records(w, &app, args.device.as_deref(), marked),
+                        None => Ok(()),
+                    }
+                })
+        }
+        None => {
+            let tick_rate = Duration::from_millis(args.interval);
+            let mut result = Ok(());
+            while !stop.load(Ordering::Relaxed) {
+                app.update();
+                if let Some(ref mut w) = writer {
+                    result = write_records(w, &app, args.device.as_deref(), app.markers.len());
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                std::thread::sleep(tick_rate);
+            }
+            result
+        }
+    };
+
+    // 下游 (head 等) 提前关闭管道属于正常结束
+    let result = match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    };
+    let saved = app.save_db();
+    result.and(saved)
+}
+
+/// 打印一轮记录: 自 `marked` 以来新增的标记，以及已有速率数据的设备
+/// (`device` 指定时只输出名称包含该字符串的设备)
+fn write_records<W: io::Write>(
+    writer: &mut RecordWriter<W>,
+    app: &App,
+    device: Option<&str>,
+    marked: usize,
+) -> io::Result<()> {
+    for marker in &app.markers[marked.min(app.markers.len())..] {
+        writer.marker(marker.time, &marker.label)?;
+    }
+    let filter = device.map(str::to_lowercase);
+    for view in &app.views {
+        if view.engine.incoming_history.is_empty() {
+            continue;
+        }
+        if filter.as_ref().is_some_and(|f| !view.info.name.to_lowercase().contains(f)) {
+            continue;
+        }
+        writer.record(app.now, view)?;
+    }
+    writer.flush()
+}
+
 // ─── 入口 ──────────────────────────────────────────────────
 
 fn print_system_info() {
@@ -402,7 +1414,43 @@ fn build_translated_command() -> clap::Command {
         .mut_arg("hide_separator", |a| a.help(t("help_hide_separator")))
         .mut_arg("no_color", |a| a.help(t("help_no_color")))
         .mut_arg("npcap", |a| a.help(t("help_npcap")))
+        .mut_arg("groups", |a| a.help(t("help_group")))
+        .mut_arg("quotas", |a| a.help(t("help_quota")))
+        .mut_arg("alerts", |a| a.help(t("help_alert")))
+        .mut_arg("anomaly", |a| a.help(t("help_anomaly")))
+        .mut_arg("record", |a| a.help(t("help_record")))
+        .mut_arg("replay", |a| a.help(t("help_replay")))
+        .mut_arg("read", |a| a.help(t("help_read")))
+        .mut_arg("output", |a| a.help(t("help_output")))
+        .mut_arg("headless", |a| a.help(t("help_headless")))
+        .mut_arg("prometheus_listen", |a| a.help(t("help_prometheus_listen")))
+        .mut_arg("web", |a| a.help(t("help_web")))
+        .mut_arg("sinks", |a| a.help(t("help_sink")))
+        .mut_arg("connect", |a| a.help(t("help_connect")))
+        .mut_arg("snmp", |a| a.help(t("help_snmp")))
+        .mut_arg("exec", |a| a.help(t("help_exec")))
+        .mut_arg("node_exporter", |a| a.help(t("help_node_exporter")))
+        .mut_arg("netns", |a| a.help(t("help_netns")))
+        .mut_arg("all_netns", |a| a.help(t("help_all_netns")))
+        .mut_arg("filters", |a| a.help(t("help_filter")))
+        .mut_arg("capture", |a| a.help(t("help_capture")))
+        .mut_arg("capture_iface", |a| a.help(t("help_capture_iface")))
+        .mut_arg("snmp_community", |a| a.help(t("help_snmp_community")))
+        .mut_arg("snmp_user", |a| a.help(t("help_snmp_user")))
+        .mut_arg("snmp_auth", |a| a.help(t("help_snmp_auth")))
+        .mut_arg("snmp_priv", |a| a.help(t("help_snmp_priv")))
+        .mut_arg("key_file", |a| a.help(t("help_key_file")))
+        .mut_arg("db", |a| a.help(t("help_db")))
         .mut_arg("lang", |a| a.help(t("help_lang")))
+        .mut_subcommand("report", |c| {
+            c.about(t("help_report"))
+                .mut_arg("db", |a| a.help(t("help_report_db")))
+                .mut_arg("device", |a| a.help(t("help_report_device")))
+        })
+        .mut_subcommand("agent", |c| {
+            c.about(t("help_agent"))
+                .mut_arg("listen", |a| a.help(t("help_agent_listen")))
+        })
 }
 
 fn main() -> io::Result<()> {
@@ -415,6 +1463,30 @@ fn main() -> io::Result<()> {
     let args = Args::from_arg_matches(&matches)
         .unwrap_or_else(|e| e.exit());
 
+    if let Some(Command::Report { db, device }) = args.command {
+        let path = resolve_db_path(db).unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        });
+        match TrafficDb::open(&path) {
+            Ok(db) => db::print_report(&db, device.as_deref()),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Agent { ref listen }) = args.command {
+        let result = remote::load_key(args.key_file.as_deref()).and_then(|key| remote::run_agent(listen, key));
+        if let Err(e) = result {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // 如果传入 --debug-info，打印接口信息后退出
     if args.debug_info {
         let collector = Collector::new();
@@ -427,9 +1499,130 @@ fn main() -> io::Result<()> {
         }
         return Ok(());
     }
+
+    // 在进入 TUI 之前打开数据库，出错时可以直接打印
+    let db = match args.db.clone() {
+        Some(path) => {
+            let opened = resolve_db_path(path).and_then(|p| TrafficDb::open(&p));
+            match opened {
+                Ok(db) => Some(db),
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let recorder = match args.record {
+        Some(ref path) => match Recorder::create(path, args.interval) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let player = match args.replay {
+        Some(ref path) => match Recording::load(path) {
+            Ok(rec) => Some(Player::new(rec)),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // 抓包文件按包的时间戳分桶成录制，之后和 --replay 一样回放
+    let (player, summary) = match args.read {
+        Some(ref path) => match pcap::load(path, args.interval, &args.filters, args.capture_iface.as_deref()) {
+            Ok((rec, summary)) => (Some(Player::new(rec)), Some(summary)),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => (player, None),
+    };
+
+    let exporter = match args.prometheus_listen {
+        Some(ref addr) => match Exporter::start(addr) {
+            Ok((exporter, local)) => {
+                if args.headless || args.output.is_some() {
+                    eprintln!("Serving Prometheus metrics on http://{local}/metrics");
+                }
+                Some(exporter)
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let web = match args.web {
+        Some(ref addr) => match WebServer::start(addr, args.unit) {
+            Ok((web, local)) => {
+                if args.headless || args.output.is_some() {
+                    eprintln!("Serving web dashboard on http://{local}/");
+                }
+                Some(web)
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // 远程模式先等到第一次连接成功，认证失败等错误在启动 TUI 之前报告
+    let source = match open_source(&args) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let res = Resources {
+        source,
+        db,
+        recorder,
+        player,
+        exporter,
+        web,
+    };
+    if args.headless || args.output.is_some() {
+        let format = args.output;
+        let unit = args.unit;
+        let result = run_headless(args, format, res);
+        // --output 的记录占用 stdout，汇总报告写到 stderr
+        if let Some(summary) = summary {
+            let printed = match format {
+                Some(_) => summary.print(&mut io::stderr(), unit),
+                None => summary.print(&mut io::stdout(), unit),
+            };
+            // 和记录一样，下游提前关闭管道不算错误
+            if let Err(e) = printed {
+                if e.kind() != io::ErrorKind::BrokenPipe {
+                    return Err(e);
+                }
+            }
+        }
+        return result;
+    }
+
+    let unit = args.unit;
     let mut terminal = ratatui::init();
-    let result = run(&mut terminal, args);
+    let result = run(&mut terminal, args, res);
     ratatui::restore();
+    if let Some(summary) = summary {
+        summary.print(&mut io::stdout(), unit)?;
+    }
     print_system_info();
     result
 }
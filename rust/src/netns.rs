@@ -0,0 +1,391 @@
+//! 网络命名空间 (--netns / --all-netns，仅 Linux)
+//!
+//! 读取其他网络命名空间里的网卡计数器，调试容器和 CNI 时不必再用 nsenter 包一层:
+//!
+//! - PID: 读取 `/proc/PID/net/dev`，即该进程所在命名空间的计数器，不需要特权
+//! - 名称 (`/run/netns/NAME`，即 `ip netns add` 创建的) 或命名空间文件路径:
+//!   后台线程用 setns 进入该命名空间后读取 `/proc/thread-self/net/dev`，需要 CAP_SYS_ADMIN
+//!
+//! 链路状态和地址来自 /sys/class/net 和 sysinfo，只反映本机所在的命名空间，
+//! 这里不提供。
+//!
+//! 本机的 veth 网卡会反查对端所在的命名空间，显示属主 (容器名或进程)，见 veth_owners。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::source::Named;
+
+/// 监控 `--netns [NAME=]NETNS`，NETNS 为名称、路径或 PID；返回 (前缀, 来源)
+#[cfg(target_os = "linux")]
+pub fn connect_all(specs: &[String], interval_ms: u64, start: Instant, multi: bool) -> Result<Vec<Named>, String> {
+    let mut feeds = Vec::new();
+    for s in specs {
+        let (name, spec) = crate::source::parse_target(s);
+        let netns = linux::Netns::parse(spec)?;
+        feeds.push((name, linux::spawn(spec, netns, interval_ms, start)));
+    }
+    crate::source::resolve(feeds, linux::READY_TIMEOUT, multi)
+}
+
+/// 监控本机可见的所有网络命名空间，前缀为命名空间名称 (本机所在的为 `host`)
+#[cfg(target_os = "linux")]
+pub fn discover_all(interval_ms: u64, start: Instant) -> Result<Vec<Named>, String> {
+    let found = linux::discover();
+    let feeds = found
+        .iter()
+        .map(|(name, netns)| (Some(name.as_str()), linux::spawn(name, netns.clone(), interval_ms, start)))
+        .collect();
+    crate::source::resolve(feeds, linux::READY_TIMEOUT, true)
+}
+
+/// 本机 veth 网卡 → 对端所在命名空间的属主标签
+///
+/// 属主为容器名 (从 cgroup 路径取容器 ID，再查 Docker 的容器配置或容器内的主机名)，
+/// 不是容器时为命名空间中 PID 最小的进程 `命令名[PID]`。找不到对端的网卡不在结果中。
+#[cfg(target_os = "linux")]
+pub fn veth_owners(names: &[String]) -> HashMap<String, String> {
+    linux::veth_owners(names)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn veth_owners(_names: &[String]) -> HashMap<String, String> {
+    HashMap::new()
+}
+
+/// veth_owners 的缓存
+///
+/// 反查要遍历所有进程的命名空间，进程多时很慢，所以只在 veth 网卡集合变化时
+/// 在后台线程重新扫描；扫描完成前返回上一次的结果。
+#[derive(Default)]
+pub struct OwnerCache {
+    state: Arc<Mutex<OwnerScan>>,
+}
+
+#[derive(Default)]
+struct OwnerScan {
+    /// 最近一次请求扫描的 veth 网卡 (已排序)
+    veths: Vec<String>,
+    owners: HashMap<String, String>,
+}
+
+impl OwnerCache {
+    pub fn get(&self, names: &[String]) -> HashMap<String, String> {
+        let mut veths: Vec<String> = names.iter().filter(|n| n.starts_with("veth")).cloned().collect();
+        veths.sort_unstable();
+        let Ok(mut state) = self.state.lock() else {
+            return HashMap::new();
+        };
+        if state.veths != veths {
+            state.veths = veths.clone();
+            if veths.is_empty() {
+                state.owners.clear();
+            } else {
+                let shared = self.state.clone();
+                thread::spawn(move || {
+                    let owners = veth_owners(&veths);
+                    // 扫描期间网卡又变了时结果作废，以后来的扫描为准
+                    if let Ok(mut state) = shared.lock() {
+                        if state.veths == veths {
+                            state.owners = owners;
+                        }
+                    }
+                });
+            }
+        }
+        state.owners.clone()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn connect_all(_specs: &[String], _interval_ms: u64, _start: Instant, _multi: bool) -> Result<Vec<Named>, String> {
+    Err("--netns is only supported on Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn discover_all(_interval_ms: u64, _start: Instant) -> Result<Vec<Named>, String> {
+    Err("--all-netns is only supported on Linux".to_string())
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::collector::{self, DeviceInfo, Snapshot};
+    use crate::source::{Feed, FeedState};
+
+    /// 启动时等待第一次读取的时间 (本地读取，很快)
+    pub const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// `ip netns add` 创建的命名空间所在目录
+    const NAMED_DIR: &str = "/run/netns";
+
+    /// 要读取的命名空间
+    #[derive(Clone)]
+    pub enum Netns {
+        /// 进程所在的命名空间
+        Pid(u32),
+        /// 命名空间文件 (/run/netns/NAME 或其他 bind mount、/proc/PID/ns/net)
+        File(PathBuf),
+    }
+
+    impl Netns {
+        /// 纯数字为 PID，含 `/` 为路径，否则为 /run/netns 下的名称
+        pub fn parse(spec: &str) -> Result<Self, String> {
+            if let Ok(pid) = spec.parse::<u32>() {
+                if !Path::new(&format!("/proc/{pid}/ns/net")).exists() {
+                    return Err(format!("No process with PID {pid}"));
+                }
+                return Ok(Self::Pid(pid));
+            }
+            let path = if spec.contains('/') {
+                PathBuf::from(spec)
+            } else {
+                Path::new(NAMED_DIR).join(spec)
+            };
+            if !path.exists() {
+                return Err(format!("Network namespace '{spec}' not found (see `ip netns list`)"));
+            }
+            Ok(Self::File(path))
+        }
+
+        /// 默认前缀: 进程为 `命令名-PID`，文件为文件名
+        fn name(&self) -> String {
+            match self {
+                Self::Pid(pid) => {
+                    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+                    let comm: String = comm
+                        .trim()
+                        .chars()
+                        .map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+                        .collect();
+                    if comm.is_empty() {
+                        format!("pid{pid}")
+                    } else {
+                        format!("{comm}-{pid}")
+                    }
+                }
+                Self::File(path) => path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string()),
+            }
+        }
+    }
+
+    pub fn spawn(target: &str, netns: Netns, interval_ms: u64, start: Instant) -> Feed {
+        Feed::spawn(target, move |state| poll(&netns, interval_ms, start, state))
+    }
+
+    /// 命名空间的标识 (nsfs 的设备号和 inode)
+    fn ns_id(path: &Path) -> Option<(u64, u64)> {
+        fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+    }
+
+    /// 列出所有可见的命名空间: 本机所在的 (`host`)、/run/netns 下有名称的、
+    /// 以及其他进程所在的 (每个命名空间取 PID 最小的进程)
+    pub fn discover() -> Vec<(String, Netns)> {
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        if let Some(id) = ns_id(Path::new("/proc/self/ns/net")) {
+            seen.insert(id);
+            found.push(("host".to_string(), Netns::Pid(std::process::id())));
+        }
+
+        let mut named: Vec<PathBuf> = fs::read_dir(NAMED_DIR)
+            .map(|dir| dir.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        named.sort();
+        for path in named {
+            if ns_id(&path).is_some_and(|id| seen.insert(id)) {
+                let netns = Netns::File(path);
+                found.push((netns.name(), netns));
+            }
+        }
+
+        found.extend(other_namespaces(&seen).into_iter().map(|pid| {
+            let netns = Netns::Pid(pid);
+            (netns.name(), netns)
+        }));
+        found
+    }
+
+    /// 不在 `seen` 中的每个命名空间里 PID 最小的进程，按 PID 排序
+    ///
+    /// 没有权限读取的进程 (非 root 时的其他用户) 跳过。
+    fn other_namespaces(seen: &HashSet<(u64, u64)>) -> Vec<u32> {
+        let mut pids: Vec<u32> = fs::read_dir("/proc")
+            .map(|dir| dir.flatten().filter_map(|e| e.file_name().to_str()?.parse().ok()).collect())
+            .unwrap_or_default();
+        pids.sort_unstable();
+        let mut by_ns: HashMap<(u64, u64), u32> = HashMap::new();
+        for pid in pids {
+            if let Some(id) = ns_id(Path::new(&format!("/proc/{pid}/ns/net"))) {
+                by_ns.entry(id).or_insert(pid);
+            }
+        }
+        let mut others: Vec<u32> = by_ns.into_iter().filter(|(id, _)| !seen.contains(id)).map(|(_, pid)| pid).collect();
+        others.sort_unstable();
+        others
+    }
+
+    // ─── veth 属主 ─────────────────────────────────────────
+
+    /// 读取 /sys/class/net 下网卡的 (ifindex, iflink)；veth 的 iflink 是对端的 ifindex
+    fn link_index(dir: &Path) -> Option<(u32, u32)> {
+        let read = |f: &str| fs::read_to_string(dir.join(f)).ok()?.trim().parse().ok();
+        Some((read("ifindex")?, read("iflink")?))
+    }
+
+    pub fn veth_owners(names: &[String]) -> HashMap<String, String> {
+        let veths: Vec<(&String, (u32, u32))> = names
+            .iter()
+            .filter(|n| n.starts_with("veth"))
+            .filter_map(|n| Some((n, link_index(&Path::new("/sys/class/net").join(n))?)))
+            .collect();
+        let mut owners = HashMap::new();
+        if veths.is_empty() {
+            return owners;
+        }
+        let seen: HashSet<_> = ns_id(Path::new("/proc/self/ns/net")).into_iter().collect();
+        for pid in other_namespaces(&seen) {
+            // 容器里的 /sys 是在容器的网络命名空间中挂载的，可以看到对端网卡；
+            // 与本机共用挂载命名空间的进程看到的是本机的 /sys，下面的双向匹配不会成立
+            let Ok(dir) = fs::read_dir(format!("/proc/{pid}/root/sys/class/net")) else {
+                continue;
+            };
+            let links: Vec<(u32, u32)> = dir.flatten().filter_map(|e| link_index(&e.path())).collect();
+            for (name, (index, peer)) in &veths {
+                if links.contains(&(*peer, *index)) {
+                    owners.insert(name.to_string(), owner_label(pid));
+                }
+            }
+        }
+        owners
+    }
+
+    /// 进程的属主标签: 容器名，或 `命令名[PID]`
+    fn owner_label(pid: u32) -> String {
+        if let Some(name) = container_name(pid) {
+            return name;
+        }
+        let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+        format!("{}[{pid}]", comm.trim())
+    }
+
+    /// 从 /proc/PID/cgroup 中找容器 ID (64 位十六进制)，如
+    /// `/system.slice/docker-<id>.scope`、`/docker/<id>`、`/kubepods/.../cri-containerd-<id>.scope`
+    fn container_name(pid: u32) -> Option<String> {
+        let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+        let (runtime, id) = cgroup.lines().flat_map(|l| l.split('/')).find_map(|segment| {
+            let trimmed = segment.trim_end_matches(".scope");
+            let id = trimmed.rsplit(['-', ':']).next()?;
+            if id.len() != 64 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            let runtime = ["docker", "containerd", "crio", "libpod"]
+                .into_iter()
+                .find(|r| cgroup.contains(r))
+                .map(|r| if r == "libpod" { "podman" } else { r })
+                .unwrap_or("container");
+            Some((runtime, id.to_string()))
+        })?;
+
+        // Docker 的容器配置里有名称 ("Name": "/postgres")
+        if let Ok(config) = fs::read_to_string(format!("/var/lib/docker/containers/{id}/config.v2.json")) {
+            let name = serde_json::from_str::<serde_json::Value>(&config)
+                .ok()
+                .and_then(|v| v.get("Name")?.as_str().map(|n| n.trim_start_matches('/').to_string()))
+                .filter(|n| !n.is_empty());
+            if name.is_some() {
+                return name;
+            }
+        }
+        // Kubernetes 等把容器的主机名设为 Pod 名；Docker 默认的主机名只是 ID 前缀，
+        // 与本机相同说明容器没有自己的 /etc/hostname，都不用
+        let hostname = fs::read_to_string(format!("/proc/{pid}/root/etc/hostname")).unwrap_or_default();
+        let hostname = hostname.trim();
+        let own = fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+        if !hostname.is_empty() && !id.starts_with(hostname) && hostname != own.trim() {
+            return Some(hostname.to_string());
+        }
+        Some(format!("{runtime}:{}", &id[..12]))
+    }
+
+    /// 用 setns 让当前线程进入命名空间；只影响 Feed 的后台线程
+    fn enter(path: &Path) -> Result<(), String> {
+        let file = fs::File::open(path).map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+        // SAFETY: fd 在调用期间有效，setns 只改变当前线程的网络命名空间
+        if unsafe { libc::setns(file.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+            let e = io::Error::last_os_error();
+            return Err(if e.kind() == io::ErrorKind::PermissionDenied {
+                format!("entering a network namespace needs root (CAP_SYS_ADMIN): {e}")
+            } else {
+                format!("cannot enter {}: {e}", path.display())
+            });
+        }
+        Ok(())
+    }
+
+    /// 按间隔读取命名空间内的 /proc/net/dev，直到出错 (进程退出、命名空间被删除等)
+    fn poll(netns: &Netns, interval_ms: u64, start: Instant, state: &Mutex<FeedState>) -> Result<(), String> {
+        let dev_path = match netns {
+            Netns::Pid(pid) => PathBuf::from(format!("/proc/{pid}/net/dev")),
+            Netns::File(path) => {
+                enter(path)?;
+                PathBuf::from("/proc/thread-self/net/dev")
+            }
+        };
+        let name = netns.name();
+        let interval = Duration::from_millis(interval_ms);
+        loop {
+            let began = Instant::now();
+            let text = fs::read_to_string(&dev_path).map_err(|e| format!("cannot read {}: {e}", dev_path.display()))?;
+            let elapsed_secs = start.elapsed().as_secs_f64();
+            let mut ifaces = collector::parse_proc_net_dev(&text);
+            ifaces.sort_by(|a, b| a.0.cmp(&b.0));
+
+            {
+                let Ok(mut s) = state.lock() else {
+                    return Ok(());
+                };
+                s.name = Some(name.clone());
+                s.snapshots = ifaces
+                    .iter()
+                    .map(|(dev, counters)| {
+                        let [bytes_recv, bytes_sent, packets_recv, packets_sent, errors_recv, errors_sent] = *counters;
+                        let snap = Snapshot {
+                            elapsed_secs,
+                            bytes_recv,
+                            bytes_sent,
+                            packets_recv,
+                            packets_sent,
+                            errors_recv,
+                            errors_sent,
+                        };
+                        (dev.clone(), snap)
+                    })
+                    .collect();
+                s.devices = ifaces
+                    .into_iter()
+                    .map(|(dev, _)| DeviceInfo {
+                        name: dev,
+                        addrs: Vec::new(),
+                    })
+                    .collect();
+                s.mark_connected();
+            }
+            thread::sleep(interval.saturating_sub(began.elapsed()));
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Prometheus node_exporter 数据源 (--node-exporter)
+//!
+//! 每个采样间隔抓取一次 node_exporter 的 `/metrics`，从 netdev / netclass 收集器的指标中
+//! 读取每个网卡的计数器，已经部署 node_exporter 的主机无需再安装 agent:
+//!
+//! - `node_network_{receive,transmit}_{bytes,packets,errs}_total{device="..."}` 计数器
+//! - `node_network_speed_bytes` 协商速率 (字节/秒)
+//! - `node_network_info{operstate="..."}` 或 `node_network_up` 链路状态
+//! - `node_uname_info{nodename="..."}` 主机名，用作多来源时的前缀
+//!
+//! 旧版 (0.16 之前) 没有 `_total` 后缀的指标名同样识别。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::collector::{DeviceInfo, LinkState, Snapshot};
+use crate::http;
+use crate::source::{self, Feed, FeedState, Named};
+
+/// node_exporter 的默认端口
+const DEFAULT_PORT: u16 = 9100;
+/// 单次抓取的超时
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 连接所有 `--node-exporter [NAME=]HOST[:PORT]`，等待第一次抓取完成，返回 (前缀, 来源)
+pub fn connect_all(targets: &[String], interval_ms: u64, start: Instant, multi: bool) -> Result<Vec<Named>, String> {
+    let mut feeds = Vec::new();
+    for t in targets {
+        let (name, target) = source::parse_target(t);
+        let url = target_url(target)?;
+        let feed = Feed::spawn(target, move |state| poll(&url, interval_ms, start, state));
+        feeds.push((name, feed));
+    }
+    source::resolve(feeds, SCRAPE_TIMEOUT + Duration::from_secs(1), multi)
+}
+
+/// `HOST[:PORT]` 补全为 `http://HOST:9100/metrics`；完整的 http:// 或 https:// URL 原样使用
+fn target_url(target: &str) -> Result<http::Url, String> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return http::parse_url(target);
+    }
+    let mut url = http::parse_url(&format!("http://{target}"))?;
+    let authority = target.split('/').next().unwrap_or_default();
+    // "[::1]" 没有端口，"[::1]:9100" 和 "host:9100" 有
+    if authority.rsplit_once(':').is_none_or(|(_, port)| port.contains(']')) {
+        url.port = DEFAULT_PORT;
+    }
+    if url.path == "/" {
+        url.path = "/metrics".to_string();
+    }
+    Ok(url)
+}
+
+/// 按间隔抓取，直到出错
+fn poll(url: &http::Url, interval_ms: u64, start: Instant, state: &Mutex<FeedState>) -> Result<(), String> {
+    let interval = Duration::from_millis(interval_ms);
+    loop {
+        let began = Instant::now();
+        let body = http::get(url, &[("Accept", "text/plain")], SCRAPE_TIMEOUT)?;
+        let elapsed_secs = start.elapsed().as_secs_f64();
+        let scrape = parse(&String::from_utf8_lossy(&body));
+
+        let mut snapshots = HashMap::new();
+        let mut links = HashMap::new();
+        let mut devices = Vec::new();
+        for (name, dev) in scrape.devices {
+            // 只有收或只有发的网卡 (指标被过滤) 跳过
+            let (Some(rx), Some(tx)) = (dev.counters[0], dev.counters[1]) else {
+                continue;
+            };
+            let [_, _, packets_recv, packets_sent, errors_recv, errors_sent] = dev.counters.map(|c| c.unwrap_or(0));
+            snapshots.insert(
+                name.clone(),
+                Snapshot {
+                    elapsed_secs,
+                    bytes_recv: rx,
+                    bytes_sent: tx,
+                    packets_recv,
+                    packets_sent,
+                    errors_recv,
+                    errors_sent,
+                },
+            );
+            if let Some(oper) = dev.oper {
+                links.insert(name.clone(), LinkState { oper, speed_mbps: dev.speed_mbps });
+            }
+            devices.push(DeviceInfo {
+                name,
+                addrs: dev.alias.into_iter().collect(),
+            });
+        }
+        if devices.is_empty() {
+            return Err("no node_network_*_bytes_total metrics (is this a node_exporter with the netdev collector?)".to_string());
+        }
+
+        {
+            let Ok(mut s) = state.lock() else {
+                return Ok(());
+            };
+            s.name = scrape.nodename.clone();
+            s.mark_connected();
+            s.devices = devices;
+            s.snapshots = snapshots;
+            s.links = links;
+        }
+        thread::sleep(interval.saturating_sub(began.elapsed()));
+    }
+}
+
+// ─── 文本格式解析 ──────────────────────────────────────────
+
+/// 一次抓取中与网卡相关的内容
+#[derive(Default)]
+struct Scrape {
+    nodename: Option<String>,
+    /// 按设备名排序
+    devices: BTreeMap<String, Device>,
+}
+
+#[derive(Default)]
+struct Device {
+    /// rx / tx 字节、包、错误
+    counters: [Option<u64>; 6],
+    oper: Option<String>,
+    speed_mbps: Option<u64>,
+    alias: Option<String>,
+}
+
+/// 计数器指标名 (去掉 `_total` 后缀) 在 Device::counters 中的位置
+const COUNTERS: [&str; 6] = [
+    "node_network_receive_bytes",
+    "node_network_transmit_bytes",
+    "node_network_receive_packets",
+    "node_network_transmit_packets",
+    "node_network_receive_errs",
+    "node_network_transmit_errs",
+];
+
+/// 解析 Prometheus 文本格式 (0.0.4)，只保留需要的指标
+fn parse(text: &str) -> Scrape {
+    let mut scrape = Scrape::default();
+    for line in text.lines() {
+        let Some((name, labels, value)) = parse_sample(line) else {
+            continue;
+        };
+        let label = |key: &str| labels.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        if name == "node_uname_info" {
+            scrape.nodename = label("nodename").filter(|n| !n.is_empty());
+            continue;
+        }
+        let Some(device) = label("device") else {
+            continue;
+        };
+        let base = name.strip_suffix("_total").unwrap_or(name);
+        if let Some(i) = COUNTERS.iter().position(|&c| c == base) {
+            if value.is_finite() && value >= 0.0 {
+                scrape.devices.entry(device).or_default().counters[i] = Some(value as u64);
+            }
+            continue;
+        }
+        // 没有计数器的网卡在 poll 中跳过
+        let dev = scrape.devices.entry(device).or_default();
+        match name {
+            "node_network_info" => {
+                if let Some(oper) = label("operstate") {
+                    dev.oper = Some(oper);
+                }
+                dev.alias = label("ifalias").filter(|a| !a.is_empty());
+            }
+            // node_network_info 的 operstate 更详细，优先使用
+            "node_network_up" if dev.oper.is_none() => {
+                dev.oper = Some(if value == 1.0 { "up" } else { "down" }.to_string());
+            }
+            "node_network_speed_bytes" if value > 0.0 => {
+                dev.speed_mbps = Some((value * 8.0 / 1_000_000.0).round() as u64);
+            }
+            _ => {}
+        }
+    }
+    scrape
+}
+
+/// 标签 (名称, 值)，按出现顺序
+type Labels = Vec<(String, String)>;
+
+/// 解析一行样本 `name{k="v",...} value [timestamp]`；注释和格式不对的行返回 None
+fn parse_sample(line: &str) -> Option<(&str, Labels, f64)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let (name, mut rest) = line.split_at(name_end);
+    let mut labels = Vec::new();
+    if let Some(body) = rest.strip_prefix('{') {
+        let (parsed, after) = parse_labels(body)?;
+        labels = parsed;
+        rest = after;
+    }
+    let value = match rest.split_whitespace().next()? {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        v => v.parse().ok()?,
+    };
+    Some((name, labels, value))
+}
+
+/// 解析 `{` 之后的标签直到 `}`，返回标签和剩余部分
+fn parse_labels(mut s: &str) -> Option<(Labels, &str)> {
+    let mut labels = Vec::new();
+    loop {
+        s = s.trim_start_matches([',', ' ']);
+        if let Some(rest) = s.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+        let (key, rest) = s.split_once('=')?;
+        let mut chars = rest.strip_prefix('"')?.char_indices();
+        let mut value = String::new();
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.push((key.trim().to_string(), value));
+        s = &rest[end + 2..];
+    }
+}
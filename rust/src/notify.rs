@@ -0,0 +1,282 @@
+//! 告警投递通道
+//!
+//! 在告警规则后用 `=>` 指定通道，多个通道以逗号分隔:
+//!
+//! ```text
+//! eth0 in > 800M for 10s => bell, syslog, webhook:https://hook.local/alert, exec:/usr/local/bin/page.sh
+//! ```
+//!
+//! - `exec:CMD`      通过 shell 执行命令，告警详情放在 WINLOAD_ALERT_* 环境变量中。
+//!   exec 会吞掉后面的全部文本 (命令里可以有逗号)，因此要放在最后
+//! - `webhook:URL`   POST 一个 JSON 对象 (https:// 通过 curl 发送，见 http.rs)
+//! - `syslog`        写入本机 syslog (/dev/log，journald 同样会收到)
+//! - `bell`          响铃、修改终端标题并发出桌面通知的转义序列；多数终端在响铃时会设置窗口的
+//!   urgency hint。只在 TUI 中生效: --headless / --output 时 stdout 是数据输出，不能混入控制字符
+//!
+//! 除 bell 外的通道都在后台线程中执行，不会阻塞界面刷新。
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::alert::{AlertEvent, Metric, Rule};
+use crate::http;
+
+/// webhook 请求超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 投递通道
+#[derive(Clone, Debug)]
+pub enum Channel {
+    Exec(String),
+    Webhook(http::Url),
+    Syslog,
+    Bell,
+}
+
+/// 解析 `=>` 之后的通道列表
+pub fn parse_channels(s: &str) -> Result<Vec<Channel>, String> {
+    let mut channels = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        if let Some(cmd) = rest.strip_prefix("exec:") {
+            let cmd = cmd.trim();
+            if cmd.is_empty() {
+                return Err("empty command in exec: channel".to_string());
+            }
+            channels.push(Channel::Exec(cmd.to_string()));
+            break;
+        }
+        let (item, tail) = rest.split_once(',').unwrap_or((rest, ""));
+        let item = item.trim();
+        rest = tail.trim();
+
+        let channel = if let Some(url) = item.strip_prefix("webhook:") {
+            Channel::Webhook(http::parse_url(url.trim())?)
+        } else {
+            match item {
+                "bell" => Channel::Bell,
+                "syslog" => {
+                    if cfg!(unix) {
+                        Channel::Syslog
+                    } else {
+                        return Err("the syslog channel is only available on Linux/macOS".to_string());
+                    }
+                }
+                "" => continue,
+                other => {
+                    return Err(format!(
+                        "unknown alert channel '{other}' (exec:CMD, webhook:URL, syslog, bell)"
+                    ))
+                }
+            }
+        };
+        channels.push(channel);
+    }
+    Ok(channels)
+}
+
+/// 告警详情，供各通道使用
+struct Details {
+    state: &'static str,
+    device: String,
+    rule: String,
+    metric: &'static str,
+    value: f64,
+    threshold: f64,
+    unit: &'static str,
+    time: String,
+    host: String,
+}
+
+impl Details {
+    fn new(rule: &Rule, event: &AlertEvent, host: &str) -> Self {
+        let (metric, unit) = match rule.metric {
+            Metric::Incoming => ("in", "bytes/s"),
+            Metric::Outgoing => ("out", "bytes/s"),
+            Metric::Total => ("total", "bytes/s"),
+            Metric::Quota => ("quota", "percent"),
+        };
+        Self {
+            state: if event.firing { "firing" } else { "cleared" },
+            device: event.device.clone(),
+            rule: rule.text.clone(),
+            metric,
+            value: event.value,
+            threshold: rule.trigger.threshold,
+            unit,
+            time: event.time.to_rfc3339(),
+            host: host.to_string(),
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "alert {}: [{}] {} (value {:.2} {})",
+            self.state, self.device, self.rule, self.value, self.unit
+        )
+    }
+
+    fn json(&self) -> String {
+        serde_json::json!({
+            "state": self.state,
+            "device": self.device,
+            "rule": self.rule,
+            "metric": self.metric,
+            "value": self.value,
+            "threshold": self.threshold,
+            "unit": self.unit,
+            "time": self.time,
+            "host": self.host,
+        })
+        .to_string()
+    }
+}
+
+/// 告警通知分发器
+pub struct Notifier {
+    host: String,
+    /// 是否响铃 (只在 TUI 中)
+    bell: bool,
+    errors_tx: Sender<String>,
+    errors_rx: Receiver<String>,
+}
+
+impl Notifier {
+    /// `bell` 为 false 时跳过 bell 通道 (非 TUI 模式)
+    pub fn new(bell: bool) -> Self {
+        let (errors_tx, errors_rx) = mpsc::channel();
+        Self {
+            host: sysinfo::System::host_name().unwrap_or_default(),
+            bell,
+            errors_tx,
+            errors_rx,
+        }
+    }
+
+    /// 把告警事件投递到规则配置的所有通道
+    pub fn dispatch(&self, rule: &Rule, event: &AlertEvent) {
+        for channel in &rule.channels {
+            let details = Details::new(rule, event, &self.host);
+            match channel {
+                Channel::Bell if self.bell => ring_bell(&details),
+                Channel::Bell => {}
+                _ => {
+                    let channel = channel.clone();
+                    let errors = self.errors_tx.clone();
+                    let spawned = thread::Builder::new()
+                        .name("alert-notify".to_string())
+                        .spawn(move || {
+                            if let Err(e) = deliver(&channel, &details) {
+                                let _ = errors.send(e);
+                            }
+                        });
+                    if let Err(e) = spawned {
+                        let _ = self.errors_tx.send(format!("Failed to spawn notify thread: {e}"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 取出后台投递线程报告的错误
+    pub fn drain_errors(&self) -> Vec<String> {
+        self.errors_rx.try_iter().collect()
+    }
+}
+
+fn deliver(channel: &Channel, d: &Details) -> Result<(), String> {
+    match channel {
+        Channel::Exec(cmd) => run_exec(cmd, d),
+        Channel::Webhook(url) => http::post(url, &[("Content-Type", "application/json")], d.json().as_bytes(), WEBHOOK_TIMEOUT)
+            .map_err(|e| format!("webhook: {e}")),
+        Channel::Syslog => send_syslog(d),
+        Channel::Bell => Ok(()),
+    }
+}
+
+fn run_exec(cmd: &str, d: &Details) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = std::process::Command::new("cmd");
+        c.arg("/C").arg(cmd);
+        c
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut command = {
+        let mut c = std::process::Command::new("sh");
+        c.arg("-c").arg(cmd);
+        c
+    };
+
+    let status = command
+        .env("WINLOAD_ALERT_STATE", d.state)
+        .env("WINLOAD_ALERT_DEVICE", &d.device)
+        .env("WINLOAD_ALERT_RULE", &d.rule)
+        .env("WINLOAD_ALERT_METRIC", d.metric)
+        .env("WINLOAD_ALERT_VALUE", format!("{}", d.value))
+        .env("WINLOAD_ALERT_THRESHOLD", format!("{}", d.threshold))
+        .env("WINLOAD_ALERT_UNIT", d.unit)
+        .env("WINLOAD_ALERT_TIME", &d.time)
+        .env("WINLOAD_ALERT_HOST", &d.host)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("exec '{cmd}': {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exec '{cmd}' exited with {status}"))
+    }
+}
+
+#[cfg(unix)]
+fn send_syslog(d: &Details) -> Result<(), String> {
+    use std::os::unix::net::UnixDatagram;
+
+    // facility user(1)，触发为 warning(4)，恢复为 notice(5)
+    let pri = 8 + if d.state == "firing" { 4 } else { 5 };
+    let msg = format!("<{pri}>winload[{}]: {}", std::process::id(), d.summary());
+
+    let sock = UnixDatagram::unbound().map_err(|e| format!("syslog: {e}"))?;
+    let mut last_err = String::from("no syslog socket found");
+    for path in ["/dev/log", "/var/run/syslog", "/var/run/log"] {
+        match sock.send_to(msg.as_bytes(), path) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_err = format!("{path}: {e}"),
+        }
+    }
+    Err(format!("syslog: {last_err}"))
+}
+
+#[cfg(not(unix))]
+fn send_syslog(_d: &Details) -> Result<(), String> {
+    Err("syslog is not available on this platform".to_string())
+}
+
+/// 响铃并修改终端标题 (在主线程直接写终端，避免与界面输出交错)
+///
+/// 触发时还发出桌面通知: OSC 9 (iTerm2、kitty、WezTerm、Ghostty) 和 OSC 777 (foot、
+/// rxvt-unicode 等)，不认识的终端会忽略。tmux / screen 默认不转发这些序列。
+fn ring_bell(d: &Details) {
+    use std::io::Write;
+
+    let title = if d.state == "firing" {
+        format!("winload: ALERT [{}] {}", d.device, d.rule)
+    } else {
+        "winload".to_string()
+    };
+    let mut out = std::io::stdout();
+    let _ = crossterm::execute!(out, crossterm::terminal::SetTitle(title));
+    if d.state == "firing" {
+        // 通知文本里不能有控制字符；OSC 777 的字段以 ';' 分隔
+        let text: String = format!("[{}] {}", d.device, d.rule).chars().filter(|c| !c.is_control()).collect();
+        let _ = write!(
+            out,
+            "\x1b]9;winload alert: {text}\x1b\\\x1b]777;notify;winload alert;{}\x1b\\\x07",
+            text.replace(';', ",")
+        );
+        let _ = out.flush();
+    }
+}
@@ -0,0 +1,459 @@
+//! OpenTelemetry OTLP 指标编码 (--sink otlp:URL)
+//!
+//! 按 opentelemetry-proto 的 metrics/v1 定义手工编码 protobuf，通过 OTLP/HTTP
+//! (`POST /v1/metrics`，`application/x-protobuf`) 发送，避免引入 protobuf 和 gRPC 依赖。
+//! OTLP/gRPC 需要 HTTP/2，不支持；collector 默认在 4318 端口同时提供 HTTP 接收。
+//!
+//! 每个设备编码为一个 ResourceMetrics:
+//!
+//! - Resource 属性: `host.name`、`host.arch`、`os.type`、`network.interface.name`、
+//!   `network.interface.addresses` (逗号分隔的 IPv4 地址)
+//! - `system.network.io` / `system.network.packets` / `system.network.errors`:
+//!   累计、单调的 Sum，值为从起点 (第一次采集，或计数器回退的时刻) 以来的增量
+//! - `winload.network.rate` / `winload.network.rate.average`: Gauge (By/s)
+//!
+//! 数据点带 `network.io.direction` 属性 (receive / transmit)。
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+
+use crate::stats::TrafficStats;
+use crate::DeviceView;
+
+// ─── protobuf 编码 ─────────────────────────────────────────
+
+/// wire type
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+
+fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn put_tag(buf: &mut Vec<u8>, field: u32, wire: u8) {
+    put_varint(buf, ((field as u64) << 3) | wire as u64);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, data: &[u8]) {
+    put_tag(buf, field, LEN);
+    put_varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn put_str(buf: &mut Vec<u8>, field: u32, s: &str) {
+    put_bytes(buf, field, s.as_bytes());
+}
+
+fn put_fixed64(buf: &mut Vec<u8>, field: u32, v: u64) {
+    put_tag(buf, field, FIXED64);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u32, v: u64) {
+    put_tag(buf, field, VARINT);
+    put_varint(buf, v);
+}
+
+/// 嵌套消息: 先编码到临时缓冲区再写入长度
+fn put_msg(buf: &mut Vec<u8>, field: u32, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut inner = Vec::new();
+    f(&mut inner);
+    put_bytes(buf, field, &inner);
+}
+
+/// KeyValue { key = 1; AnyValue value = 2 { string_value = 1 } }
+fn put_attr(buf: &mut Vec<u8>, field: u32, key: &str, value: &str) {
+    put_msg(buf, field, |kv| {
+        put_str(kv, 1, key);
+        put_msg(kv, 2, |any| put_str(any, 1, value));
+    });
+}
+
+// ─── metrics/v1 ───────────────────────────────────────────
+
+/// 数据点的值
+#[derive(Clone, Copy)]
+enum Value {
+    Int(u64),
+    Double(f64),
+}
+
+/// 指标的取值方式
+enum Source {
+    /// 累计 Sum，值为 counters() 中的第几项
+    Counter(usize),
+    /// Gauge
+    Rate(fn(&TrafficStats) -> f64),
+}
+
+/// 一个指标的定义: 名称、单位和取值方式
+struct Family {
+    name: &'static str,
+    description: &'static str,
+    unit: &'static str,
+    source: Source,
+}
+
+const FAMILIES: [Family; 5] = [
+    Family {
+        name: "system.network.io",
+        description: "Bytes transferred since the interface came up",
+        unit: "By",
+        source: Source::Counter(0),
+    },
+    Family {
+        name: "system.network.packets",
+        description: "Packets transferred since the interface came up",
+        unit: "{packet}",
+        source: Source::Counter(1),
+    },
+    Family {
+        name: "system.network.errors",
+        description: "Errors since the interface came up",
+        unit: "{error}",
+        source: Source::Counter(2),
+    },
+    Family {
+        name: "winload.network.rate",
+        description: "Current rate over the last second",
+        unit: "By/s",
+        source: Source::Rate(|s| s.current),
+    },
+    Family {
+        name: "winload.network.rate.average",
+        description: "Average rate over the --average window",
+        unit: "By/s",
+        source: Source::Rate(|s| s.average),
+    },
+];
+
+/// AggregationTemporality.CUMULATIVE
+const CUMULATIVE: u64 = 2;
+
+/// 累计 Sum 用到的计数器: [字节, 包, 错误]
+fn counters(s: &TrafficStats) -> [u64; 3] {
+    [s.total, s.packets, s.errors]
+}
+
+/// 一个设备一个方向的累计起点
+///
+/// 累计 Sum 的值必须是从 start_time 开始计的，而网卡计数器从开机 (或网卡创建) 时开始，
+/// 所以导出相对起点的增量。计数器回退 (网卡重建、驱动重载等) 时从回退后的值重新累计，
+/// 起点移到上一次采集的时刻，接收端据此把它当成一个新的累计序列。
+#[derive(Clone, Copy, Debug)]
+pub struct Origin {
+    /// start_time_unix_nano
+    time: DateTime<Local>,
+    /// 起点时的计数器
+    base: [u64; 3],
+    /// 上一次采集的计数器和时间
+    last: [u64; 3],
+    seen: DateTime<Local>,
+}
+
+impl Origin {
+    fn new(counters: [u64; 3], now: DateTime<Local>) -> Self {
+        Self {
+            time: now,
+            base: counters,
+            last: counters,
+            seen: now,
+        }
+    }
+
+    fn advance(&mut self, counters: [u64; 3], now: DateTime<Local>) {
+        if counters.iter().zip(&self.last).any(|(c, l)| c < l) {
+            self.time = self.seen;
+            self.base = [0; 3];
+        }
+        self.last = counters;
+        self.seen = now;
+    }
+}
+
+/// 各设备各方向 (设备名, 是否为接收) 的累计起点，由调用方在两次采集之间保留
+pub type Origins = HashMap<(String, bool), Origin>;
+
+/// 语义约定中的 os.type 取值 (macOS 为 darwin)
+fn os_type() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    }
+}
+
+fn nanos(t: DateTime<Local>) -> u64 {
+    t.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+/// NumberDataPoint { attributes = 7; start_time_unix_nano = 2; time_unix_nano = 3;
+/// as_double = 4; as_int = 6 }；Gauge 不带 start_time
+fn put_point(buf: &mut Vec<u8>, field: u32, direction: &str, start: Option<u64>, time: u64, value: Value) {
+    put_msg(buf, field, |p| {
+        put_attr(p, 7, "network.io.direction", direction);
+        if let Some(start) = start {
+            put_fixed64(p, 2, start);
+        }
+        put_fixed64(p, 3, time);
+        match value {
+            Value::Double(v) => put_fixed64(p, 4, v.to_bits()),
+            // sfixed64，计数器不会超过 i64 范围
+            Value::Int(v) => put_fixed64(p, 6, v),
+        }
+    });
+}
+
+/// 把一个设备编码为 ExportMetricsServiceRequest 的一个 `resource_metrics` (字段 1)
+///
+/// 多个设备 / 多次采集的编码结果直接拼接，就是一个合法的请求体。
+/// `origins` 中该设备的起点随本次采集更新。
+pub fn encode_device(host: &str, origins: &mut Origins, view: &DeviceView, now: DateTime<Local>) -> Vec<u8> {
+    let time = nanos(now);
+    let directions = [("receive", true, &view.engine.incoming), ("transmit", false, &view.engine.outgoing)];
+    let origins = directions.map(|(_, incoming, stats)| {
+        let origin = origins
+            .entry((view.info.name.clone(), incoming))
+            .and_modify(|o| o.advance(counters(stats), now))
+            .or_insert_with(|| Origin::new(counters(stats), now));
+        *origin
+    });
+    let mut buf = Vec::new();
+    // ResourceMetrics { resource = 1; scope_metrics = 2 }
+    put_msg(&mut buf, 1, |rm| {
+        // Resource { attributes = 1 }
+        put_msg(rm, 1, |res| {
+            put_attr(res, 1, "host.name", host);
+            put_attr(res, 1, "host.arch", std::env::consts::ARCH);
+            put_attr(res, 1, "os.type", os_type());
+            put_attr(res, 1, "network.interface.name", &view.info.name);
+            put_attr(res, 1, "network.interface.addresses", &view.info.addrs.join(","));
+        });
+        // ScopeMetrics { scope = 1 { name = 1; version = 2 }; metrics = 2 }
+        put_msg(rm, 2, |sm| {
+            put_msg(sm, 1, |scope| {
+                put_str(scope, 1, "winload");
+                put_str(scope, 2, env!("CARGO_PKG_VERSION"));
+            });
+            for family in &FAMILIES {
+                // Metric { name = 1; description = 2; unit = 3; gauge = 5; sum = 7 }
+                put_msg(sm, 2, |m| {
+                    put_str(m, 1, family.name);
+                    put_str(m, 2, family.description);
+                    put_str(m, 3, family.unit);
+                    let points = |data: &mut Vec<u8>| {
+                        for ((dir, _, stats), origin) in directions.iter().zip(&origins) {
+                            let (start, value) = match family.source {
+                                Source::Counter(i) => (
+                                    Some(nanos(origin.time)),
+                                    Value::Int(counters(stats)[i].saturating_sub(origin.base[i])),
+                                ),
+                                Source::Rate(f) => (None, Value::Double(f(stats))),
+                            };
+                            put_point(data, 1, dir, start, time, value);
+                        }
+                    };
+                    if let Source::Counter(_) = family.source {
+                        // Sum { data_points = 1; aggregation_temporality = 2; is_monotonic = 3 }
+                        put_msg(m, 7, |sum| {
+                            points(sum);
+                            put_uint(sum, 2, CUMULATIVE);
+                            put_uint(sum, 3, 1);
+                        });
+                    } else {
+                        // Gauge { data_points = 1 }
+                        put_msg(m, 5, points);
+                    }
+                });
+            }
+        });
+    });
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::{DeviceInfo, Snapshot};
+    use crate::stats::StatisticsEngine;
+    use chrono::TimeZone;
+
+    /// 一个 protobuf 字段的值: VARINT / FIXED64 为整数，LEN 为原始字节
+    #[derive(Debug, PartialEq)]
+    enum Field<'a> {
+        Int(u64),
+        Bytes(&'a [u8]),
+    }
+
+    fn varint(buf: &mut &[u8]) -> u64 {
+        let mut v = 0;
+        for shift in (0..64).step_by(7) {
+            let (&b, rest) = buf.split_first().expect("truncated varint");
+            *buf = rest;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        v
+    }
+
+    /// 解出一层消息的全部字段
+    fn parse(mut buf: &[u8]) -> Vec<(u32, Field<'_>)> {
+        let mut out = Vec::new();
+        while !buf.is_empty() {
+            let key = varint(&mut buf);
+            let value = match key as u8 & 7 {
+                VARINT => Field::Int(varint(&mut buf)),
+                FIXED64 => {
+                    let (v, rest) = buf.split_at(8);
+                    buf = rest;
+                    Field::Int(u64::from_le_bytes(v.try_into().unwrap()))
+                }
+                LEN => {
+                    let len = varint(&mut buf) as usize;
+                    let (v, rest) = buf.split_at(len);
+                    buf = rest;
+                    Field::Bytes(v)
+                }
+                wire => panic!("unexpected wire type {wire}"),
+            };
+            out.push(((key >> 3) as u32, value));
+        }
+        out
+    }
+
+    fn bytes<'a>(fields: &[(u32, Field<'a>)], field: u32) -> Vec<&'a [u8]> {
+        fields
+            .iter()
+            .filter_map(|(f, v)| match v {
+                Field::Bytes(b) if *f == field => Some(*b),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn int(fields: &[(u32, Field)], field: u32) -> Option<u64> {
+        fields.iter().find_map(|(f, v)| match v {
+            Field::Int(i) if *f == field => Some(*i),
+            _ => None,
+        })
+    }
+
+    fn text(buf: &[u8]) -> &str {
+        std::str::from_utf8(buf).unwrap()
+    }
+
+    /// KeyValue 列表 → (key, string_value)
+    fn attrs(fields: &[(u32, Field)], field: u32) -> Vec<(String, String)> {
+        bytes(fields, field)
+            .into_iter()
+            .map(|kv| {
+                let kv = parse(kv);
+                let value = parse(bytes(&kv, 2)[0]);
+                (text(bytes(&kv, 1)[0]).to_string(), text(bytes(&value, 1)[0]).to_string())
+            })
+            .collect()
+    }
+
+    /// 某个指标某个方向的数据点: (start_time, time, as_int, as_double)
+    type Point = (Option<u64>, u64, Option<u64>, Option<f64>);
+    /// 指标名, 是否为 Sum, 各方向的数据点
+    type Metric = (String, bool, Vec<(String, Point)>);
+
+    /// 解码 encode_device 的输出: resource 属性和各指标
+    fn decode(buf: &[u8]) -> (Vec<(String, String)>, Vec<Metric>) {
+        let top = parse(buf);
+        let rm = parse(bytes(&top, 1)[0]);
+        let resource = attrs(&parse(bytes(&rm, 1)[0]), 1);
+        let sm = parse(bytes(&rm, 2)[0]);
+        let metrics = bytes(&sm, 2)
+            .into_iter()
+            .map(|m| {
+                let m = parse(m);
+                let name = text(bytes(&m, 1)[0]).to_string();
+                let (is_sum, data) = match bytes(&m, 7).first() {
+                    Some(sum) => {
+                        let sum = parse(sum);
+                        assert_eq!((int(&sum, 2), int(&sum, 3)), (Some(CUMULATIVE), Some(1)), "{name}");
+                        (true, bytes(&sum, 1).into_iter().map(<[u8]>::to_vec).collect::<Vec<_>>())
+                    }
+                    None => (false, bytes(&parse(bytes(&m, 5)[0]), 1).into_iter().map(<[u8]>::to_vec).collect()),
+                };
+                let points = data
+                    .iter()
+                    .map(|p| {
+                        let p = parse(p);
+                        let dir = attrs(&p, 7).remove(0).1;
+                        (dir, (int(&p, 2), int(&p, 3).unwrap(), int(&p, 6), int(&p, 4).map(f64::from_bits)))
+                    })
+                    .collect();
+                (name, is_sum, points)
+            })
+            .collect();
+        (resource, metrics)
+    }
+
+    fn snapshot(secs: f64, bytes: [u64; 2], packets: [u64; 2]) -> Snapshot {
+        Snapshot {
+            elapsed_secs: secs,
+            bytes_recv: bytes[0],
+            bytes_sent: bytes[1],
+            packets_recv: packets[0],
+            packets_sent: packets[1],
+            errors_recv: 0,
+            errors_sent: 0,
+        }
+    }
+
+    #[test]
+    fn sums_count_from_origin_and_restart_on_counter_reset() {
+        let mut view = DeviceView {
+            info: DeviceInfo {
+                name: "eth0".to_string(),
+                addrs: vec!["192.0.2.1".to_string()],
+            },
+            engine: StatisticsEngine::new(1000, 10),
+        };
+        let t0 = Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let mut origins = Origins::new();
+        let mut export = |view: &DeviceView, secs: i64| decode(&encode_device("host1", &mut origins, view, at(secs)));
+        let io = |metrics: &[Metric], dir: &str| {
+            let (_, is_sum, points) = metrics.iter().find(|(n, ..)| n == "system.network.io").unwrap();
+            assert!(*is_sum);
+            points.iter().find(|(d, _)| d == dir).unwrap().1
+        };
+
+        view.engine.update(snapshot(0.0, [1_000_000, 500], [100, 50]));
+        let (resource, metrics) = export(&view, 0);
+        assert!(resource.contains(&("host.name".to_string(), "host1".to_string())));
+        assert!(resource.contains(&("network.interface.name".to_string(), "eth0".to_string())));
+        assert!(resource.contains(&("network.interface.addresses".to_string(), "192.0.2.1".to_string())));
+        assert_eq!(metrics.len(), FAMILIES.len());
+        // 第一次采集是起点，开机以来的流量不计入
+        assert_eq!(io(&metrics, "receive"), (Some(nanos(at(0))), nanos(at(0)), Some(0), None));
+
+        view.engine.update(snapshot(1.0, [1_003_000, 900], [130, 54]));
+        let (_, metrics) = export(&view, 1);
+        assert_eq!(io(&metrics, "receive"), (Some(nanos(at(0))), nanos(at(1)), Some(3000), None));
+        assert_eq!(io(&metrics, "transmit").2, Some(400));
+        let (_, _, packets) = metrics.iter().find(|(n, ..)| n == "system.network.packets").unwrap();
+        assert_eq!(packets.iter().map(|(_, p)| p.2).collect::<Vec<_>>(), [Some(30), Some(4)]);
+        // Gauge 不带 start_time
+        let (_, is_sum, rate) = metrics.iter().find(|(n, ..)| n == "winload.network.rate").unwrap();
+        assert!(!is_sum);
+        assert_eq!(rate[0].1, (None, nanos(at(1)), None, Some(3000.0)));
+
+        // 网卡重建: 接收方向从回退后的值重新累计，起点移到上一次采集
+        view.engine.update(snapshot(2.0, [2000, 1300], [20, 58]));
+        let (_, metrics) = export(&view, 2);
+        assert_eq!(io(&metrics, "receive"), (Some(nanos(at(1))), nanos(at(2)), Some(2000), None));
+        assert_eq!(io(&metrics, "transmit"), (Some(nanos(at(0))), nanos(at(2)), Some(800), None));
+    }
+}
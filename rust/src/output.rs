@@ -0,0 +1,142 @@
+//! 非交互输出 (--output jsonl|csv)
+//!
+//! 不进入 TUI，每个采样间隔为每个设备向 stdout 打印一条记录，
+//! 方便接 jq / awk / 日志采集器。速率统一为 bytes/s，不受 --unit 影响。
+//!
+//! JSONL 每行一个对象:
+//!
+//! ```text
+//! {"time":"2026-10-19T14:00:00.500+08:00","device":"eth0",
+//!  "in":{"current":..,"average":..,"min":..,"max":..,"total":..,"packets":..,"errors":..},
+//!  "out":{...}}
+//! ```
+//!
+//! CSV 第一行为表头，之后每行一条记录。用户标记 (回放录制文件时) 在 JSONL 中是
+//! `{"time":..,"marker":"label"}`，在 CSV 中是 device 为 `*`、只有 marker 列的行。
+
+use std::io::{self, Write};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde_json::json;
+
+use crate::stats::TrafficStats;
+use crate::DeviceView;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+const CSV_HEADER: &str = "time,device,\
+in_current,in_average,in_min,in_max,in_total,in_packets,in_errors,\
+out_current,out_average,out_min,out_max,out_total,out_packets,out_errors,\
+marker";
+
+/// 记录写出器
+pub struct RecordWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    header_written: bool,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(out: W, format: OutputFormat) -> Self {
+        Self {
+            out,
+            format,
+            header_written: false,
+        }
+    }
+
+    /// 写出一个设备的当前统计
+    pub fn record(&mut self, time: DateTime<Local>, view: &DeviceView) -> io::Result<()> {
+        let time = format_time(time);
+        let (rx, tx) = (&view.engine.incoming, &view.engine.outgoing);
+        match self.format {
+            OutputFormat::Jsonl => {
+                let obj = json!({
+                    "time": time,
+                    "device": view.info.name,
+                    "in": stats_json(rx),
+                    "out": stats_json(tx),
+                });
+                writeln!(self.out, "{obj}")
+            }
+            OutputFormat::Csv => {
+                self.header()?;
+                writeln!(
+                    self.out,
+                    "{time},{},{},{},",
+                    csv_field(&view.info.name),
+                    stats_csv(rx),
+                    stats_csv(tx)
+                )
+            }
+        }
+    }
+
+    /// 写出一个用户标记
+    pub fn marker(&mut self, time: DateTime<Local>, label: &str) -> io::Result<()> {
+        let time = format_time(time);
+        match self.format {
+            OutputFormat::Jsonl => writeln!(self.out, "{}", json!({ "time": time, "marker": label })),
+            OutputFormat::Csv => {
+                self.header()?;
+                writeln!(self.out, "{time},*,{}{}", ",".repeat(14), csv_field(label))
+            }
+        }
+    }
+
+    /// 每轮记录写完后调用，让下游及时看到数据
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            writeln!(self.out, "{CSV_HEADER}")?;
+        }
+        Ok(())
+    }
+}
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+/// 速率保留两位小数，避免输出一长串浮点噪声
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+fn stats_json(s: &TrafficStats) -> serde_json::Value {
+    json!({
+        "current": round2(s.current),
+        "average": round2(s.average),
+        "min": round2(s.minimum),
+        "max": round2(s.maximum),
+        "total": s.total,
+        "packets": s.packets,
+        "errors": s.errors,
+    })
+}
+
+fn stats_csv(s: &TrafficStats) -> String {
+    format!(
+        "{:.2},{:.2},{:.2},{:.2},{},{},{}",
+        s.current, s.average, s.minimum, s.maximum, s.total, s.packets, s.errors
+    )
+}
+
+/// 按 RFC 4180 转义: 含逗号、引号或换行时整体加引号，内部引号加倍
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,129 @@
+//! Prometheus 指标导出 (--prometheus-listen)
+//!
+//! 在指定地址上提供 `/metrics`，格式为 Prometheus 文本格式 (0.0.4)。
+//! 每次采集后由主循环渲染一次文本，HTTP 线程只返回最近一次的结果，
+//! 抓取频率不影响采集。TUI 和 --headless / --output 模式下都可使用。
+
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use crate::http;
+use crate::stats::TrafficStats;
+use crate::App;
+
+/// 后台 HTTP 服务，持有最近一次渲染的指标文本
+pub struct Exporter {
+    text: Arc<Mutex<String>>,
+}
+
+impl Exporter {
+    /// 开始监听；返回导出器和实际监听的地址
+    pub fn start(addr: &str) -> Result<(Self, String), String> {
+        let text = Arc::new(Mutex::new(String::new()));
+        let shared = text.clone();
+        let local = http::serve(addr, move |req, mut stream| {
+            let _ = match (req.method.as_str(), req.path.as_str()) {
+                ("GET", "/metrics") => {
+                    let body = shared.lock().map(|t| t.clone()).unwrap_or_default();
+                    http::respond(&mut stream, 200, "text/plain; version=0.0.4; charset=utf-8", body.as_bytes())
+                }
+                ("GET", _) => http::respond(&mut stream, 404, "text/plain", b"try /metrics\n"),
+                _ => http::respond(&mut stream, 405, "text/plain", b"method not allowed\n"),
+            };
+        })?;
+        Ok((Self { text }, local.to_string()))
+    }
+
+    /// 用最新统计更新指标文本
+    pub fn publish(&self, app: &App) {
+        let text = render(app);
+        if let Ok(mut shared) = self.text.lock() {
+            *shared = text;
+        }
+    }
+}
+
+/// 按设备和方向导出的一个指标
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&TrafficStats) -> String,
+}
+
+const PER_DIRECTION: [Family; 5] = [
+    Family {
+        name: "winload_bytes_total",
+        kind: "counter",
+        help: "Bytes transferred since the interface came up",
+        value: |s| s.total.to_string(),
+    },
+    Family {
+        name: "winload_packets_total",
+        kind: "counter",
+        help: "Packets transferred since the interface came up",
+        value: |s| s.packets.to_string(),
+    },
+    Family {
+        name: "winload_errors_total",
+        kind: "counter",
+        help: "Errors since the interface came up",
+        value: |s| s.errors.to_string(),
+    },
+    Family {
+        name: "winload_rate_bytes_per_second",
+        kind: "gauge",
+        help: "Current rate over the last second",
+        value: |s| fmt_f64(s.current),
+    },
+    Family {
+        name: "winload_average_rate_bytes_per_second",
+        kind: "gauge",
+        help: "Average rate over the --average window",
+        value: |s| fmt_f64(s.average),
+    },
+];
+
+/// 渲染所有指标
+pub fn render(app: &App) -> String {
+    let mut out = String::new();
+
+    for Family { name, kind, help, value } in PER_DIRECTION {
+        header(&mut out, name, kind, help);
+        for view in &app.views {
+            let device = escape(&view.info.name);
+            for (dir, stats) in [("in", &view.engine.incoming), ("out", &view.engine.outgoing)] {
+                let _ = writeln!(out, "{name}{{device=\"{device}\",direction=\"{dir}\"}} {}", value(stats));
+            }
+        }
+    }
+
+    if !app.alerts.rules.is_empty() {
+        header(&mut out, "winload_alert_firing", "gauge", "1 while an alert rule is firing for a device");
+        for alert in &app.alerts.alerts {
+            let rule = escape(&app.alerts.rules[alert.rule].text);
+            let _ = writeln!(
+                out,
+                "winload_alert_firing{{device=\"{}\",rule=\"{rule}\"}} {}",
+                escape(&alert.device),
+                u8::from(alert.is_firing())
+            );
+        }
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn fmt_f64(v: f64) -> String {
+    format!("{v:.2}")
+}
+
+/// 标签值转义: 反斜杠、双引号、换行
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,141 @@
+//! 协议分布 (按 L4 协议、常见服务端口和 IP 版本)
+//!
+//! 抓包线程把每个包归入一个类别 (HTTPS / QUIC / SSH / DNS / 其他 TCP / 其他 UDP / ICMP / 其他)，
+//! 同时按 IPv4 / IPv6 计数；App 每个采样间隔取出增量算出当前速率，并保留各类别速率的历史，
+//! 画成堆叠图，UDP 洪水或流量转向 QUIC 时一眼就能看出来。
+
+use std::collections::VecDeque;
+
+use crate::decode::{Network, Packet};
+
+/// 速率历史的最大长度，同 StatisticsEngine
+const MAX_HISTORY: usize = 1024;
+
+/// 包的类别，服务端口优先于 L4 协议
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    /// TCP 443
+    Https,
+    /// UDP 443
+    Quic,
+    /// TCP 22
+    Ssh,
+    /// TCP / UDP 53
+    Dns,
+    Tcp,
+    Udp,
+    /// ICMP 和 ICMPv6
+    Icmp,
+    Other,
+}
+
+impl Class {
+    /// 所有类别，顺序即表格和堆叠图中从下到上的顺序
+    pub const ALL: [Class; 8] = [
+        Class::Https,
+        Class::Quic,
+        Class::Ssh,
+        Class::Dns,
+        Class::Tcp,
+        Class::Udp,
+        Class::Icmp,
+        Class::Other,
+    ];
+
+    /// 包所属的类别；任意一端的端口匹配即可
+    pub fn of(packet: &Packet) -> Self {
+        let port = |p: u16| packet.src_port == Some(p) || packet.dst_port == Some(p);
+        match (packet.network, packet.protocol) {
+            (_, Some(6)) if port(443) => Class::Https,
+            (_, Some(17)) if port(443) => Class::Quic,
+            (_, Some(6)) if port(22) => Class::Ssh,
+            (_, Some(6 | 17)) if port(53) => Class::Dns,
+            (_, Some(6)) => Class::Tcp,
+            (_, Some(17)) => Class::Udp,
+            (Network::Ipv4, Some(1)) | (Network::Ipv6, Some(58)) => Class::Icmp,
+            _ => Class::Other,
+        }
+    }
+
+    /// 在 ALL 中的下标
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 抓包线程交给 App 的增量，各项均为 [收, 发] 字节
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Breakdown {
+    /// 按类别，顺序同 Class::ALL
+    pub classes: [[u64; 2]; 8],
+    /// [IPv4, IPv6]
+    pub families: [[u64; 2]; 2],
+}
+
+impl Breakdown {
+    pub fn add(&mut self, packet: &Packet) {
+        let dir = packet.outgoing as usize;
+        let len = packet.len as u64;
+        self.classes[Class::of(packet).index()][dir] += len;
+        match packet.network {
+            Network::Ipv4 => self.families[0][dir] += len,
+            Network::Ipv6 => self.families[1][dir] += len,
+            _ => {}
+        }
+    }
+
+    /// 并入另一份增量 (如 Npcap 回环捕获的)
+    pub fn merge(&mut self, other: &Breakdown) {
+        for (a, b) in self.classes.iter_mut().chain(&mut self.families).zip(other.classes.iter().chain(&other.families)) {
+            a[0] += b[0];
+            a[1] += b[1];
+        }
+    }
+}
+
+/// 各类别的当前速率和历史
+pub struct ProtocolStats {
+    /// 当前速率 [收, 发] (字节/秒)，顺序同 Class::ALL
+    pub classes: [[f64; 2]; 8],
+    /// 当前速率 [收, 发]: [IPv4, IPv6]
+    pub families: [[f64; 2]; 2],
+    /// 各类别收发之和的历史 (front = 最新)，用于堆叠图
+    pub history: VecDeque<Vec<f64>>,
+    /// 上次更新的时间 (秒)
+    last: Option<f64>,
+}
+
+impl ProtocolStats {
+    pub fn new() -> Self {
+        Self {
+            classes: [[0.0; 2]; 8],
+            families: [[0.0; 2]; 2],
+            history: VecDeque::with_capacity(MAX_HISTORY),
+            last: None,
+        }
+    }
+
+    /// 加入一个间隔的增量；`now` 为自启动以来的秒数
+    ///
+    /// 第一次调用只记下时间：抓包开始前的间隔长度未知，算不出速率。
+    pub fn update(&mut self, deltas: Breakdown, now: f64) {
+        let Some(last) = self.last.replace(now) else {
+            return;
+        };
+        let secs = (now - last).max(f64::EPSILON);
+        let rate = |bytes: [u64; 2]| [bytes[0] as f64 / secs, bytes[1] as f64 / secs];
+        self.classes = deltas.classes.map(rate);
+        self.families = deltas.families.map(rate);
+
+        if self.history.len() >= MAX_HISTORY {
+            self.history.pop_back();
+        }
+        self.history.push_front(self.classes.iter().map(|r| r[0] + r[1]).collect());
+    }
+
+    /// 所有类别收发之和的当前速率
+    pub fn total(&self) -> f64 {
+        self.classes.iter().map(|r| r[0] + r[1]).sum()
+    }
+}
+
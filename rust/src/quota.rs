@@ -0,0 +1,249 @@
+//! 流量配额 (data cap) 统计
+//!
+//! 为网卡或网卡组设定每个计费周期的流量上限 (如 500G/month@1)，
+//! 基于 StatisticsEngine 的累计字节数统计本周期用量，并按当前平均速率
+//! 预测周期结束时的总用量。
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+
+use crate::db::TrafficDb;
+use crate::stats::StatisticsEngine;
+
+/// 计费周期
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+/// 单条配额规则及其当前周期的用量
+#[derive(Clone, Debug)]
+pub struct Quota {
+    /// 网卡或组名
+    pub target: String,
+    /// 周期上限 (字节)
+    pub limit: u64,
+    pub period: Period,
+    /// 重置日: 月周期为 1..=31 (超出当月天数时取月末)，周周期为 1(周一)..=7(周日)
+    pub reset_day: u32,
+    /// 本周期已用字节数 (收 + 发)
+    pub used: u64,
+    pub period_start: DateTime<Local>,
+    pub period_end: DateTime<Local>,
+    /// 最近一次看到的引擎累计值 (rx + tx)
+    last_total: Option<u64>,
+    /// 预测的周期结束时总用量
+    pub projected: u64,
+}
+
+/// 解析配额规则: `TARGET=SIZE[/day|/week|/month][@RESET_DAY]`
+///
+/// 例: `wwan0=500G/month@1`、`lte=2G/day`、`uplinks=100G/week@1`
+pub fn parse_quota(s: &str) -> Result<Quota, String> {
+    let (target, rest) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TARGET=SIZE[/PERIOD][@DAY], got: {s}"))?;
+    let target = target.trim();
+    if target.is_empty() {
+        return Err("empty quota target".to_string());
+    }
+
+    let (rest, reset_day) = match rest.split_once('@') {
+        Some((r, d)) => {
+            let day: u32 = d.trim().parse().map_err(|e| format!("invalid reset day: {e}"))?;
+            (r, Some(day))
+        }
+        None => (rest, None),
+    };
+    let (size, period) = match rest.split_once('/') {
+        Some((sz, p)) => {
+            let period = match p.trim().to_lowercase().as_str() {
+                "day" | "d" => Period::Day,
+                "week" | "w" => Period::Week,
+                "month" | "m" => Period::Month,
+                other => return Err(format!("unknown quota period: {other} (day, week, month)")),
+            };
+            (sz, period)
+        }
+        None => (rest, Period::Month),
+    };
+
+    let limit = crate::parse_max_value(size)? as u64;
+    if limit == 0 {
+        return Err("quota size must be greater than zero".to_string());
+    }
+
+    let reset_day = match (period, reset_day) {
+        (Period::Month, Some(d)) if !(1..=31).contains(&d) => {
+            return Err(format!("monthly reset day must be 1..31, got {d}"))
+        }
+        (Period::Week, Some(d)) if !(1..=7).contains(&d) => {
+            return Err(format!("weekly reset day must be 1 (Mon)..7 (Sun), got {d}"))
+        }
+        (_, Some(d)) => d,
+        (_, None) => 1,
+    };
+
+    let now = Local::now();
+    let (period_start, period_end) = period_bounds(period, reset_day, now);
+    Ok(Quota {
+        target: target.to_string(),
+        limit,
+        period,
+        reset_day,
+        used: 0,
+        period_start,
+        period_end,
+        last_total: None,
+        projected: 0,
+    })
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (ny, nm) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(ny, nm, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+fn month_reset_date(year: i32, month: u32, reset_day: u32) -> NaiveDate {
+    let day = reset_day.min(days_in_month(year, month));
+    NaiveDate::from_ymd_opt(year, month, day).unwrap_or_default()
+}
+
+fn local_midnight(date: NaiveDate) -> DateTime<Local> {
+    let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+/// 计算包含 `now` 的计费周期 [start, end)
+fn period_bounds(period: Period, reset_day: u32, now: DateTime<Local>) -> (DateTime<Local>, DateTime<Local>) {
+    let today = now.date_naive();
+    let (start, end) = match period {
+        Period::Day => (today, today + Duration::days(1)),
+        Period::Week => {
+            let back = (today.weekday().number_from_monday() + 7 - reset_day) % 7;
+            let start = today - Duration::days(back as i64);
+            (start, start + Duration::days(7))
+        }
+        Period::Month => {
+            let (mut y, mut m) = (today.year(), today.month());
+            if today < month_reset_date(y, m, reset_day) {
+                if m == 1 {
+                    y -= 1;
+                    m = 12;
+                } else {
+                    m -= 1;
+                }
+            }
+            let start = month_reset_date(y, m, reset_day);
+            let (ny, nm) = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+            (start, month_reset_date(ny, nm, reset_day))
+        }
+    };
+    (local_midnight(start), local_midnight(end))
+}
+
+impl Quota {
+    /// 用持久化数据库中本周期已记录的流量作为起始用量
+    ///
+    /// `members` 为该配额覆盖的网卡 (组展开后的成员)。数据库按天分桶，
+    /// 周期起始当天的流量按整天计入。
+    pub fn seed_from_db(&mut self, db: &TrafficDb, members: &[String]) {
+        let first_day = self.period_start.format("%Y-%m-%d").to_string();
+        self.used = members
+            .iter()
+            .filter_map(|m| db.ifaces.get(m))
+            .flat_map(|rec| rec.days.range(first_day.clone()..))
+            .map(|(_, v)| v.rx.saturating_add(v.tx))
+            .sum();
+    }
+
+    /// 根据引擎的累计值更新用量和预测
+    ///
+    /// `now` 落在当前周期之外时 (进入下一周期，或回放以前的录制) 按它重新计算周期。
+    pub fn update(&mut self, engine: &StatisticsEngine, now: DateTime<Local>) {
+        if now < self.period_start || now >= self.period_end {
+            let (start, end) = period_bounds(self.period, self.reset_day, now);
+            self.period_start = start;
+            self.period_end = end;
+            self.used = 0;
+        }
+
+        let total = engine.incoming.total.saturating_add(engine.outgoing.total);
+        if let Some(last) = self.last_total {
+            // 计数器回退 (重启 / 网卡重建) 时把新值整体视为增量
+            let delta = if total >= last { total - last } else { total };
+            self.used = self.used.saturating_add(delta);
+        }
+        self.last_total = Some(total);
+
+        let rate = engine.incoming.average + engine.outgoing.average;
+        let remaining = (self.period_end - now).num_milliseconds().max(0) as f64 / 1000.0;
+        self.projected = self.used.saturating_add((rate * remaining) as u64);
+    }
+
+    /// 清空本周期用量 (回放往回跳转时使用)
+    pub fn reset(&mut self) {
+        self.used = 0;
+        self.last_total = None;
+        self.projected = 0;
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used)
+    }
+
+    /// 已用百分比 (0..=100+)
+    pub fn percent(&self) -> f64 {
+        self.used as f64 * 100.0 / self.limit as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        local_midnight(NaiveDate::from_ymd_opt(y, m, d).unwrap()) + Duration::hours(h as i64)
+    }
+
+    #[test]
+    fn period_bounds_clamp_reset_day_and_wrap_years() {
+        let month = |now| period_bounds(Period::Month, 31, now);
+        assert_eq!(month(at(2026, 2, 15, 12)), (at(2026, 1, 31, 0), at(2026, 2, 28, 0)));
+        assert_eq!(month(at(2026, 2, 28, 0)), (at(2026, 2, 28, 0), at(2026, 3, 31, 0)));
+        assert_eq!(period_bounds(Period::Month, 5, at(2026, 1, 3, 0)), (at(2025, 12, 5, 0), at(2026, 1, 5, 0)));
+        // 2026-10-19 是周一
+        assert_eq!(period_bounds(Period::Week, 3, at(2026, 10, 19, 8)), (at(2026, 10, 14, 0), at(2026, 10, 21, 0)));
+        assert_eq!(period_bounds(Period::Day, 1, at(2026, 10, 19, 23)), (at(2026, 10, 19, 0), at(2026, 10, 20, 0)));
+    }
+
+    #[test]
+    fn update_moves_period_both_ways() {
+        let mut quota = parse_quota("eth0=1G/month@1").unwrap();
+        let mut engine = StatisticsEngine::new(500, 30);
+
+        // 回放较早的录制: 周期退回到录制所在的月份
+        quota.update(&engine, at(2024, 5, 20, 12));
+        assert_eq!((quota.period_start, quota.period_end), (at(2024, 5, 1, 0), at(2024, 6, 1, 0)));
+        engine.incoming.total = 300;
+        quota.update(&engine, at(2024, 5, 31, 23));
+        assert_eq!(quota.used, 300);
+
+        // 进入下一周期时用量清零，跨周期的增量计入新周期
+        engine.incoming.total = 500;
+        quota.update(&engine, at(2024, 6, 1, 1));
+        assert_eq!((quota.period_start, quota.used), (at(2024, 6, 1, 0), 200));
+
+        // 往回跳转: 周期跟着退回
+        quota.reset();
+        quota.update(&engine, at(2024, 5, 25, 0));
+        assert_eq!((quota.period_start, quota.used), (at(2024, 5, 1, 0), 0));
+    }
+}
@@ -0,0 +1,335 @@
+//! 会话录制与回放
+//!
+//! `--record FILE` 把每次采集到的原始快照 (各网卡的累计计数器) 连同时间戳写入文件，
+//! `--replay FILE` 把文件里的快照按原来的节奏重新送进 StatisticsEngine，
+//! 用同一套界面查看 (可暂停、变速、前后跳转)。适合在服务器上录下事故现场，
+//! 之后拿到笔记本上慢慢看。
+//!
+//! 文件为纯文本，字段之间用制表符分隔，第一行是版本号。每次采集写一行 `f`，
+//! 网卡第一次出现时先写一行 `dev` 分配编号:
+//!
+//! ```text
+//! winload-rec  2
+//! start  2026-10-19T14:00:00+08:00  500
+//! dev    0  eth0  192.168.1.2,10.0.0.1
+//! f      1000  0 <rx> <tx> <rx_pkts> <tx_pkts> <rx_errs> <tx_errs>  1 ...
+//! mark   1500  started deploy
+//! ```
+//!
+//! `start` 行为录制开始的时间和采样间隔 (ms)，`f` 与 `mark` 行的时间为
+//! 相对录制开始的毫秒数。版本 1 的 `f` 行只有 `<rx> <tx>`，读取时包数和错误数记为 0。
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::collector::{DeviceInfo, Snapshot};
+use crate::flows::FlowDeltas;
+use crate::protocols::Breakdown;
+
+const MAGIC: &str = "winload-rec";
+const VERSION: u32 = 2;
+
+/// 录制文件落盘间隔，进程被杀时最多丢这么长时间的数据
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 回放速度的上下限
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 64.0;
+
+// ─── 录制 ──────────────────────────────────────────────────
+
+/// 录制器: 把快照追加写入文件
+pub struct Recorder {
+    out: BufWriter<File>,
+    /// 网卡名 → 文件内编号
+    ids: HashMap<String, usize>,
+    last_flush: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, interval_ms: u64) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {}: {e}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{MAGIC}\t{VERSION}")
+            .and_then(|_| writeln!(out, "start\t{}\t{interval_ms}", Local::now().to_rfc3339()))
+            .map_err(|e| format!("Cannot write {}: {e}", path.display()))?;
+        Ok(Self {
+            out,
+            ids: HashMap::new(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// 写入一次采集的所有快照；`devices` 用于新网卡的地址信息
+    pub fn write_frame(
+        &mut self,
+        elapsed_secs: f64,
+        snapshots: &HashMap<String, Snapshot>,
+        devices: &[DeviceInfo],
+    ) -> io::Result<()> {
+        // 按名称排序，让输出稳定、便于 diff
+        let mut names: Vec<&String> = snapshots.keys().collect();
+        names.sort();
+
+        let mut line = format!("f\t{}", (elapsed_secs * 1000.0).round() as u64);
+        for name in names {
+            let id = match self.ids.get(name) {
+                Some(&id) => id,
+                None => {
+                    let id = self.ids.len();
+                    let addrs = devices
+                        .iter()
+                        .find(|d| &d.name == name)
+                        .map(|d| d.addrs.join(","))
+                        .unwrap_or_default();
+                    writeln!(self.out, "dev\t{id}\t{name}\t{addrs}")?;
+                    self.ids.insert(name.clone(), id);
+                    id
+                }
+            };
+            let snap = &snapshots[name];
+            line.push_str(&format!(
+                "\t{id} {} {} {} {} {} {}",
+                snap.bytes_recv, snap.bytes_sent, snap.packets_recv, snap.packets_sent, snap.errors_recv, snap.errors_sent
+            ));
+        }
+        writeln!(self.out, "{line}")?;
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 写入一个用户标记
+    pub fn mark(&mut self, elapsed_secs: f64, label: &str) -> io::Result<()> {
+        // 标签里的制表符 / 换行会破坏格式
+        let label: String = label.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        writeln!(self.out, "mark\t{}\t{label}", (elapsed_secs * 1000.0).round() as u64)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        self.out.flush()
+    }
+}
+
+// ─── 读取 ──────────────────────────────────────────────────
+
+/// 一次采集: 时间 (秒) 和各网卡的 (编号, 计数器)
+///
+/// 计数器依次为 rx / tx 字节数、rx / tx 包数、rx / tx 错误数
+pub struct Frame {
+    pub t: f64,
+    pub samples: Vec<(usize, [u64; 6])>,
+    /// 这一帧内各连接、各协议的字节数，只有抓包文件 (--read) 才有
+    pub flows: FlowDeltas,
+    pub protocols: Breakdown,
+}
+
+/// 读入内存的录制文件
+pub struct Recording {
+    pub started: DateTime<Local>,
+    pub interval_ms: u64,
+    /// 下标即文件内编号
+    pub devices: Vec<DeviceInfo>,
+    pub frames: Vec<Frame>,
+    /// (时间秒, 标签)，按时间排序
+    pub marks: Vec<(f64, String)>,
+    /// 来自抓包文件，帧里带有连接和协议数据
+    pub captured: bool,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+        let name = path.display().to_string();
+        let bad = |n: usize| format!("{name}: malformed line {}", n + 1);
+        let mut lines = text.lines().enumerate();
+
+        let mut header = lines.next().map(|(_, l)| l.split('\t')).ok_or_else(|| format!("{name}: empty file"))?;
+        if header.next() != Some(MAGIC) {
+            return Err(format!("{name}: not a winload recording"));
+        }
+        let version: u32 = header.next().and_then(|v| v.parse().ok()).ok_or_else(|| bad(0))?;
+        if version > VERSION {
+            return Err(format!(
+                "{name}: recording version {version} is newer than supported ({VERSION})"
+            ));
+        }
+
+        let mut rec = Recording {
+            started: Local::now(),
+            interval_ms: 500,
+            devices: Vec::new(),
+            frames: Vec::new(),
+            marks: Vec::new(),
+            captured: false,
+        };
+        let ms = |s: &str| s.parse::<u64>().map(|v| v as f64 / 1000.0);
+
+        for (n, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let f: Vec<&str> = line.split('\t').collect();
+            match f[0] {
+                "start" if f.len() == 3 => {
+                    rec.started = DateTime::parse_from_rfc3339(f[1]).map_err(|_| bad(n))?.with_timezone(&Local);
+                    rec.interval_ms = f[2].parse().map_err(|_| bad(n))?;
+                }
+                "dev" if f.len() == 4 => {
+                    let id: usize = f[1].parse().map_err(|_| bad(n))?;
+                    if id != rec.devices.len() {
+                        return Err(bad(n));
+                    }
+                    rec.devices.push(DeviceInfo {
+                        name: f[2].to_string(),
+                        addrs: f[3].split(',').filter(|a| !a.is_empty()).map(String::from).collect(),
+                    });
+                }
+                "f" if f.len() >= 2 => {
+                    let t = ms(f[1]).map_err(|_| bad(n))?;
+                    let mut samples = Vec::with_capacity(f.len() - 2);
+                    for field in &f[2..] {
+                        let v: Vec<u64> = field.split(' ').map(|x| x.parse()).collect::<Result<_, _>>().map_err(|_| bad(n))?;
+                        let counters = match v[..] {
+                            [_, rx, tx] if version < 2 => [rx, tx, 0, 0, 0, 0],
+                            [_, rx, tx, prx, ptx, erx, etx] => [rx, tx, prx, ptx, erx, etx],
+                            _ => return Err(bad(n)),
+                        };
+                        let id = v[0] as usize;
+                        if id >= rec.devices.len() {
+                            return Err(bad(n));
+                        }
+                        samples.push((id, counters));
+                    }
+                    rec.frames.push(Frame {
+                        t,
+                        samples,
+                        flows: FlowDeltas::new(),
+                        protocols: Breakdown::default(),
+                    });
+                }
+                "mark" if f.len() >= 2 => {
+                    let t = ms(f[1]).map_err(|_| bad(n))?;
+                    rec.marks.push((t, f.get(2).unwrap_or(&"").to_string()));
+                }
+                _ => return Err(bad(n)),
+            }
+        }
+
+        if rec.frames.is_empty() {
+            return Err(format!("{name}: recording contains no samples"));
+        }
+        rec.marks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(rec)
+    }
+
+    /// 某一帧的快照，按网卡名索引
+    pub fn snapshots(&self, frame: &Frame) -> HashMap<String, Snapshot> {
+        frame
+            .samples
+            .iter()
+            .map(|&(id, [rx, tx, prx, ptx, erx, etx])| {
+                (
+                    self.devices[id].name.clone(),
+                    Snapshot {
+                        elapsed_secs: frame.t,
+                        bytes_recv: rx,
+                        bytes_sent: tx,
+                        packets_recv: prx,
+                        packets_sent: ptx,
+                        errors_recv: erx,
+                        errors_sent: etx,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+// ─── 回放 ──────────────────────────────────────────────────
+
+/// 回放控制: 播放位置、暂停、速度
+pub struct Player {
+    pub rec: Recording,
+    /// 下一个要播放的帧
+    next: usize,
+    /// 播放位置 (录制时间轴上的秒数)
+    pub clock: f64,
+    pub paused: bool,
+    pub speed: f64,
+}
+
+impl Player {
+    pub fn new(rec: Recording) -> Self {
+        let clock = rec.frames[0].t;
+        Self {
+            rec,
+            next: 0,
+            clock,
+            paused: false,
+            speed: 1.0,
+        }
+    }
+
+    pub fn start(&self) -> f64 {
+        self.rec.frames[0].t
+    }
+
+    pub fn end(&self) -> f64 {
+        self.rec.frames[self.rec.frames.len() - 1].t
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.rec.frames.len()
+    }
+
+    /// 播放位置对应的墙上时间
+    pub fn wall_time(&self, t: f64) -> DateTime<Local> {
+        self.rec.started + chrono::Duration::milliseconds((t * 1000.0) as i64)
+    }
+
+    /// 经过 `real_secs` 秒真实时间后应当播放的帧
+    pub fn advance(&mut self, real_secs: f64) -> Range<usize> {
+        if !self.paused {
+            self.clock = (self.clock + real_secs * self.speed).min(self.end());
+        }
+        self.due()
+    }
+
+    /// 跳转 `delta` 秒；返回 true 表示往回跳，调用方需要清空状态后从头重放
+    pub fn seek(&mut self, delta: f64) -> bool {
+        self.clock = (self.clock + delta).clamp(self.start(), self.end());
+        let played = self.next.checked_sub(1).map(|i| self.rec.frames[i].t);
+        let rewind = played.is_some_and(|t| self.clock < t);
+        if rewind {
+            self.next = 0;
+        }
+        rewind
+    }
+
+    /// 时间不晚于播放位置、尚未播放的帧
+    pub fn due(&mut self) -> Range<usize> {
+        let start = self.next;
+        while self.next < self.rec.frames.len() && self.rec.frames[self.next].t <= self.clock {
+            self.next += 1;
+        }
+        start..self.next
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+}
@@ -0,0 +1,379 @@
+//! 远程监控: agent 服务端 (`winload agent`) 和网络数据源 (`--connect`)
+//!
+//! 协议为按行分隔的文本，运行在普通 TCP 上。连接建立后先用共享密钥做双向
+//! HMAC-SHA256 质询-应答，之后 agent 按客户端要求的间隔推送 JSON 快照:
+//!
+//! ```text
+//! S: winload-agent 1 <server_nonce>
+//! C: auth <client_nonce> <hmac(key, "client\n" + server_nonce + "\n" + client_nonce)>
+//! S: ok <hmac(key, "server\n" + server_nonce + "\n" + client_nonce)> <hostname>
+//! C: subscribe <interval_ms>
+//! S: {"devices":[{"name":"eth0","addrs":["10.0.0.2"]}]}          (列表变化时)
+//! S: {"t":12.5,"samples":{"eth0":[rx,tx,rx_pkts,tx_pkts,rx_errs,tx_errs]},
+//!     "links":{"eth0":{"oper":"up","speed":1000}}}                (每个间隔)
+//! ```
+//!
+//! 认证只保证双方持有同一密钥，数据本身不加密；需要保密时请走 SSH 隧道或 VPN。
+//! 密钥从 --key-file 读取，未指定时使用环境变量 WINLOAD_KEY。
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::collector::{self, Collector, DeviceInfo, LinkState, Snapshot};
+use crate::source::{self, Feed, FeedState, Named};
+
+const MAGIC: &str = "winload-agent";
+const VERSION: u32 = 1;
+
+/// 密钥最短长度 (字节)
+const MIN_KEY_LEN: usize = 16;
+/// 握手阶段的超时 (整个握手，不是每次读)
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 握手行的最大长度
+const MAX_HANDSHAKE_LINE: usize = 512;
+/// agent 推送的一行 JSON 的最大长度
+const MAX_MESSAGE_LINE: usize = 4 << 20;
+/// agent 同时处理的未认证连接数上限，超出的连接直接关闭
+const MAX_PENDING: usize = 16;
+/// agent 接受的最小推送间隔
+const MIN_INTERVAL_MS: u64 = 100;
+/// agent 重新扫描网卡列表的间隔
+const LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+type HmacSha256 = Hmac<Sha256>;
+
+// ─── 认证 ──────────────────────────────────────────────────
+
+/// 读取共享密钥: --key-file 优先，其次环境变量 WINLOAD_KEY
+pub fn load_key(path: Option<&Path>) -> Result<Vec<u8>, String> {
+    let key = match path {
+        Some(p) => std::fs::read_to_string(p).map_err(|e| format!("Cannot read key file {}: {e}", p.display()))?,
+        None => std::env::var("WINLOAD_KEY")
+            .map_err(|_| "A shared key is required: pass --key-file PATH or set WINLOAD_KEY".to_string())?,
+    };
+    let key = key.trim();
+    if key.len() < MIN_KEY_LEN {
+        return Err(format!("The shared key must be at least {MIN_KEY_LEN} characters long"));
+    }
+    Ok(key.as_bytes().to_vec())
+}
+
+fn nonce() -> Result<String, String> {
+    let mut buf = [0u8; 16];
+    getrandom::getrandom(&mut buf).map_err(|e| format!("Cannot generate nonce: {e}"))?;
+    Ok(to_hex(&buf))
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn mac(key: &[u8], role: &str, server_nonce: &str, client_nonce: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(format!("{role}\n{server_nonce}\n{client_nonce}").as_bytes());
+    mac
+}
+
+fn sign(key: &[u8], role: &str, server_nonce: &str, client_nonce: &str) -> String {
+    to_hex(&mac(key, role, server_nonce, client_nonce).finalize().into_bytes())
+}
+
+/// 常数时间比较签名
+fn verify(key: &[u8], role: &str, server_nonce: &str, client_nonce: &str, signature: &str) -> bool {
+    from_hex(signature).is_some_and(|sig| mac(key, role, server_nonce, client_nonce).verify_slice(&sig).is_ok())
+}
+
+/// 读一行，最长 `max` 字节
+///
+/// 指定 `deadline` 时整行必须在此之前读完: 每次读之前把套接字的超时缩短到剩余时间，
+/// 对端一次只发一个字节也拖不过期限。
+fn read_line(reader: &mut BufReader<TcpStream>, max: usize, deadline: Option<Instant>) -> Result<String, String> {
+    let mut line = Vec::new();
+    loop {
+        if let Some(deadline) = deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err("handshake timed out".to_string());
+            }
+            reader.get_ref().set_read_timeout(Some(left)).ok();
+        }
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) if deadline.is_some() && matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                return Err("handshake timed out".to_string())
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        if buf.is_empty() {
+            return Err("connection closed".to_string());
+        }
+        let (chunk, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (&buf[..=i], true),
+            None => (buf, false),
+        };
+        if line.len() + chunk.len() > max {
+            return Err("line too long".to_string());
+        }
+        line.extend_from_slice(chunk);
+        let n = chunk.len();
+        reader.consume(n);
+        if done {
+            break;
+        }
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end().to_string())
+        .map_err(|_| "line is not valid UTF-8".to_string())
+}
+
+/// 未认证连接的计数，握手结束 (成功或失败) 时减一
+struct Pending(Arc<AtomicUsize>);
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// ─── agent 服务端 ──────────────────────────────────────────
+
+/// `winload agent`: 监听并为每个通过认证的连接推送本机快照，直到进程结束
+pub fn run_agent(listen: &str, key: Vec<u8>) -> Result<(), String> {
+    let listener = TcpListener::bind(listen).map_err(|e| format!("Cannot listen on {listen}: {e}"))?;
+    let local = listener.local_addr().map_err(|e| e.to_string())?;
+    eprintln!("winload agent listening on {local}");
+    let key = Arc::new(key);
+    let pending = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming().flatten() {
+        let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        // 认证之前不知道对方是谁，限制同时握手的连接数，避免被耗尽线程
+        if pending.fetch_add(1, Ordering::Relaxed) >= MAX_PENDING {
+            pending.fetch_sub(1, Ordering::Relaxed);
+            eprintln!("{peer}: too many unauthenticated connections, dropped");
+            continue;
+        }
+        let guard = Pending(pending.clone());
+        let key = key.clone();
+        thread::spawn(move || {
+            match serve_client(stream, &key, guard) {
+                Ok(()) => eprintln!("{peer}: disconnected"),
+                Err(e) => eprintln!("{peer}: {e}"),
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_client(stream: TcpStream, key: &[u8], pending: Pending) -> Result<(), String> {
+    let deadline = Some(Instant::now() + HANDSHAKE_TIMEOUT);
+    stream.set_nodelay(true).ok();
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut out = stream;
+
+    let server_nonce = nonce()?;
+    writeln!(out, "{MAGIC} {VERSION} {server_nonce}").map_err(|e| e.to_string())?;
+    let line = read_line(&mut reader, MAX_HANDSHAKE_LINE, deadline)?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    let ["auth", client_nonce, signature] = parts[..] else {
+        return Err("malformed handshake".to_string());
+    };
+    if !verify(key, "client", &server_nonce, client_nonce, signature) {
+        let _ = writeln!(out, "error authentication failed");
+        return Err("authentication failed".to_string());
+    }
+    let host = sysinfo::System::host_name().unwrap_or_default().replace(' ', "_");
+    writeln!(out, "ok {} {host}", sign(key, "server", &server_nonce, client_nonce)).map_err(|e| e.to_string())?;
+
+    let line = read_line(&mut reader, MAX_HANDSHAKE_LINE, deadline)?;
+    drop(pending);
+    let interval = line
+        .strip_prefix("subscribe ")
+        .and_then(|ms| ms.parse::<u64>().ok())
+        .ok_or_else(|| "malformed subscribe".to_string())?
+        .max(MIN_INTERVAL_MS);
+    eprintln!("{}: authenticated, streaming every {interval}ms", out.peer_addr().map(|a| a.to_string()).unwrap_or_default());
+
+    let mut collector = Collector::new();
+    let mut last_devices: Option<Vec<(String, Vec<String>)>> = None;
+    let mut last_refresh = Instant::now();
+    loop {
+        if last_refresh.elapsed() >= LIST_REFRESH_INTERVAL {
+            collector.refresh_list();
+            last_refresh = Instant::now();
+        }
+        let devices: Vec<(String, Vec<String>)> =
+            collector.devices().into_iter().map(|d| (d.name, d.addrs)).collect();
+        if last_devices.as_ref() != Some(&devices) {
+            let list: Vec<Value> = devices.iter().map(|(n, a)| json!({ "name": n, "addrs": a })).collect();
+            writeln!(out, "{}", json!({ "devices": list })).map_err(|e| e.to_string())?;
+            last_devices = Some(devices);
+        }
+
+        let snapshots = collector.collect();
+        let mut samples = serde_json::Map::new();
+        let mut links = serde_json::Map::new();
+        for (name, s) in &snapshots {
+            samples.insert(
+                name.clone(),
+                json!([s.bytes_recv, s.bytes_sent, s.packets_recv, s.packets_sent, s.errors_recv, s.errors_sent]),
+            );
+            if let Some(link) = collector::link_state(name) {
+                links.insert(name.clone(), json!({ "oper": link.oper, "speed": link.speed_mbps }));
+            }
+        }
+        let t = collector.elapsed_secs();
+        writeln!(out, "{}", json!({ "t": t, "samples": samples, "links": links })).map_err(|e| e.to_string())?;
+        thread::sleep(Duration::from_millis(interval));
+    }
+}
+
+// ─── 客户端数据源 ──────────────────────────────────────────
+
+/// 连接所有 `--connect [NAME=]HOST:PORT`，等待第一次连接完成，返回 (前缀, 来源)
+///
+/// 未指定 NAME 时前缀为 agent 报告的主机名，见 source::resolve。
+pub fn connect_all(
+    targets: &[String],
+    key: Vec<u8>,
+    interval_ms: u64,
+    start: Instant,
+    multi: bool,
+) -> Result<Vec<Named>, String> {
+    // 先全部发起连接，再逐个等待，总等待时间不随主机数增长
+    let feeds = targets
+        .iter()
+        .map(|t| {
+            let (name, addr) = source::parse_target(t);
+            let (addr_owned, key) = (addr.to_string(), key.clone());
+            let feed = Feed::spawn(addr, move |state| stream_from(&addr_owned, &key, interval_ms, start, state));
+            (name, feed)
+        })
+        .collect();
+    source::resolve(feeds, HANDSHAKE_TIMEOUT, multi)
+}
+
+/// 建立一次连接并持续接收，直到出错
+fn stream_from(addr: &str, key: &[u8], interval_ms: u64, start: Instant, state: &Mutex<FeedState>) -> Result<(), String> {
+    let sock = addr
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve: {e}"))?
+        .next()
+        .ok_or_else(|| "cannot resolve".to_string())?;
+    let deadline = Some(Instant::now() + HANDSHAKE_TIMEOUT);
+    let stream = TcpStream::connect_timeout(&sock, HANDSHAKE_TIMEOUT).map_err(|e| e.to_string())?;
+    stream.set_nodelay(true).ok();
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut out = stream;
+
+    let line = read_line(&mut reader, MAX_HANDSHAKE_LINE, deadline)?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    let [MAGIC, version, server_nonce] = parts[..] else {
+        return Err("not a winload agent".to_string());
+    };
+    if version.parse::<u32>().ok() != Some(VERSION) {
+        return Err(format!("unsupported agent protocol version {version}"));
+    }
+    let client_nonce = nonce()?;
+    writeln!(out, "auth {client_nonce} {}", sign(key, "client", server_nonce, &client_nonce)).map_err(|e| e.to_string())?;
+
+    let line = read_line(&mut reader, MAX_HANDSHAKE_LINE, deadline)?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    let host = match parts[..] {
+        ["ok", signature, host] if verify(key, "server", server_nonce, &client_nonce, signature) => host.to_string(),
+        ["ok", ..] => return Err("agent failed to prove the shared key".to_string()),
+        _ => return Err("authentication rejected (wrong key?)".to_string()),
+    };
+    writeln!(out, "subscribe {interval_ms}").map_err(|e| e.to_string())?;
+
+    // 正常推送时每个间隔都有一行；长时间没有数据视为连接已断
+    let idle = Duration::from_millis(interval_ms * 10).max(HANDSHAKE_TIMEOUT);
+    out.set_read_timeout(Some(idle)).ok();
+    // 远程时间 + offset = 本地时间轴
+    let mut offset: Option<f64> = None;
+
+    loop {
+        let line = read_line(&mut reader, MAX_MESSAGE_LINE, None)?;
+        let msg: Value = serde_json::from_str(&line).map_err(|e| format!("bad message from agent: {e}"))?;
+        let Ok(mut s) = state.lock() else {
+            return Ok(());
+        };
+        if !s.connected {
+            s.name = Some(host.clone());
+            s.mark_connected();
+        }
+        if let Some(list) = msg.get("devices").and_then(Value::as_array) {
+            s.devices = list
+                .iter()
+                .filter_map(|d| {
+                    Some(DeviceInfo {
+                        name: d.get("name")?.as_str()?.to_string(),
+                        addrs: d
+                            .get("addrs")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|a| a.as_str().map(String::from))
+                            .collect(),
+                    })
+                })
+                .collect();
+        }
+        if let (Some(t), Some(samples)) = (msg.get("t").and_then(Value::as_f64), msg.get("samples").and_then(Value::as_object)) {
+            let offset = *offset.get_or_insert_with(|| start.elapsed().as_secs_f64() - t);
+            s.snapshots = samples
+                .iter()
+                .filter_map(|(name, v)| {
+                    let c: Vec<u64> = v.as_array()?.iter().map(|x| x.as_u64()).collect::<Option<_>>()?;
+                    let [rx, tx, prx, ptx, erx, etx] = c[..] else {
+                        return None;
+                    };
+                    Some((
+                        name.clone(),
+                        Snapshot {
+                            elapsed_secs: t + offset,
+                            bytes_recv: rx,
+                            bytes_sent: tx,
+                            packets_recv: prx,
+                            packets_sent: ptx,
+                            errors_recv: erx,
+                            errors_sent: etx,
+                        },
+                    ))
+                })
+                .collect();
+            if let Some(links) = msg.get("links").and_then(Value::as_object) {
+                s.links = links
+                    .iter()
+                    .filter_map(|(name, l)| {
+                        Some((
+                            name.clone(),
+                            LinkState {
+                                oper: l.get("oper")?.as_str()?.to_string(),
+                                speed_mbps: l.get("speed").and_then(Value::as_u64),
+                            },
+                        ))
+                    })
+                    .collect();
+            }
+        }
+    }
+}
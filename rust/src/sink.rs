@@ -0,0 +1,384 @@
+//! 指标推送 (--sink)
+//!
+//! 每次采集后把各设备的统计推送到外部时序库，可重复指定多个目标:
+//!
+//! ```text
+//! --sink influx:http://influx.local:8086/api/v2/write?org=ops&bucket=net
+//! --sink influx-udp:influx.local:8089
+//! --sink graphite:graphite.local:2003
+//! --sink statsd:127.0.0.1:8125
+//! --sink otlp:http://otel-collector.local:4318/v1/metrics
+//! ```
+//!
+//! - `influx:URL`        InfluxDB 行协议，HTTP POST (v1 `/write?db=` 和 v2 `/api/v2/write` 均可)；
+//!   设置了环境变量 INFLUX_TOKEN 时带上 `Authorization: Token ...`
+//! - `influx-udp:ADDR`   InfluxDB 行协议，UDP (默认端口 8089)
+//! - `graphite:ADDR`     Graphite 明文协议，TCP (默认端口 2003)
+//! - `statsd:ADDR`       StatsD gauge，UDP (默认端口 8125)
+//! - `otlp:URL`          OpenTelemetry OTLP/HTTP protobuf (见 otlp.rs)；
+//!   OTEL_EXPORTER_OTLP_HEADERS 中的 `k=v,k2=v2` 会作为请求头发送
+//!
+//! 每个目标一个后台线程: 数据先进入队列，攒够 BATCH_LINES 条或每隔 FLUSH_INTERVAL 发送一次；
+//! 发送失败时保留队列并按指数退避重试，队列超过 MAX_QUEUE 条时丢弃最旧的数据。
+//! 文本协议的一条是一行；OTLP 的一条是一次采集的一个设备。
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::http;
+use crate::otlp;
+use crate::stats::TrafficStats;
+use crate::DeviceView;
+
+/// 发送间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// 每次发送的最大条数
+const BATCH_LINES: usize = 5000;
+/// 队列上限 (条)，目标长时间不可用时丢弃最旧的数据
+const MAX_QUEUE: usize = 100_000;
+/// 重试退避的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// 连接 / 发送超时
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+/// UDP 数据报的最大长度，留在常见 MTU 以内
+const MAX_DATAGRAM: usize = 1400;
+
+/// 推送目标
+#[derive(Clone, Debug)]
+pub enum Sink {
+    InfluxHttp(http::Url),
+    InfluxUdp(String),
+    Graphite(String),
+    Statsd(String),
+    Otlp(http::Url),
+}
+
+/// 解析 --sink
+pub fn parse_sink(s: &str) -> Result<Sink, String> {
+    let s = s.trim();
+    let (kind, target) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid sink '{s}', expected KIND:TARGET (influx, influx-udp, graphite, statsd, otlp)"))?;
+    let target = target.trim();
+    if target.is_empty() {
+        return Err(format!("missing address in sink '{s}'"));
+    }
+    match kind {
+        "influx" => Ok(Sink::InfluxHttp(http::parse_url(target)?)),
+        "influx-udp" => Ok(Sink::InfluxUdp(with_default_port(target, 8089))),
+        "graphite" => Ok(Sink::Graphite(with_default_port(target, 2003))),
+        "statsd" => Ok(Sink::Statsd(with_default_port(target, 8125))),
+        "otlp" => Ok(Sink::Otlp(http::parse_url(target)?)),
+        other => Err(format!(
+            "unknown sink '{other}' (influx:URL, influx-udp:ADDR, graphite:ADDR, statsd:ADDR, otlp:URL)"
+        )),
+    }
+}
+
+/// 没有写端口时补上默认端口 (`[v6]` 和不含冒号的主机名)
+fn with_default_port(addr: &str, port: u16) -> String {
+    if addr.ends_with(']') || !addr.contains(':') {
+        format!("{addr}:{port}")
+    } else {
+        addr.to_string()
+    }
+}
+
+impl Sink {
+    fn name(&self) -> String {
+        match self {
+            Sink::InfluxHttp(url) => format!("influx {}://{}:{}", url.scheme(), url.host, url.port),
+            Sink::InfluxUdp(addr) => format!("influx-udp {addr}"),
+            Sink::Graphite(addr) => format!("graphite {addr}"),
+            Sink::Statsd(addr) => format!("statsd {addr}"),
+            Sink::Otlp(url) => format!("otlp {url}"),
+        }
+    }
+
+    /// 把一次采集渲染成该目标格式的若干条
+    fn items(&self, host: &str, origins: &mut otlp::Origins, views: &[DeviceView], now: DateTime<Local>) -> Vec<Vec<u8>> {
+        if let Sink::Otlp(_) = self {
+            return views.iter().map(|v| otlp::encode_device(host, origins, v, now)).collect();
+        }
+        let mut lines = Vec::new();
+        for view in views {
+            for (dir, stats) in [("in", &view.engine.incoming), ("out", &view.engine.outgoing)] {
+                let fields = fields(stats);
+                match self {
+                    Sink::InfluxHttp(_) | Sink::InfluxUdp(_) => {
+                        let fields: Vec<String> = fields.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                        lines.push(format!(
+                            "winload,host={},device={},direction={dir} {} {}",
+                            influx_tag(host),
+                            influx_tag(&view.info.name),
+                            fields.join(","),
+                            now.timestamp_nanos_opt().unwrap_or_default()
+                        ));
+                    }
+                    Sink::Graphite(_) => {
+                        let prefix = format!("winload.{}.{}.{dir}", path_segment(host), path_segment(&view.info.name));
+                        for (k, v) in &fields {
+                            lines.push(format!("{prefix}.{k} {} {}", v.trim_end_matches('i'), now.timestamp()));
+                        }
+                    }
+                    Sink::Statsd(_) => {
+                        let prefix = format!("winload.{}.{}.{dir}", path_segment(host), path_segment(&view.info.name));
+                        for (k, v) in &fields {
+                            lines.push(format!("{prefix}.{k}:{}|g", v.trim_end_matches('i')));
+                        }
+                    }
+                    Sink::Otlp(_) => unreachable!(),
+                }
+            }
+        }
+        lines.into_iter().map(String::into_bytes).collect()
+    }
+
+    /// 发送一批数据；`headers` 为额外的 HTTP 请求头
+    fn send(&self, items: &[Vec<u8>], headers: &[(String, String)]) -> Result<(), String> {
+        let mut extra: Vec<(&str, &str)> = headers.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        match self {
+            Sink::InfluxHttp(url) => {
+                extra.push(("Content-Type", "text/plain; charset=utf-8"));
+                http::post(url, &extra, &items.join(&b'\n'), SEND_TIMEOUT)
+            }
+            // 每条都是 ExportMetricsServiceRequest 的一个 resource_metrics 字段，直接拼接即可
+            Sink::Otlp(url) => {
+                extra.push(("Content-Type", "application/x-protobuf"));
+                http::post(url, &extra, &items.concat(), SEND_TIMEOUT)
+            }
+            Sink::Graphite(addr) => {
+                let sock = resolve(addr)?;
+                let mut stream = TcpStream::connect_timeout(&sock, SEND_TIMEOUT).map_err(|e| format!("connect {addr}: {e}"))?;
+                stream.set_write_timeout(Some(SEND_TIMEOUT)).ok();
+                let mut body = items.join(&b'\n');
+                body.push(b'\n');
+                stream.write_all(&body).map_err(|e| format!("send to {addr}: {e}"))
+            }
+            Sink::InfluxUdp(addr) | Sink::Statsd(addr) => {
+                let sock = resolve(addr)?;
+                let bind = if sock.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+                let udp = UdpSocket::bind(bind).map_err(|e| format!("bind {bind}: {e}"))?;
+                for datagram in datagrams(items) {
+                    udp.send_to(&datagram, sock).map_err(|e| format!("send to {addr}: {e}"))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 每个方向导出的字段；整数带 Influx 的 `i` 后缀，其他格式发送时去掉
+fn fields(s: &TrafficStats) -> [(&'static str, String); 5] {
+    [
+        ("bytes", format!("{}i", s.total)),
+        ("packets", format!("{}i", s.packets)),
+        ("errors", format!("{}i", s.errors)),
+        ("rate", format!("{:.2}", s.current)),
+        ("average", format!("{:.2}", s.average)),
+    ]
+}
+
+/// Influx tag 值转义: 逗号、等号、空格
+fn influx_tag(s: &str) -> String {
+    s.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// Graphite / StatsD 路径的一段: 只保留字母数字、`-` 和 `_`
+fn path_segment(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn resolve(addr: &str) -> Result<std::net::SocketAddr, String> {
+    addr.to_socket_addrs()
+        .map_err(|e| format!("cannot resolve {addr}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {addr}"))
+}
+
+/// 按行拼成不超过 MAX_DATAGRAM 的数据报
+fn datagrams(lines: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut out: Vec<Vec<u8>> = Vec::new();
+    for line in lines {
+        match out.last_mut() {
+            Some(cur) if cur.len() + 1 + line.len() <= MAX_DATAGRAM => {
+                cur.push(b'\n');
+                cur.extend_from_slice(line);
+            }
+            _ => out.push(line.clone()),
+        }
+    }
+    out
+}
+
+/// 各目标的额外请求头: Influx 的 INFLUX_TOKEN，OTLP 的 OTEL_EXPORTER_OTLP_HEADERS
+fn extra_headers(sink: &Sink) -> Vec<(String, String)> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    match sink {
+        Sink::InfluxHttp(_) => env("INFLUX_TOKEN")
+            .map(|t| vec![("Authorization".to_string(), format!("Token {t}"))])
+            .unwrap_or_default(),
+        Sink::Otlp(_) => env("OTEL_EXPORTER_OTLP_HEADERS")
+            .map(|h| {
+                h.split(',')
+                    .filter_map(|kv| kv.split_once('='))
+                    .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+// ─── 后台发送 ──────────────────────────────────────────────
+
+enum Msg {
+    Items(Vec<Vec<u8>>),
+    /// 退出前最后发送一次
+    Shutdown,
+}
+
+struct Worker {
+    sink: Sink,
+    tx: Sender<Msg>,
+    handle: JoinHandle<()>,
+}
+
+/// 所有推送目标
+pub struct Sinks {
+    host: String,
+    /// OTLP 累计值的起点
+    origins: otlp::Origins,
+    workers: Vec<Worker>,
+    errors_tx: Sender<String>,
+    errors_rx: Receiver<String>,
+}
+
+impl Sinks {
+    pub fn start(sinks: &[Sink]) -> Self {
+        let (errors_tx, errors_rx) = mpsc::channel();
+        let mut workers = Vec::new();
+        for sink in sinks {
+            let (tx, rx) = mpsc::channel();
+            let (worker_sink, errors) = (sink.clone(), errors_tx.clone());
+            let spawned = thread::Builder::new()
+                .name("metrics-sink".to_string())
+                .spawn(move || run_worker(worker_sink, rx, errors));
+            match spawned {
+                Ok(handle) => workers.push(Worker {
+                    sink: sink.clone(),
+                    tx,
+                    handle,
+                }),
+                Err(e) => {
+                    let _ = errors_tx.send(format!("Failed to spawn sink thread: {e}"));
+                }
+            }
+        }
+        Self {
+            host: sysinfo::System::host_name().unwrap_or_default(),
+            origins: otlp::Origins::new(),
+            workers,
+            errors_tx,
+            errors_rx,
+        }
+    }
+
+    /// 把一次采集交给各目标的发送线程
+    pub fn publish(&mut self, views: &[DeviceView], now: DateTime<Local>) {
+        for worker in &self.workers {
+            let items = worker.sink.items(&self.host, &mut self.origins, views, now);
+            if worker.tx.send(Msg::Items(items)).is_err() {
+                let _ = self.errors_tx.send(format!("{}: sink thread exited", worker.sink.name()));
+            }
+        }
+    }
+
+    /// 取出发送线程报告的错误
+    pub fn drain_errors(&self) -> Vec<String> {
+        self.errors_rx.try_iter().collect()
+    }
+
+    /// 退出前把队列中剩余的数据发出去 (每个目标只尝试一次)
+    pub fn shutdown(&mut self) {
+        for worker in self.workers.drain(..) {
+            let _ = worker.tx.send(Msg::Shutdown);
+            let _ = worker.handle.join();
+        }
+    }
+}
+
+fn run_worker(sink: Sink, rx: Receiver<Msg>, errors: Sender<String>) {
+    let headers = extra_headers(&sink);
+    let mut queue: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut last_send = Instant::now();
+    let mut retry_at: Option<Instant> = None;
+    let mut backoff = Duration::from_secs(1);
+    // 只在状态变化时报告错误，避免每个间隔都刷一条
+    let mut failing = false;
+    let mut dropping = false;
+
+    loop {
+        let shutdown = match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(Msg::Items(items)) => {
+                queue.extend(items);
+                false
+            }
+            Ok(Msg::Shutdown) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        };
+        if queue.len() > MAX_QUEUE {
+            queue.drain(..queue.len() - MAX_QUEUE);
+            if !dropping {
+                let _ = errors.send(format!("{}: queue full, dropping oldest samples", sink.name()));
+            }
+            dropping = true;
+        }
+
+        let due = match retry_at {
+            Some(t) => Instant::now() >= t,
+            None => last_send.elapsed() >= FLUSH_INTERVAL || queue.len() >= BATCH_LINES,
+        };
+        if (due || shutdown) && !queue.is_empty() {
+            last_send = Instant::now();
+            match flush(&sink, &mut queue, &headers) {
+                Ok(()) => {
+                    retry_at = None;
+                    backoff = Duration::from_secs(1);
+                    failing = false;
+                    dropping = false;
+                }
+                Err(e) => {
+                    if !failing && !shutdown {
+                        let _ = errors.send(format!("{}: {e} (retrying)", sink.name()));
+                    }
+                    failing = true;
+                    retry_at = Some(Instant::now() + backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+        if shutdown {
+            return;
+        }
+    }
+}
+
+/// 分批发送队列中的数据，成功的批次从队列中移除；遇到失败立即停止
+fn flush(sink: &Sink, queue: &mut VecDeque<Vec<u8>>, headers: &[(String, String)]) -> Result<(), String> {
+    while !queue.is_empty() {
+        let n = queue.len().min(BATCH_LINES);
+        let batch: Vec<Vec<u8>> = queue.iter().take(n).cloned().collect();
+        sink.send(&batch, headers)?;
+        queue.drain(..n);
+    }
+    Ok(())
+}
@@ -0,0 +1,1177 @@
+//! SNMP 数据源 (--snmp)
+//!
+//! 通过 SNMP v2c 或 v3 (USM) 轮询路由器、交换机的 IF-MIB，每个端口作为一个设备显示:
+//!
+//! - 设备名为 `ifDescr`，`ifAlias` (端口描述) 显示在地址的位置
+//! - 计数器: `ifHCInOctets` / `ifHCOutOctets` (64 位)、`ifHCInUcastPkts` /
+//!   `ifHCOutUcastPkts`、`ifInErrors` / `ifOutErrors`
+//! - 链路状态: `ifOperStatus`、`ifHighSpeed`
+//!
+//! 每次采集用 GetBulk 遍历这些列；端口列表每 LIST_REFRESH_INTERVAL 重新读取一次。
+//! 轮询在后台线程中进行，网络慢或设备无响应时不阻塞界面。
+//!
+//! v3 支持 authNoPriv / authPriv，认证为 HMAC-MD5-96、HMAC-SHA-96、HMAC-SHA-256-192，
+//! 加密为 AES-128-CFB (RFC 3826)；口令从环境变量读取，避免出现在进程列表中。
+//! BER 编解码只实现了用到的部分。
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aes::Aes128;
+use cfb_mode::cipher::{AsyncStreamCipher, KeyIvInit};
+use clap::ValueEnum;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::collector::{DeviceInfo, LinkState, Snapshot};
+use crate::source::{self, Feed, FeedState, Named};
+
+const DEFAULT_PORT: u16 = 161;
+/// 单次请求的超时和重试次数
+const TIMEOUT: Duration = Duration::from_secs(2);
+const RETRIES: usize = 2;
+/// 端口列表 (ifDescr / ifAlias) 的刷新间隔
+const LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// 单个 GetBulk 响应中期望的最多变量数，避免超过设备的报文大小限制
+const MAX_VARBINDS: usize = 48;
+/// 认证口令最短长度 (RFC 3414)
+const MIN_PASS_LEN: usize = 8;
+
+// ─── OID ───────────────────────────────────────────────────
+
+const SYS_NAME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 5, 0];
+const IF_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2];
+const IF_OPER_STATUS: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 8];
+const IF_IN_ERRORS: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 14];
+const IF_OUT_ERRORS: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 20];
+const IF_HC_IN_OCTETS: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6];
+const IF_HC_IN_UCAST: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 7];
+const IF_HC_OUT_OCTETS: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 10];
+const IF_HC_OUT_UCAST: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 11];
+const IF_HIGH_SPEED: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 15];
+const IF_ALIAS: &[u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 18];
+
+/// 每次采集遍历的列，顺序与 poll 中的下标对应
+const COUNTER_COLUMNS: [&[u32]; 8] = [
+    IF_HC_IN_OCTETS,
+    IF_HC_OUT_OCTETS,
+    IF_HC_IN_UCAST,
+    IF_HC_OUT_UCAST,
+    IF_IN_ERRORS,
+    IF_OUT_ERRORS,
+    IF_OPER_STATUS,
+    IF_HIGH_SPEED,
+];
+
+/// usmStats 计数器 (Report PDU 中说明失败原因)
+const USM_STATS: &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1];
+const NOT_IN_TIME_WINDOWS: u32 = 2;
+const UNKNOWN_ENGINE_IDS: u32 = 4;
+
+// ─── 认证参数 ──────────────────────────────────────────────
+
+/// v3 认证协议
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum AuthProto {
+    Md5,
+    Sha,
+    Sha256,
+}
+
+/// v3 加密协议
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum PrivProto {
+    Aes,
+}
+
+/// SNMP 凭据
+#[derive(Clone)]
+pub enum Credentials {
+    /// v2c community
+    Community(String),
+    V3(V3),
+}
+
+/// v3 用户 (USM)
+#[derive(Clone)]
+pub struct V3 {
+    user: String,
+    auth: Option<(AuthProto, String)>,
+    privacy: Option<(PrivProto, String)>,
+}
+
+/// 根据命令行参数生成凭据；v3 口令从 WINLOAD_SNMP_AUTH_PASS / WINLOAD_SNMP_PRIV_PASS 读取
+pub fn credentials(
+    community: &str,
+    user: Option<&str>,
+    auth: Option<AuthProto>,
+    privacy: Option<PrivProto>,
+) -> Result<Credentials, String> {
+    let Some(user) = user else {
+        return Ok(Credentials::Community(community.to_string()));
+    };
+    let pass = |var: &str| -> Result<String, String> {
+        let pass = std::env::var(var).map_err(|_| format!("{var} must be set for SNMPv3 user '{user}'"))?;
+        if pass.len() < MIN_PASS_LEN {
+            return Err(format!("{var} must be at least {MIN_PASS_LEN} characters long"));
+        }
+        Ok(pass)
+    };
+    Ok(Credentials::V3(V3 {
+        user: user.to_string(),
+        auth: auth.map(|p| pass("WINLOAD_SNMP_AUTH_PASS").map(|pw| (p, pw))).transpose()?,
+        privacy: privacy.map(|p| pass("WINLOAD_SNMP_PRIV_PASS").map(|pw| (p, pw))).transpose()?,
+    }))
+}
+
+impl AuthProto {
+    /// 报文中认证码的长度
+    fn mac_len(self) -> usize {
+        match self {
+            AuthProto::Md5 | AuthProto::Sha => 12,
+            AuthProto::Sha256 => 24,
+        }
+    }
+
+    /// 口令 → 本地化密钥 (RFC 3414 A.2)
+    fn localized_key(self, pass: &str, engine_id: &[u8]) -> Vec<u8> {
+        match self {
+            AuthProto::Md5 => localize::<Md5>(pass.as_bytes(), engine_id),
+            AuthProto::Sha => localize::<Sha1>(pass.as_bytes(), engine_id),
+            AuthProto::Sha256 => localize::<Sha256>(pass.as_bytes(), engine_id),
+        }
+    }
+
+    fn mac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = match self {
+            AuthProto::Md5 => mac_with::<Hmac<Md5>>(key, data),
+            AuthProto::Sha => mac_with::<Hmac<Sha1>>(key, data),
+            AuthProto::Sha256 => mac_with::<Hmac<Sha256>>(key, data),
+        };
+        mac.truncate(self.mac_len());
+        mac
+    }
+}
+
+/// 把口令重复到 1 MB 做摘要，再与引擎 ID 组合: H(Ku || engineID || Ku)
+fn localize<D: Digest>(pass: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut d = D::new();
+    let mut chunk = [0u8; 64];
+    for i in 0..(1 << 20) / chunk.len() {
+        for (j, b) in chunk.iter_mut().enumerate() {
+            *b = pass[(i * 64 + j) % pass.len()];
+        }
+        d.update(chunk);
+    }
+    let ku = d.finalize();
+    let mut d = D::new();
+    d.update(&ku);
+    d.update(engine_id);
+    d.update(&ku);
+    d.finalize().to_vec()
+}
+
+fn mac_with<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// AES-128-CFB，IV 为 engineBoots || engineTime || salt (RFC 3826)
+fn aes_cfb(key: &[u8], boots: u32, time: u32, salt: &[u8], data: &mut [u8], encrypt: bool) -> Result<(), String> {
+    let key = key.get(..16).ok_or("privacy key too short")?;
+    let mut iv = [0u8; 16];
+    iv[..4].copy_from_slice(&boots.to_be_bytes());
+    iv[4..8].copy_from_slice(&time.to_be_bytes());
+    iv[8..].copy_from_slice(salt.get(..8).ok_or("bad privacy parameters")?);
+    if encrypt {
+        cfb_mode::Encryptor::<Aes128>::new_from_slices(key, &iv).map_err(|e| e.to_string())?.encrypt(data);
+    } else {
+        cfb_mode::Decryptor::<Aes128>::new_from_slices(key, &iv).map_err(|e| e.to_string())?.decrypt(data);
+    }
+    Ok(())
+}
+
+// ─── BER 编解码 ────────────────────────────────────────────
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_ID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const IP_ADDRESS: u8 = 0x40;
+const COUNTER32: u8 = 0x41;
+const GAUGE32: u8 = 0x42;
+const TIME_TICKS: u8 = 0x43;
+const COUNTER64: u8 = 0x46;
+const NO_SUCH_OBJECT: u8 = 0x80;
+const NO_SUCH_INSTANCE: u8 = 0x81;
+const END_OF_MIB_VIEW: u8 = 0x82;
+
+const GET_REQUEST: u8 = 0xa0;
+const RESPONSE: u8 = 0xa2;
+const GET_BULK: u8 = 0xa5;
+const REPORT: u8 = 0xa8;
+
+fn put_len(buf: &mut Vec<u8>, len: usize) {
+    if len < 0x80 {
+        buf.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        buf.push(0x80 | (bytes.len() - skip) as u8);
+        buf.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+fn put_tlv(buf: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    buf.push(tag);
+    put_len(buf, content.len());
+    buf.extend_from_slice(content);
+}
+
+/// 构造类型: 先编码到临时缓冲区再写入长度
+fn put_constructed(buf: &mut Vec<u8>, tag: u8, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut inner = Vec::new();
+    f(&mut inner);
+    put_tlv(buf, tag, &inner);
+}
+
+fn put_int(buf: &mut Vec<u8>, v: i64) {
+    let bytes = v.to_be_bytes();
+    // 去掉多余的符号扩展字节
+    let mut start = 0;
+    while start < 7 && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0) || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0)) {
+        start += 1;
+    }
+    put_tlv(buf, INTEGER, &bytes[start..]);
+}
+
+fn put_oid(buf: &mut Vec<u8>, oid: &[u32]) {
+    let mut content = vec![(oid[0] * 40 + oid[1]) as u8];
+    for &sub in &oid[2..] {
+        let mut chunk = vec![(sub & 0x7f) as u8];
+        let mut v = sub >> 7;
+        while v > 0 {
+            chunk.push((v & 0x7f) as u8 | 0x80);
+            v >>= 7;
+        }
+        content.extend(chunk.iter().rev());
+    }
+    put_tlv(buf, OBJECT_ID, &content);
+}
+
+/// 一个已解码的 TLV，`data` 指向原始报文中的内容
+struct Tlv<'a> {
+    tag: u8,
+    data: &'a [u8],
+}
+
+fn read_tlv<'a>(buf: &mut &'a [u8]) -> Result<Tlv<'a>, String> {
+    let malformed = || "malformed BER".to_string();
+    let (&tag, rest) = buf.split_first().ok_or_else(malformed)?;
+    let (&first, mut rest) = rest.split_first().ok_or_else(malformed)?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return Err(malformed());
+        }
+        let len = rest[..n].iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        rest = &rest[n..];
+        len
+    };
+    if rest.len() < len {
+        return Err(malformed());
+    }
+    let (data, rest) = rest.split_at(len);
+    *buf = rest;
+    Ok(Tlv { tag, data })
+}
+
+/// 构造类型的全部子项
+fn items<'a>(tlv: &Tlv<'a>, tag: u8) -> Result<Vec<Tlv<'a>>, String> {
+    if tlv.tag != tag {
+        return Err(format!("expected tag {tag:#04x}, got {:#04x}", tlv.tag));
+    }
+    let mut data = tlv.data;
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        out.push(read_tlv(&mut data)?);
+    }
+    Ok(out)
+}
+
+fn as_int(tlv: &Tlv) -> Result<i64, String> {
+    if tlv.tag != INTEGER || tlv.data.is_empty() || tlv.data.len() > 8 {
+        return Err("expected INTEGER".to_string());
+    }
+    let init = if tlv.data[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Ok(tlv.data.iter().fold(init, |acc, &b| (acc << 8) | b as i64))
+}
+
+fn as_uint(data: &[u8]) -> u64 {
+    data.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn as_octets<'a>(tlv: &Tlv<'a>) -> Result<&'a [u8], String> {
+    if tlv.tag != OCTET_STRING {
+        return Err("expected OCTET STRING".to_string());
+    }
+    Ok(tlv.data)
+}
+
+fn as_oid(tlv: &Tlv) -> Result<Vec<u32>, String> {
+    let (&first, rest) = tlv.data.split_first().filter(|_| tlv.tag == OBJECT_ID).ok_or("expected OBJECT IDENTIFIER")?;
+    let mut oid = vec![(first / 40) as u32, (first % 40) as u32];
+    let mut sub = 0u32;
+    for &b in rest {
+        sub = (sub << 7) | (b & 0x7f) as u32;
+        if b & 0x80 == 0 {
+            oid.push(sub);
+            sub = 0;
+        }
+    }
+    Ok(oid)
+}
+
+/// 变量的值 (只区分用到的类型)
+#[derive(Clone, Debug)]
+enum Value {
+    Int(i64),
+    /// Counter32 / Gauge32 / TimeTicks / Counter64
+    Uint(u64),
+    Octets(Vec<u8>),
+    /// NULL、noSuchObject、noSuchInstance 及其他类型
+    Missing,
+    EndOfMibView,
+}
+
+impl Value {
+    fn decode(tlv: &Tlv) -> Value {
+        match tlv.tag {
+            INTEGER => as_int(tlv).map(Value::Int).unwrap_or(Value::Missing),
+            COUNTER32 | GAUGE32 | TIME_TICKS | COUNTER64 => Value::Uint(as_uint(tlv.data)),
+            OCTET_STRING | IP_ADDRESS => Value::Octets(tlv.data.to_vec()),
+            END_OF_MIB_VIEW => Value::EndOfMibView,
+            NULL | NO_SUCH_OBJECT | NO_SUCH_INSTANCE => Value::Missing,
+            _ => Value::Missing,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Uint(v) => Some(v),
+            Value::Int(v) => u64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_text(&self) -> Option<String> {
+        match self {
+            Value::Octets(b) => Some(String::from_utf8_lossy(b).trim_end_matches('\0').trim().to_string()),
+            _ => None,
+        }
+    }
+}
+
+type VarBinds = Vec<(Vec<u32>, Value)>;
+
+/// GetRequest / GetBulk 等 PDU；`a`、`b` 为 error-status / error-index 或 non-repeaters / max-repetitions
+fn encode_pdu(tag: u8, request_id: i32, a: i64, b: i64, oids: &[Vec<u32>]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_constructed(&mut buf, tag, |pdu| {
+        put_int(pdu, request_id as i64);
+        put_int(pdu, a);
+        put_int(pdu, b);
+        put_constructed(pdu, SEQUENCE, |list| {
+            for oid in oids {
+                put_constructed(list, SEQUENCE, |vb| {
+                    put_oid(vb, oid);
+                    put_tlv(vb, NULL, &[]);
+                });
+            }
+        });
+    });
+    buf
+}
+
+struct Pdu {
+    tag: u8,
+    request_id: i64,
+    error_status: i64,
+    varbinds: VarBinds,
+}
+
+fn decode_pdu(tlv: &Tlv) -> Result<Pdu, String> {
+    if tlv.tag != RESPONSE && tlv.tag != REPORT {
+        return Err(format!("unexpected PDU type {:#04x}", tlv.tag));
+    }
+    let mut data = tlv.data;
+    let request_id = as_int(&read_tlv(&mut data)?)?;
+    let error_status = as_int(&read_tlv(&mut data)?)?;
+    let _error_index = read_tlv(&mut data)?;
+    let mut varbinds = Vec::new();
+    for vb in items(&read_tlv(&mut data)?, SEQUENCE)? {
+        let parts = items(&vb, SEQUENCE)?;
+        let [ref name, ref value] = parts[..] else {
+            return Err("malformed variable binding".to_string());
+        };
+        varbinds.push((as_oid(name)?, Value::decode(value)));
+    }
+    Ok(Pdu {
+        tag: tlv.tag,
+        request_id,
+        error_status,
+        varbinds,
+    })
+}
+
+fn error_status_name(status: i64) -> String {
+    match status {
+        1 => "tooBig".to_string(),
+        2 => "noSuchName".to_string(),
+        5 => "genErr".to_string(),
+        6 => "noAccess".to_string(),
+        16 => "authorizationError".to_string(),
+        n => format!("error-status {n}"),
+    }
+}
+
+// ─── v3 报文 ───────────────────────────────────────────────
+
+const FLAG_AUTH: u8 = 0x01;
+const FLAG_PRIV: u8 = 0x02;
+const FLAG_REPORTABLE: u8 = 0x04;
+const USM_SECURITY_MODEL: i64 = 3;
+const MAX_MSG_SIZE: i64 = 65507;
+
+/// 与 agent 引擎同步后的 USM 状态
+struct Usm {
+    engine_id: Vec<u8>,
+    boots: u32,
+    time: u32,
+    /// 得到 boots / time 的时刻，用于推算当前 engineTime
+    synced: Instant,
+    auth_key: Vec<u8>,
+    priv_key: Vec<u8>,
+    salt: u64,
+}
+
+impl Usm {
+    fn engine_time(&self) -> u32 {
+        self.time.saturating_add(self.synced.elapsed().as_secs() as u32)
+    }
+}
+
+/// 解析后的 v3 报文
+struct V3Message<'a> {
+    msg_id: i64,
+    flags: u8,
+    engine_id: &'a [u8],
+    boots: u32,
+    time: u32,
+    /// msgAuthenticationParameters 在原始报文中的位置
+    auth_range: Range<usize>,
+    priv_params: &'a [u8],
+    /// ScopedPDU (明文) 或加密后的 OCTET STRING
+    data: Tlv<'a>,
+}
+
+fn parse_v3(msg: &[u8]) -> Result<V3Message<'_>, String> {
+    let mut buf = msg;
+    let top = items(&read_tlv(&mut buf)?, SEQUENCE)?;
+    let [ref version, ref global, ref security, ref data] = top[..] else {
+        return Err("malformed SNMPv3 message".to_string());
+    };
+    if as_int(version)? != 3 {
+        return Err("not an SNMPv3 message".to_string());
+    }
+    let global = items(global, SEQUENCE)?;
+    let msg_id = as_int(global.first().ok_or("malformed msgGlobalData")?)?;
+    let flags = *global
+        .get(2)
+        .map(as_octets)
+        .transpose()?
+        .and_then(|f| f.first())
+        .ok_or("malformed msgFlags")?;
+    let mut sec = as_octets(security)?;
+    let sec = items(&read_tlv(&mut sec)?, SEQUENCE)?;
+    let [ref engine_id, ref boots, ref time, _, ref auth, ref privacy] = sec[..] else {
+        return Err("malformed USM security parameters".to_string());
+    };
+    let auth = as_octets(auth)?;
+    let offset = auth.as_ptr() as usize - msg.as_ptr() as usize;
+    Ok(V3Message {
+        msg_id,
+        flags,
+        engine_id: as_octets(engine_id)?,
+        boots: as_int(boots)? as u32,
+        time: as_int(time)? as u32,
+        auth_range: offset..offset + auth.len(),
+        priv_params: as_octets(privacy)?,
+        data: Tlv {
+            tag: data.tag,
+            data: data.data,
+        },
+    })
+}
+
+// ─── 会话 ──────────────────────────────────────────────────
+
+/// 与一台设备的 SNMP 会话
+struct Session {
+    sock: UdpSocket,
+    creds: Credentials,
+    /// v3 引擎状态，发现前为 None
+    usm: Option<Usm>,
+    next_id: i32,
+}
+
+impl Session {
+    fn open(target: &str, creds: Credentials) -> Result<Self, String> {
+        // 未指定端口时使用 161
+        let addr = target
+            .to_socket_addrs()
+            .or_else(|_| (target.trim_start_matches('[').trim_end_matches(']'), DEFAULT_PORT).to_socket_addrs())
+            .map_err(|e| format!("cannot resolve: {e}"))?
+            .next()
+            .ok_or("cannot resolve")?;
+        let bind = if addr.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+        let sock = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+        sock.connect(addr).map_err(|e| e.to_string())?;
+        sock.set_read_timeout(Some(TIMEOUT)).ok();
+        let mut next_id = [0u8; 4];
+        getrandom::getrandom(&mut next_id).map_err(|e| e.to_string())?;
+        let mut session = Self {
+            sock,
+            creds,
+            usm: None,
+            next_id: i32::from_be_bytes(next_id) & 0x3fff_ffff,
+        };
+        if matches!(session.creds, Credentials::V3(_)) {
+            session.discover()?;
+        }
+        Ok(session)
+    }
+
+    /// 引擎发现: 发送不带用户的请求，从 Report 中得到 engineID、boots 和 time，并本地化密钥
+    fn discover(&mut self) -> Result<(), String> {
+        let Credentials::V3(ref v3) = self.creds else {
+            return Ok(());
+        };
+        let v3 = v3.clone();
+        self.usm = None;
+        let reply = self.exchange(GET_REQUEST, 0, 0, &[])?;
+        let (engine_id, boots, time) = reply.engine.ok_or("no engine ID in discovery response")?;
+        let auth_key = v3.auth.as_ref().map(|(p, pass)| p.localized_key(pass, &engine_id)).unwrap_or_default();
+        // 加密密钥用认证协议的摘要算法本地化
+        let priv_key = match (&v3.auth, &v3.privacy) {
+            (Some((p, _)), Some((_, pass))) => p.localized_key(pass, &engine_id),
+            _ => Vec::new(),
+        };
+        let mut salt = [0u8; 8];
+        getrandom::getrandom(&mut salt).map_err(|e| e.to_string())?;
+        self.usm = Some(Usm {
+            engine_id,
+            boots,
+            time,
+            synced: Instant::now(),
+            auth_key,
+            priv_key,
+            salt: u64::from_be_bytes(salt),
+        });
+        Ok(())
+    }
+
+    /// 发送请求并返回响应中的变量；处理 v3 时间窗口失步
+    fn request(&mut self, tag: u8, a: i64, b: i64, oids: &[Vec<u32>]) -> Result<VarBinds, String> {
+        let mut resynced = false;
+        loop {
+            let reply = self.exchange(tag, a, b, oids)?;
+            if reply.pdu.tag == REPORT {
+                let reason = reply.pdu.varbinds.first().map(|(oid, _)| oid.clone()).unwrap_or_default();
+                let counter = reason.strip_prefix(USM_STATS).and_then(|r| r.first().copied());
+                // 设备重启后 boots / time 变化，重新同步一次
+                if !resynced && matches!(counter, Some(NOT_IN_TIME_WINDOWS | UNKNOWN_ENGINE_IDS)) {
+                    resynced = true;
+                    self.discover()?;
+                    continue;
+                }
+                return Err(match counter {
+                    Some(1) => "unsupported security level for this user".to_string(),
+                    Some(NOT_IN_TIME_WINDOWS) => "not in time window".to_string(),
+                    Some(3) => "unknown user name".to_string(),
+                    Some(UNKNOWN_ENGINE_IDS) => "unknown engine ID".to_string(),
+                    Some(5) => "wrong digest (check the authentication passphrase)".to_string(),
+                    Some(6) => "decryption error (check the privacy passphrase)".to_string(),
+                    _ => "request rejected (report PDU)".to_string(),
+                });
+            }
+            if reply.pdu.error_status != 0 {
+                return Err(error_status_name(reply.pdu.error_status));
+            }
+            return Ok(reply.pdu.varbinds);
+        }
+    }
+
+    /// 发送一个 PDU 并等待对应的响应，超时重试
+    ///
+    /// 解不开或认证失败的报文直接丢弃、继续等待 (可能是伪造或损坏的)，
+    /// 直到超时都没有合法响应时才报告最后一个解码错误。
+    fn exchange(&mut self, tag: u8, a: i64, b: i64, oids: &[Vec<u32>]) -> Result<Reply, String> {
+        let mut rejected = None;
+        for _ in 0..=RETRIES {
+            self.next_id = self.next_id.wrapping_add(1) & 0x7fff_ffff;
+            let id = self.next_id;
+            let pdu = encode_pdu(tag, id, a, b, oids);
+            let msg = self.encode(id, &pdu)?;
+            self.sock.send(&msg).map_err(|e| e.to_string())?;
+
+            let deadline = Instant::now() + TIMEOUT;
+            let mut buf = vec![0u8; 65535];
+            while Instant::now() < deadline {
+                let n = match self.sock.recv(&mut buf) {
+                    Ok(n) => n,
+                    Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => break,
+                    Err(e) => return Err(e.to_string()),
+                };
+                let reply = match self.decode(&buf[..n]) {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        rejected = Some(e);
+                        continue;
+                    }
+                };
+                // 丢弃迟到的旧响应
+                if reply.id == id as i64 {
+                    return Ok(reply);
+                }
+            }
+        }
+        if let Some(e) = rejected {
+            return Err(e);
+        }
+        Err(match self.creds {
+            // v2c 的 community 错误时设备不回应
+            Credentials::Community(_) => "no response (timeout; check the address and community)".to_string(),
+            Credentials::V3(_) => "no response (timeout)".to_string(),
+        })
+    }
+
+    fn encode(&mut self, msg_id: i32, pdu: &[u8]) -> Result<Vec<u8>, String> {
+        let v3 = match self.creds {
+            Credentials::Community(ref community) => {
+                let mut msg = Vec::new();
+                put_constructed(&mut msg, SEQUENCE, |m| {
+                    put_int(m, 1); // v2c
+                    put_tlv(m, OCTET_STRING, community.as_bytes());
+                    m.extend_from_slice(pdu);
+                });
+                return Ok(msg);
+            }
+            Credentials::V3(ref v3) => v3,
+        };
+
+        let mut scoped = Vec::new();
+        let engine_id = self.usm.as_ref().map(|u| u.engine_id.clone()).unwrap_or_default();
+        put_constructed(&mut scoped, SEQUENCE, |s| {
+            put_tlv(s, OCTET_STRING, &engine_id);
+            put_tlv(s, OCTET_STRING, b"");
+            s.extend_from_slice(pdu);
+        });
+
+        // 发现阶段 (还没有引擎信息) 不带用户、不认证
+        let mut flags = FLAG_REPORTABLE;
+        let (mut user, mut boots, mut time, mut mac_len) = ("", 0, 0, 0);
+        let mut priv_params = Vec::new();
+        let mut data = scoped;
+        if let Some(ref mut usm) = self.usm {
+            user = &v3.user;
+            boots = usm.boots;
+            time = usm.engine_time();
+            if let Some((proto, _)) = v3.auth {
+                flags |= FLAG_AUTH;
+                mac_len = proto.mac_len();
+            }
+            if v3.privacy.is_some() && v3.auth.is_some() {
+                flags |= FLAG_PRIV;
+                usm.salt = usm.salt.wrapping_add(1);
+                priv_params = usm.salt.to_be_bytes().to_vec();
+                aes_cfb(&usm.priv_key, boots, time, &priv_params, &mut data, true)?;
+                let mut wrapped = Vec::new();
+                put_tlv(&mut wrapped, OCTET_STRING, &data);
+                data = wrapped;
+            }
+        }
+
+        let mut security = Vec::new();
+        put_constructed(&mut security, SEQUENCE, |s| {
+            put_tlv(s, OCTET_STRING, &engine_id);
+            put_int(s, boots as i64);
+            put_int(s, time as i64);
+            put_tlv(s, OCTET_STRING, user.as_bytes());
+            put_tlv(s, OCTET_STRING, &vec![0; mac_len]);
+            put_tlv(s, OCTET_STRING, &priv_params);
+        });
+        let mut msg = Vec::new();
+        put_constructed(&mut msg, SEQUENCE, |m| {
+            put_int(m, 3);
+            put_constructed(m, SEQUENCE, |g| {
+                put_int(g, msg_id as i64);
+                put_int(g, MAX_MSG_SIZE);
+                put_tlv(g, OCTET_STRING, &[flags]);
+                put_int(g, USM_SECURITY_MODEL);
+            });
+            put_tlv(m, OCTET_STRING, &security);
+            m.extend_from_slice(&data);
+        });
+
+        // 认证码覆盖整个报文，计算时该字段为全零
+        if let (Some((proto, _)), Some(usm)) = (&v3.auth, &self.usm) {
+            let range = parse_v3(&msg)?.auth_range;
+            let mac = proto.mac(&usm.auth_key, &msg);
+            msg[range].copy_from_slice(&mac);
+        }
+        Ok(msg)
+    }
+
+    fn decode(&self, msg: &[u8]) -> Result<Reply, String> {
+        let v3 = match self.creds {
+            Credentials::Community(_) => {
+                let mut buf = msg;
+                let top = items(&read_tlv(&mut buf)?, SEQUENCE)?;
+                let [_, _, ref pdu] = top[..] else {
+                    return Err("malformed SNMP message".to_string());
+                };
+                let pdu = decode_pdu(pdu)?;
+                return Ok(Reply {
+                    id: pdu.request_id,
+                    pdu,
+                    engine: None,
+                });
+            }
+            Credentials::V3(ref v3) => v3,
+        };
+
+        let m = parse_v3(msg)?;
+        if m.flags & FLAG_AUTH != 0 {
+            let (Some((proto, _)), Some(usm)) = (&v3.auth, &self.usm) else {
+                return Err("unexpected authenticated message".to_string());
+            };
+            let mut zeroed = msg.to_vec();
+            zeroed[m.auth_range.clone()].fill(0);
+            let expected = proto.mac(&usm.auth_key, &zeroed);
+            let got = &msg[m.auth_range.clone()];
+            // 常数时间比较
+            if expected.len() != got.len() || expected.iter().zip(got).fold(0, |acc, (a, b)| acc | (a ^ b)) != 0 {
+                return Err("response failed authentication (check the authentication passphrase)".to_string());
+            }
+        }
+        let scoped = if m.flags & FLAG_PRIV != 0 {
+            let usm = self.usm.as_ref().ok_or("unexpected encrypted message")?;
+            let mut plain = as_octets(&m.data)?.to_vec();
+            aes_cfb(&usm.priv_key, m.boots, m.time, m.priv_params, &mut plain, false)?;
+            plain
+        } else {
+            let mut raw = Vec::new();
+            put_tlv(&mut raw, m.data.tag, m.data.data);
+            raw
+        };
+        let mut buf = scoped.as_slice();
+        let parts = items(&read_tlv(&mut buf)?, SEQUENCE)
+            .map_err(|_| "cannot decrypt response (check the privacy passphrase)".to_string())?;
+        let [_, _, ref pdu] = parts[..] else {
+            return Err("malformed scoped PDU".to_string());
+        };
+        let pdu = decode_pdu(pdu)?;
+        // 配置了认证时，发现阶段之后只有 Report (如时间窗口失步) 可以不带认证，
+        // 否则任何能发 UDP 包的人都能伪造计数器
+        if v3.auth.is_some() && self.usm.is_some() && m.flags & FLAG_AUTH == 0 && pdu.tag != REPORT {
+            return Err("unauthenticated response".to_string());
+        }
+        Ok(Reply {
+            id: m.msg_id,
+            pdu,
+            engine: Some((m.engine_id.to_vec(), m.boots, m.time)),
+        })
+    }
+
+    fn get(&mut self, oid: &[u32]) -> Result<Value, String> {
+        let vbs = self.request(GET_REQUEST, 0, 0, &[oid.to_vec()])?;
+        Ok(vbs.into_iter().next().map(|(_, v)| v).unwrap_or(Value::Missing))
+    }
+
+    /// 用 GetBulk 同时遍历多列，返回每列的 索引 → 值 (表以单个整数为索引，如 ifIndex)
+    fn walk(&mut self, columns: &[&[u32]]) -> Result<Vec<BTreeMap<u32, Value>>, String> {
+        let mut result = vec![BTreeMap::new(); columns.len()];
+        let mut cursor: Vec<Option<Vec<u32>>> = columns.iter().map(|c| Some(c.to_vec())).collect();
+        loop {
+            let active: Vec<usize> = (0..columns.len()).filter(|&i| cursor[i].is_some()).collect();
+            if active.is_empty() {
+                return Ok(result);
+            }
+            let oids: Vec<Vec<u32>> = active.iter().filter_map(|&i| cursor[i].clone()).collect();
+            let repetitions = (MAX_VARBINDS / active.len()).max(1);
+            let vbs = self.request(GET_BULK, 0, repetitions as i64, &oids)?;
+            if vbs.is_empty() {
+                return Ok(result);
+            }
+            // 响应按行排列: 每行依次是各活动列的下一个变量
+            for (k, (oid, value)) in vbs.into_iter().enumerate() {
+                let col = active[k % active.len()];
+                let Some(ref prev) = cursor[col] else {
+                    continue;
+                };
+                let prefix = columns[col];
+                if matches!(value, Value::EndOfMibView) || !oid.starts_with(prefix) || oid.len() != prefix.len() + 1 {
+                    cursor[col] = None;
+                    continue;
+                }
+                if oid <= *prev {
+                    return Err("agent returned OIDs out of order".to_string());
+                }
+                result[col].insert(oid[prefix.len()], value);
+                cursor[col] = Some(oid);
+            }
+        }
+    }
+}
+
+/// 一个响应: PDU 以及 v3 报文中的引擎信息 (engineID, boots, time)
+struct Reply {
+    /// 用于匹配请求: v2c 为 request-id，v3 为 msgID (Report 的 request-id 可能为 0)
+    id: i64,
+    pdu: Pdu,
+    engine: Option<(Vec<u8>, u32, u32)>,
+}
+
+// ─── 数据源 ────────────────────────────────────────────────
+
+/// 连接所有 `--snmp [NAME=]HOST[:PORT]`，等待第一次轮询完成，返回 (前缀, 来源)
+///
+/// 未指定 NAME 时前缀为设备的 sysName，见 source::resolve。
+pub fn connect_all(
+    targets: &[String],
+    creds: &Credentials,
+    interval_ms: u64,
+    start: Instant,
+    multi: bool,
+) -> Result<Vec<Named>, String> {
+    let feeds = targets
+        .iter()
+        .map(|t| {
+            let (name, host) = source::parse_target(t);
+            let (host_owned, creds) = (host.to_string(), creds.clone());
+            let feed = Feed::spawn(host, move |state| poll(&host_owned, creds.clone(), interval_ms, start, state));
+            (name, feed)
+        })
+        .collect();
+    // v3 本地化密钥需要计算约 1 MB 摘要，首次轮询多留一些时间
+    source::resolve(feeds, TIMEOUT * (RETRIES as u32 + 1) * 3, multi)
+}
+
+/// 端口: ifIndex → (设备名, ifAlias)
+type Ports = BTreeMap<u32, (String, String)>;
+
+/// 读取端口列表；ifDescr 重复时在名称后加上 ifIndex
+fn read_ports(session: &mut Session) -> Result<Ports, String> {
+    let mut cols = session.walk(&[IF_DESCR, IF_ALIAS])?.into_iter();
+    let (descr, alias) = (cols.next().unwrap_or_default(), cols.next().unwrap_or_default());
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let names: Vec<(u32, String)> = descr
+        .iter()
+        .map(|(&idx, v)| (idx, v.as_text().filter(|s| !s.is_empty()).unwrap_or_else(|| format!("if{idx}"))))
+        .collect();
+    for (_, name) in &names {
+        *counts.entry(name.clone()).or_default() += 1;
+    }
+    Ok(names
+        .into_iter()
+        .map(|(idx, name)| {
+            let name = if counts[&name] > 1 { format!("{name}#{idx}") } else { name };
+            let alias = alias.get(&idx).and_then(Value::as_text).unwrap_or_default();
+            (idx, (name, alias))
+        })
+        .collect())
+}
+
+/// ifOperStatus → 与 Linux operstate 相同的名称
+fn oper_status(v: u64) -> &'static str {
+    match v {
+        1 => "up",
+        2 => "down",
+        3 => "testing",
+        5 => "dormant",
+        6 => "notpresent",
+        7 => "lowerlayerdown",
+        _ => "unknown",
+    }
+}
+
+/// 建立会话并按间隔轮询，直到出错
+fn poll(target: &str, creds: Credentials, interval_ms: u64, start: Instant, state: &Mutex<FeedState>) -> Result<(), String> {
+    let mut session = Session::open(target, creds)?;
+    let sys_name = session.get(SYS_NAME)?.as_text().filter(|s| !s.is_empty());
+    let mut ports = Ports::new();
+    let mut last_list: Option<Instant> = None;
+    let interval = Duration::from_millis(interval_ms);
+
+    loop {
+        let began = Instant::now();
+        if last_list.is_none_or(|t| t.elapsed() >= LIST_REFRESH_INTERVAL) {
+            ports = read_ports(&mut session)?;
+            last_list = Some(Instant::now());
+        }
+        let cols = session.walk(&COUNTER_COLUMNS)?;
+        let elapsed_secs = start.elapsed().as_secs_f64();
+        let counter = |col: usize, idx: &u32| cols[col].get(idx).and_then(Value::as_u64);
+
+        let mut snapshots = HashMap::new();
+        let mut links = HashMap::new();
+        let mut devices = Vec::new();
+        for (idx, (name, alias)) in &ports {
+            // 没有 64 位计数器的端口 (部分虚拟接口) 跳过
+            let (Some(rx), Some(tx)) = (counter(0, idx), counter(1, idx)) else {
+                continue;
+            };
+            snapshots.insert(
+                name.clone(),
+                Snapshot {
+                    elapsed_secs,
+                    bytes_recv: rx,
+                    bytes_sent: tx,
+                    packets_recv: counter(2, idx).unwrap_or(0),
+                    packets_sent: counter(3, idx).unwrap_or(0),
+                    errors_recv: counter(4, idx).unwrap_or(0),
+                    errors_sent: counter(5, idx).unwrap_or(0),
+                },
+            );
+            if let Some(oper) = counter(6, idx) {
+                let speed = counter(7, idx).filter(|&s| s > 0);
+                links.insert(name.clone(), LinkState { oper: oper_status(oper).to_string(), speed_mbps: speed });
+            }
+            devices.push(DeviceInfo {
+                name: name.clone(),
+                addrs: if alias.is_empty() { Vec::new() } else { vec![alias.clone()] },
+            });
+        }
+        if devices.is_empty() {
+            return Err("no interfaces with 64-bit counters (ifXTable) found".to_string());
+        }
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+        {
+            let Ok(mut s) = state.lock() else {
+                return Ok(());
+            };
+            s.name = sys_name.clone();
+            s.mark_connected();
+            s.devices = devices;
+            s.snapshots = snapshots;
+            s.links = links;
+        }
+        thread::sleep(interval.saturating_sub(began.elapsed()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn localize_matches_rfc3414_vectors() {
+        // RFC 3414 A.3.1 / A.3.2
+        let engine_id = hex("000000000000000000000002");
+        assert_eq!(AuthProto::Md5.localized_key("maplesyrup", &engine_id), hex("526f5eed9fcce26f8964c2930787d82b"));
+        assert_eq!(
+            AuthProto::Sha.localized_key("maplesyrup", &engine_id),
+            hex("6695febc9288e36282235fc7151f128497b38f3f")
+        );
+    }
+
+    #[test]
+    fn int_round_trip() {
+        for v in [0, 1, -1, 127, 128, -128, -129, 255, 256, i32::MAX as i64, i64::MIN, i64::MAX] {
+            let mut buf = Vec::new();
+            put_int(&mut buf, v);
+            let mut data = buf.as_slice();
+            assert_eq!(as_int(&read_tlv(&mut data).unwrap()), Ok(v), "{v}");
+            assert!(data.is_empty());
+        }
+        // 最短编码: 128 需要前导 0，-128 不需要
+        let encoded = |v| {
+            let mut buf = Vec::new();
+            put_int(&mut buf, v);
+            buf
+        };
+        assert_eq!(encoded(128), [INTEGER, 2, 0x00, 0x80]);
+        assert_eq!(encoded(-128), [INTEGER, 1, 0x80]);
+    }
+
+    #[test]
+    fn oid_round_trip() {
+        for oid in [IF_HC_IN_OCTETS.to_vec(), vec![1, 3, 6, 1, 4, 1, 2636, 3, 1, 127, 128, 16384, u32::MAX]] {
+            let mut buf = Vec::new();
+            put_oid(&mut buf, &oid);
+            assert_eq!(as_oid(&read_tlv(&mut buf.as_slice()).unwrap()), Ok(oid));
+        }
+    }
+
+    #[test]
+    fn pdu_round_trip() {
+        let oids = [SYS_NAME.to_vec(), IF_DESCR.to_vec()];
+        let buf = encode_pdu(RESPONSE, 0x1234_5678, 2, 1, &oids);
+        let pdu = decode_pdu(&read_tlv(&mut buf.as_slice()).unwrap()).unwrap();
+        assert_eq!((pdu.tag, pdu.request_id, pdu.error_status), (RESPONSE, 0x1234_5678, 2));
+        assert_eq!(pdu.varbinds.iter().map(|(oid, _)| oid.clone()).collect::<Vec<_>>(), oids);
+        assert!(pdu.varbinds.iter().all(|(_, v)| matches!(v, Value::Missing)));
+        // 请求类 PDU 不是响应
+        let request = encode_pdu(GET_BULK, 1, 0, 10, &oids);
+        assert!(decode_pdu(&read_tlv(&mut request.as_slice()).unwrap()).is_err());
+    }
+
+    /// 不经过网络的 v3 会话，引擎信息已同步
+    fn v3_session(auth: Option<AuthProto>, privacy: bool) -> Session {
+        let v3 = V3 {
+            user: "winload".to_string(),
+            auth: auth.map(|p| (p, "authpass123".to_string())),
+            privacy: privacy.then(|| (PrivProto::Aes, "privpass123".to_string())),
+        };
+        let engine_id = hex("80001f8880aabbccddeeff0011");
+        let key = |pass: &str| auth.map(|p| p.localized_key(pass, &engine_id)).unwrap_or_default();
+        Session {
+            sock: UdpSocket::bind("127.0.0.1:0").unwrap(),
+            usm: Some(Usm {
+                auth_key: key("authpass123"),
+                priv_key: key("privpass123"),
+                engine_id,
+                boots: 3,
+                time: 1000,
+                synced: Instant::now(),
+                salt: 0,
+            }),
+            creds: Credentials::V3(v3),
+            next_id: 1,
+        }
+    }
+
+    #[test]
+    fn v3_auth_priv_round_trip() {
+        let mut session = v3_session(Some(AuthProto::Sha256), true);
+        let pdu = encode_pdu(RESPONSE, 7, 0, 0, &[SYS_NAME.to_vec()]);
+        let msg = session.encode(7, &pdu).unwrap();
+        // 加密后报文中不应出现明文 PDU
+        assert!(!msg.windows(pdu.len()).any(|w| w == pdu));
+
+        let reply = session.decode(&msg).unwrap();
+        assert_eq!((reply.id, reply.pdu.request_id), (7, 7));
+        assert_eq!(reply.pdu.varbinds[0].0, SYS_NAME);
+        let (engine_id, boots, _) = reply.engine.unwrap();
+        assert_eq!((engine_id, boots), (hex("80001f8880aabbccddeeff0011"), 3));
+
+        // 任何一个字节被改动都通不过认证
+        let mut tampered = msg.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(session.decode(&tampered).err().is_some_and(|e| e.contains("authentication")));
+    }
+
+    #[test]
+    fn v3_rejects_unauthenticated_response() {
+        let session = v3_session(Some(AuthProto::Sha), false);
+        let mut forger = v3_session(None, false);
+        let response = forger.encode(9, &encode_pdu(RESPONSE, 9, 0, 0, &[SYS_NAME.to_vec()])).unwrap();
+        assert_eq!(session.decode(&response).err().as_deref(), Some("unauthenticated response"));
+        // Report (如时间窗口失步) 允许不带认证
+        let report = forger.encode(9, &encode_pdu(REPORT, 9, 0, 0, &[USM_STATS.to_vec()])).unwrap();
+        assert_eq!(session.decode(&report).unwrap().pdu.tag, REPORT);
+    }
+
+    type Canned = Vec<(Vec<u32>, u8, Vec<u8>)>;
+
+    /// 按顺序用预设的变量回应 GetBulk，返回地址和每个请求中的 OID
+    fn fake_agent(replies: Vec<Canned>) -> (String, thread::JoinHandle<Vec<Vec<Vec<u32>>>>) {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let addr = sock.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let mut requested = Vec::new();
+            let mut buf = [0u8; 65535];
+            for canned in replies {
+                let (n, peer) = sock.recv_from(&mut buf).unwrap();
+                let top = items(&read_tlv(&mut &buf[..n]).unwrap(), SEQUENCE).unwrap();
+                assert_eq!(top[2].tag, GET_BULK);
+                let mut pdu = top[2].data;
+                let id = as_int(&read_tlv(&mut pdu).unwrap()).unwrap();
+                read_tlv(&mut pdu).unwrap();
+                read_tlv(&mut pdu).unwrap();
+                let vbs = items(&read_tlv(&mut pdu).unwrap(), SEQUENCE).unwrap();
+                requested.push(vbs.iter().map(|vb| as_oid(&items(vb, SEQUENCE).unwrap()[0]).unwrap()).collect());
+
+                let mut msg = Vec::new();
+                put_constructed(&mut msg, SEQUENCE, |m| {
+                    put_int(m, 1);
+                    put_tlv(m, OCTET_STRING, b"public");
+                    put_constructed(m, RESPONSE, |p| {
+                        put_int(p, id);
+                        put_int(p, 0);
+                        put_int(p, 0);
+                        put_constructed(p, SEQUENCE, |list| {
+                            for (oid, tag, value) in &canned {
+                                put_constructed(list, SEQUENCE, |vb| {
+                                    put_oid(vb, oid);
+                                    put_tlv(vb, *tag, value);
+                                });
+                            }
+                        });
+                    });
+                });
+                sock.send_to(&msg, peer).unwrap();
+            }
+            requested
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn walk_follows_getbulk_rows_until_columns_end() {
+        let oid = |col: &[u32], idx: u32| [col, &[idx]].concat();
+        let (addr, agent) = fake_agent(vec![
+            vec![
+                (oid(IF_DESCR, 1), OCTET_STRING, b"eth0".to_vec()),
+                (oid(IF_HC_IN_OCTETS, 1), COUNTER64, vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                (oid(IF_DESCR, 2), OCTET_STRING, b"eth1".to_vec()),
+                (oid(IF_HC_IN_OCTETS, 2), COUNTER64, vec![0x05]),
+            ],
+            // 两列都走出了各自的前缀 (下一列 / 下一个对象)
+            vec![
+                (oid(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 3], 1), INTEGER, vec![6]),
+                (oid(IF_HC_IN_UCAST, 1), COUNTER64, vec![0]),
+            ],
+        ]);
+        let mut session = Session::open(&addr, Credentials::Community("public".to_string())).unwrap();
+        let cols = session.walk(&[IF_DESCR, IF_HC_IN_OCTETS]).unwrap();
+
+        let descr: Vec<_> = cols[0].iter().map(|(&i, v)| (i, v.as_text().unwrap())).collect();
+        assert_eq!(descr, [(1, "eth0".to_string()), (2, "eth1".to_string())]);
+        let octets: Vec<_> = cols[1].iter().map(|(&i, v)| (i, v.as_u64().unwrap())).collect();
+        assert_eq!(octets, [(1, 1 << 40), (2, 5)]);
+        // 第二次请求从每列最后一个变量继续
+        assert_eq!(
+            agent.join().unwrap(),
+            [vec![IF_DESCR.to_vec(), IF_HC_IN_OCTETS.to_vec()], vec![oid(IF_DESCR, 2), oid(IF_HC_IN_OCTETS, 2)]]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! 计数器来源
+//!
+//! App 通过 Source 取得设备列表、累计计数器和链路状态，不关心数据来自哪里:
+//! 本机 (Collector，基于 sysinfo)，或由后台线程填充的 Feed (远程 agent、SNMP 等)。
+//! 多个 Feed 由 MultiSource 合并，设备名带主机前缀。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::collector::{self, Collector, DeviceInfo, LinkState, Snapshot};
+use crate::flows::FlowDeltas;
+use crate::i18n::t;
+use crate::protocols::Breakdown;
+
+pub trait Source {
+    /// 当前设备列表 (按名称排序)
+    fn devices(&self) -> Vec<DeviceInfo>;
+
+    /// 重新扫描设备列表 (周期调用)
+    fn refresh_list(&mut self);
+
+    /// 采集一次所有设备的当前累计数据
+    ///
+    /// 快照的 elapsed_secs 需要换算到本进程的时间轴 (自 `start` 以来的秒数)。
+    fn collect(&mut self) -> HashMap<String, Snapshot>;
+
+    /// 设备的链路状态；不支持时返回 None
+    fn link_state(&self, name: &str) -> Option<LinkState>;
+
+    /// 设备的属主标签 (如 veth 对端所在的容器)，只列出有属主的设备
+    fn owners(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// 取出上次调用以来抓包得到的各连接字节数；不抓包时为空
+    fn take_flows(&mut self) -> FlowDeltas {
+        FlowDeltas::new()
+    }
+
+    /// 取出上次调用以来抓包得到的各协议字节数；不抓包时全为 0
+    fn take_protocols(&mut self) -> Breakdown {
+        Breakdown::default()
+    }
+
+    /// 是否在抓包 (连接表和协议分布只在抓包时有数据)
+    fn capturing(&self) -> bool {
+        false
+    }
+
+    /// 取出来源报告的错误 (连接断开等)，每条为 (出错的来源, 消息)，由 App 写入事件日志
+    fn take_errors(&mut self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// 来源当前是否可用 (远程来源断线时为 false)
+    fn connected(&self) -> bool {
+        true
+    }
+
+    /// 显示在标题栏的来源说明，本机为 None
+    fn label(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Source for Collector {
+    fn devices(&self) -> Vec<DeviceInfo> {
+        Collector::devices(self)
+    }
+
+    fn refresh_list(&mut self) {
+        Collector::refresh_list(self)
+    }
+
+    fn collect(&mut self) -> HashMap<String, Snapshot> {
+        Collector::collect(self)
+    }
+
+    fn link_state(&self, name: &str) -> Option<LinkState> {
+        collector::link_state(name)
+    }
+
+    fn owners(&self) -> HashMap<String, String> {
+        Collector::owners(self)
+    }
+}
+
+// ─── 多主机 ────────────────────────────────────────────────
+
+/// 带前缀 (主机名) 的来源
+pub type Named = (String, Box<dyn Source>);
+
+/// 拆分 `[NAME=]TARGET`
+///
+/// NAME 只能含字母、数字和 `-_.`，这样 `ssh -o Opt=val host ...` 这类目标中的 `=`
+/// 不会被误认为名称分隔符。
+pub fn parse_target(s: &str) -> (Option<&str>, &str) {
+    match s.split_once('=') {
+        Some((name, target)) if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c)) => {
+            (Some(name), target.trim())
+        }
+        _ => (None, s.trim()),
+    }
+}
+
+/// 合并 (前缀, 来源)；只有一个来源时直接返回，设备名不加前缀
+pub fn combine(mut sources: Vec<Named>) -> Result<Box<dyn Source>, String> {
+    if sources.len() == 1 {
+        return Ok(sources.remove(0).1);
+    }
+    for (i, (prefix, _)) in sources.iter().enumerate() {
+        if prefix.is_empty() || prefix.contains('/') {
+            return Err(format!("Invalid host name '{prefix}': use NAME=TARGET"));
+        }
+        if sources[..i].iter().any(|(p, _)| p == prefix) {
+            return Err(format!("Two hosts are named '{prefix}': use NAME=TARGET to tell them apart"));
+        }
+    }
+    Ok(Box::new(MultiSource::new(sources)))
+}
+
+/// 把多个来源合并为一个，设备名加上 `前缀/` (如 `web1/eth0`)
+pub struct MultiSource {
+    sources: Vec<Named>,
+}
+
+impl MultiSource {
+    /// `sources` 为 (前缀, 来源)，前缀不能重复
+    pub fn new(sources: Vec<Named>) -> Self {
+        Self { sources }
+    }
+
+    /// 把 `前缀/设备` 拆成对应的来源和原始设备名
+    fn lookup<'a>(&self, name: &'a str) -> Option<(&dyn Source, &'a str)> {
+        let (prefix, dev) = name.split_once('/')?;
+        self.sources
+            .iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, s)| (s.as_ref(), dev))
+    }
+}
+
+impl Source for MultiSource {
+    fn devices(&self) -> Vec<DeviceInfo> {
+        let mut devices: Vec<DeviceInfo> = self
+            .sources
+            .iter()
+            .flat_map(|(prefix, s)| {
+                s.devices().into_iter().map(move |d| DeviceInfo {
+                    name: format!("{prefix}/{}", d.name),
+                    addrs: d.addrs,
+                })
+            })
+            .collect();
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+        devices
+    }
+
+    fn refresh_list(&mut self) {
+        for (_, s) in &mut self.sources {
+            s.refresh_list();
+        }
+    }
+
+    fn collect(&mut self) -> HashMap<String, Snapshot> {
+        let mut all = HashMap::new();
+        for (prefix, s) in &mut self.sources {
+            all.extend(s.collect().into_iter().map(|(name, snap)| (format!("{prefix}/{name}"), snap)));
+        }
+        all
+    }
+
+    fn link_state(&self, name: &str) -> Option<LinkState> {
+        let (source, dev) = self.lookup(name)?;
+        source.link_state(dev)
+    }
+
+    fn owners(&self) -> HashMap<String, String> {
+        self.sources
+            .iter()
+            .flat_map(|(prefix, s)| s.owners().into_iter().map(move |(dev, owner)| (format!("{prefix}/{dev}"), owner)))
+            .collect()
+    }
+
+    /// 来源以前缀 (主机名) 标识，和设备名一致
+    fn take_errors(&mut self) -> Vec<(String, String)> {
+        self.sources
+            .iter_mut()
+            .flat_map(|(prefix, s)| s.take_errors().into_iter().map(move |(_, e)| (prefix.clone(), e)))
+            .collect()
+    }
+
+    fn connected(&self) -> bool {
+        self.sources.iter().all(|(_, s)| s.connected())
+    }
+
+    /// 列出所有主机，断线的主机加上标记
+    fn label(&self) -> Option<String> {
+        let labels: Vec<String> = self
+            .sources
+            .iter()
+            .map(|(prefix, s)| {
+                if s.connected() {
+                    prefix.clone()
+                } else {
+                    format!("{prefix} ({})", t("remote_disconnected"))
+                }
+            })
+            .collect();
+        Some(labels.join(", "))
+    }
+}
+
+// ─── 后台来源 ──────────────────────────────────────────────
+
+/// 重连退避的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 后台线程和 App 共享的状态
+#[derive(Default)]
+pub struct FeedState {
+    /// 对端报告的名称 (主机名、sysName 等)，用作多主机前缀
+    pub name: Option<String>,
+    pub connected: bool,
+    pub devices: Vec<DeviceInfo>,
+    /// 上次 collect 以来新到的快照
+    pub snapshots: HashMap<String, Snapshot>,
+    pub links: HashMap<String, LinkState>,
+    /// 后台线程报告的错误，由 take_errors 标上目标
+    errors: Vec<String>,
+    /// 第一次连接的结果，供启动时等待
+    first_result: Option<Result<(), String>>,
+}
+
+impl FeedState {
+    /// 连接成功并收到数据后调用
+    pub fn mark_connected(&mut self) {
+        self.connected = true;
+        self.first_result.get_or_insert(Ok(()));
+    }
+}
+
+/// 由后台线程填充数据的来源 (远程 agent、SNMP 设备、外部命令、node_exporter、其他网络命名空间)
+///
+/// 后台线程反复调用 `session`: 每次调用建立一次连接并持续更新状态，直到出错返回；
+/// 出错后按指数退避重试，错误进入事件日志。
+pub struct Feed {
+    target: String,
+    state: Arc<Mutex<FeedState>>,
+}
+
+impl Feed {
+    pub fn spawn<F>(target: &str, mut session: F) -> Self
+    where
+        F: FnMut(&Mutex<FeedState>) -> Result<(), String> + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(FeedState::default()));
+        let thread_state = state.clone();
+        thread::spawn(move || {
+            let mut backoff = Duration::from_secs(1);
+            let mut reported = false;
+            loop {
+                let result = session(&thread_state);
+                if let Ok(mut s) = thread_state.lock() {
+                    if s.first_result.is_none() {
+                        s.first_result = Some(result.clone());
+                    }
+                    let was_connected = s.connected;
+                    s.connected = false;
+                    s.snapshots.clear();
+                    if let Err(e) = result {
+                        // 连上后断开，或第一次连接失败时报告；重试失败不重复报告
+                        if was_connected || !reported {
+                            s.errors.push(format!("{e}, retrying"));
+                        }
+                    }
+                    if was_connected {
+                        backoff = Duration::from_secs(1);
+                    }
+                }
+                reported = true;
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+        Self {
+            target: target.to_string(),
+            state,
+        }
+    }
+
+    /// 等待第一次连接成功；失败或超时时返回错误
+    pub fn wait_ready(&self, timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Ok(s) = self.state.lock() {
+                if s.connected {
+                    return Ok(());
+                }
+                if let Some(Err(ref e)) = s.first_result {
+                    return Err(format!("{}: {e}", self.target));
+                }
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        Err(format!("{}: timed out", self.target))
+    }
+
+    /// 对端报告的名称 (第一次连接成功前为 None)
+    pub fn name(&self) -> Option<String> {
+        self.state.lock().ok()?.name.clone()
+    }
+}
+
+/// 等待各来源第一次连接完成并确定前缀
+///
+/// 前缀为 NAME，未指定时用对端报告的名称，再没有时用目标本身。`multi` 为 true
+/// (不止一个来源) 时，指定了 NAME 的来源启动时连不上也继续运行 (错误进入事件日志，
+/// 后台重试)；未指定 NAME 时需要先连上才能知道名称。
+pub fn resolve(feeds: Vec<(Option<&str>, Feed)>, timeout: Duration, multi: bool) -> Result<Vec<Named>, String> {
+    let mut sources: Vec<Named> = Vec::new();
+    for (name, feed) in feeds {
+        let ready = feed.wait_ready(timeout);
+        let prefix = match name {
+            Some(name) if multi => name.to_string(),
+            _ => {
+                ready?;
+                name.map(String::from).or_else(|| feed.name()).unwrap_or_else(|| feed.target.clone())
+            }
+        };
+        sources.push((prefix, Box::new(feed)));
+    }
+    Ok(sources)
+}
+
+impl Source for Feed {
+    fn devices(&self) -> Vec<DeviceInfo> {
+        self.state.lock().map(|s| s.devices.clone()).unwrap_or_default()
+    }
+
+    fn refresh_list(&mut self) {}
+
+    /// 只返回上次调用以来新到的快照，避免同一份数据被重复计入
+    fn collect(&mut self) -> HashMap<String, Snapshot> {
+        self.state.lock().map(|mut s| std::mem::take(&mut s.snapshots)).unwrap_or_default()
+    }
+
+    fn link_state(&self, name: &str) -> Option<LinkState> {
+        self.state.lock().ok()?.links.get(name).cloned()
+    }
+
+    fn take_errors(&mut self) -> Vec<(String, String)> {
+        let errors = self.state.lock().map(|mut s| std::mem::take(&mut s.errors)).unwrap_or_default();
+        errors.into_iter().map(|e| (self.target.clone(), e)).collect()
+    }
+
+    fn connected(&self) -> bool {
+        self.state.lock().map(|s| s.connected).unwrap_or(false)
+    }
+
+    fn label(&self) -> Option<String> {
+        let s = self.state.lock().ok()?;
+        let name = s.name.clone().unwrap_or_else(|| self.target.clone());
+        Some(if s.connected { name } else { format!("{name} ({})", t("remote_disconnected")) })
+    }
+}
@@ -18,6 +18,10 @@ pub struct TrafficStats {
     pub maximum: f64,
     /// 累计字节数
     pub total: u64,
+    /// 累计包数
+    pub packets: u64,
+    /// 累计错误数
+    pub errors: u64,
 }
 
 impl Default for TrafficStats {
@@ -28,6 +32,8 @@ impl Default for TrafficStats {
             minimum: f64::INFINITY,
             maximum: 0.0,
             total: 0,
+            packets: 0,
+            errors: 0,
         }
     }
 }
@@ -69,7 +75,17 @@ impl StatisticsEngine {
     }
 
     /// 喂入新的采样快照，重新计算统计
-    pub fn update(&mut self, snapshot: Snapshot) {
+    ///
+    /// 返回 true 表示检测到计数器回退 (网卡重建、驱动重载、32 位计数器溢出等)。
+    /// 此时丢弃旧样本，从新计数器重新开始计算速率，避免出现一个负的尖峰。
+    pub fn update(&mut self, snapshot: Snapshot) -> bool {
+        let reset = self.samples.back().is_some_and(|prev| {
+            snapshot.bytes_recv < prev.bytes_recv || snapshot.bytes_sent < prev.bytes_sent
+        });
+        if reset {
+            self.samples.clear();
+        }
+
         self.samples.push_back(snapshot);
         if self.samples.len() > self.max_samples {
             self.samples.pop_front();
@@ -77,12 +93,20 @@ impl StatisticsEngine {
         self.sample_count += 1;
 
         let n = self.samples.len();
+        let latest = &self.samples[n - 1];
+
+        // ── Total ── (第一个样本就更新，供配额等按累计值计算的功能使用)
+        self.incoming.total = latest.bytes_recv;
+        self.outgoing.total = latest.bytes_sent;
+        self.incoming.packets = latest.packets_recv;
+        self.outgoing.packets = latest.packets_sent;
+        self.incoming.errors = latest.errors_recv;
+        self.outgoing.errors = latest.errors_sent;
+
         if n < 2 {
-            return;
+            return reset;
         }
 
-        let latest = &self.samples[n - 1];
-
         // ── 当前速率 (最近 ~1s 的窗口) ──
         let sec_idx = if n > self.second_window + 1 {
             n - 1 - self.second_window
@@ -135,9 +159,7 @@ impl StatisticsEngine {
             self.outgoing.minimum = 0.0;
         }
 
-        // ── Total ──
-        self.incoming.total = latest.bytes_recv;
-        self.outgoing.total = latest.bytes_sent;
+        reset
     }
 }
 
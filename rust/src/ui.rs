@@ -11,9 +11,16 @@ use ratatui::{
     Frame,
 };
 
+use crate::anomaly::Anomaly;
 use crate::graph;
+use crate::quota::Quota;
+use crate::recording::Player;
 use crate::stats::{self, TrafficStats};
-use crate::{App, BarStyle, Unit};
+use crate::alert::{Alert, Metric, State};
+use crate::events::EventKind;
+use crate::flows::{SortBy, WINDOWS};
+use crate::protocols::Class;
+use crate::{App, BarStyle, Unit, ViewMode};
 use crate::i18n::t;
 #[cfg(target_os = "windows")]
 use crate::loopback::LoopbackMode;
@@ -60,6 +67,18 @@ pub fn draw(frame: &mut Frame, app: &App) {
     if show_loopback_warning || show_loopback_info {
         header_height += 1; // warning/info line
     }
+    if app.current_quota().is_some() {
+        header_height += 1; // quota line
+    }
+    if app.alerts.firing().next().is_some() {
+        header_height += 1; // alert banner
+    }
+    if current_anomaly(app).is_some() {
+        header_height += 1; // anomaly line
+    }
+    if app.player().is_some() {
+        header_height += 1; // replay status
+    }
     if !app.hide_separator {
         header_height += 1; // separator line
     }
@@ -75,8 +94,14 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .split(area);
 
     draw_header(frame, chunks[0], app, show_loopback_warning, show_loopback_info);
-    draw_panels(frame, chunks[1], app);
-    draw_help(frame, chunks[2], app.emoji, app.bar_style, app.no_color);
+    match app.view_mode {
+        ViewMode::Traffic => draw_panels(frame, chunks[1], app),
+        ViewMode::Alerts => draw_alerts(frame, chunks[1], app),
+        ViewMode::Events => draw_events(frame, chunks[1], app),
+        ViewMode::Flows => draw_flows(frame, chunks[1], app),
+        ViewMode::Protocols => draw_protocols(frame, chunks[1], app),
+    }
+    draw_help(frame, chunks[2], app.emoji, app.bar_style, app.no_color, app.prompt.as_deref());
 }
 
 // ─── Header ────────────────────────────────────────────────
@@ -98,6 +123,8 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App, show_loopback_warning:
         } else {
             String::new()
         };
+        // veth 显示对端所在的容器或进程
+        let owner_str = app.owner(&view.info.name).map(|o| format!(" \u{2192} {o}")).unwrap_or_default();
 
         let is_loopback = view.info.name.to_lowercase().contains("loopback");
 
@@ -116,25 +143,32 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App, show_loopback_warning:
             ""
         };
 
+        // 远程模式下标明数据来自哪台主机
+        let source_tag = app.source_label().map(|l| format!(" @{l}")).unwrap_or_default();
+
         let header_text = if app.emoji {
             format!(
-                "{} {}{} ({}/{}){} \u{1f4e1}:",
+                "{} {}{}{} ({}/{}){}{} \u{1f4e1}:",
                 t("device_emoji"),
                 view.info.name,
+                owner_str,
                 addr_str,
                 app.current_idx + 1,
                 app.views.len(),
                 mode_tag,
+                source_tag,
             )
         } else {
             format!(
-                "{} {}{} ({}/{}){}:",
+                "{} {}{}{} ({}/{}){}{}:",
                 t("device"),
                 view.info.name,
+                owner_str,
                 addr_str,
                 app.current_idx + 1,
                 app.views.len(),
                 mode_tag,
+                source_tag,
             )
         };
 
@@ -163,7 +197,19 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App, show_loopback_warning:
         let header = Line::from(Span::styled(header_display, header_style));
 
         let mut lines = vec![header];
-        
+
+        if let Some(player) = app.player() {
+            lines.push(replay_line(player, app, width));
+        }
+
+        if let Some(banner) = alert_banner(app, width) {
+            lines.push(banner);
+        }
+
+        if let Some(anomaly) = current_anomaly(app) {
+            lines.push(anomaly_line(anomaly, app, width));
+        }
+
         if show_loopback_warning {
             let warn_text = t("loopback_warning");
             let warn_style = maybe_strip(match app.bar_style {
@@ -196,6 +242,10 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App, show_loopback_warning:
             }
         }
 
+        if let Some(quota) = app.current_quota() {
+            lines.push(quota_line(quota, app, width));
+        }
+
         // Add separator line as part of lines if not hidden
         if !app.hide_separator {
             let sep_width = area.width as usize;
@@ -217,6 +267,150 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App, show_loopback_warning:
     }
 }
 
+/// 告警指标值的显示文本: 速率按当前单位，配额为百分比
+fn format_alert_value(alert: &Alert, app: &App) -> String {
+    match app.alerts.rules[alert.rule].metric {
+        Metric::Quota => format!("{:.1}%", alert.value),
+        _ => stats::format_speed_unit(alert.value, app.unit),
+    }
+}
+
+/// 告警横幅: 显示第一条正在触发的告警，其余以 "+N" 表示
+fn alert_banner(app: &App, width: usize) -> Option<Line<'static>> {
+    let mut firing = app.alerts.firing();
+    let first = firing.next()?;
+    let rest = firing.count();
+
+    let mut text = format!(
+        "{}: [{}] {} ({})",
+        t("alert_banner"),
+        first.device,
+        app.alerts.rules[first.rule].text,
+        format_alert_value(first, app),
+    );
+    if rest > 0 {
+        text.push_str(&format!(" +{rest} {}", t("alert_more")));
+    }
+
+    let style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&text, width)
+    } else {
+        text
+    };
+    Some(Line::from(Span::styled(display, style)))
+}
+
+/// 把秒数格式化为 HH:MM:SS
+fn format_hms(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// 回放状态行: 播放位置对应的时间、进度、速度和按键提示
+fn replay_line(player: &Player, app: &App, width: usize) -> Line<'static> {
+    let state = if player.finished() && player.clock >= player.end() {
+        t("replay_end")
+    } else if player.paused {
+        t("replay_paused")
+    } else {
+        t("replay_playing")
+    };
+    let text = format!(
+        " {state} {} ({} / {}) x{} | {}",
+        player.wall_time(player.clock).format("%Y-%m-%d %H:%M:%S"),
+        format_hms(player.clock - player.start()),
+        format_hms(player.end() - player.start()),
+        player.speed,
+        t("replay_keys"),
+    );
+    let style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&text, width)
+    } else {
+        text
+    };
+    Line::from(Span::styled(display, style))
+}
+
+/// 当前设备正在进行的流量异常 (收方向优先)
+fn current_anomaly(app: &App) -> Option<&Anomaly> {
+    let detector = app.anomalies.as_ref()?;
+    let view = app.current_view()?;
+    detector
+        .active(&view.info.name, true)
+        .or_else(|| detector.active(&view.info.name, false))
+}
+
+/// 异常行: 方向、偏离最大时的速率、基线均值和 z 值
+fn anomaly_line(anomaly: &Anomaly, app: &App, width: usize) -> Line<'static> {
+    let dir = if anomaly.incoming { t("incoming") } else { t("outgoing") };
+    let text = format!(
+        "{}: {dir} {} ({} ~{}, z = {:.1}) @ {}",
+        t("anomaly_banner"),
+        stats::format_speed_unit(anomaly.value, app.unit),
+        t("anomaly_expected"),
+        stats::format_speed_unit(anomaly.expected, app.unit),
+        anomaly.z,
+        anomaly.started_at.format("%H:%M:%S"),
+    );
+    let style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Magenta).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Magenta).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&text, width)
+    } else {
+        text
+    };
+    Line::from(Span::styled(display, style))
+}
+
+/// 配额行: 已用 / 上限 (百分比) | 剩余 | 周期末预计用量
+fn quota_line(quota: &Quota, app: &App, width: usize) -> Line<'static> {
+    let label = if app.emoji { t("quota_emoji") } else { t("quota") };
+    let text = format!(
+        " {label}: {} / {} ({:.1}%) | {} {} | {} {} @ {}",
+        stats::format_bytes(quota.used),
+        stats::format_bytes(quota.limit),
+        quota.percent(),
+        t("quota_left"),
+        stats::format_bytes(quota.remaining()),
+        t("quota_projected"),
+        stats::format_bytes(quota.projected),
+        quota.period_end.format("%Y-%m-%d"),
+    );
+
+    // 已超额: 红; 已用 >= 80% 或预计超额: 黄; 其余: 绿
+    let color = if quota.used >= quota.limit {
+        Color::Red
+    } else if quota.percent() >= 80.0 || quota.projected > quota.limit {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    let style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(color).fg(Color::Black),
+        BarStyle::Color => Style::default().bg(color).fg(Color::Black),
+        BarStyle::Plain => Style::default().fg(color),
+    }, app.no_color);
+    let display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&text, width)
+    } else {
+        text
+    };
+    Line::from(Span::styled(display, style))
+}
+
 // ─── Panels ────────────────────────────────────────────────
 
 fn draw_panels(frame: &mut Frame, area: Rect, app: &App) {
@@ -226,6 +420,20 @@ fn draw_panels(frame: &mut Frame, area: Rect, app: &App) {
         .split(area);
 
     if let Some(view) = app.current_view() {
+        // 异常列和可见的标记按图形区域的实际宽度 (面板宽度减去统计列) 计算
+        let overlay = |incoming: bool, graph_width: usize| Overlay {
+            anomaly: app
+                .anomalies
+                .as_ref()
+                .map(|d| d.marks(&view.info.name, incoming, app.tick, graph_width))
+                .unwrap_or_default(),
+            markers: app
+                .markers
+                .iter()
+                .map(|m| ((app.tick - m.tick) as usize, m.label.as_str()))
+                .filter(|(age, _)| *age < graph_width)
+                .collect(),
+        };
         let (in_label, out_label) = if app.emoji {
             (t("incoming_emoji"), t("outgoing_emoji"))
         } else {
@@ -245,6 +453,8 @@ fn draw_panels(frame: &mut Frame, area: Rect, app: &App) {
             app.fixed_max,
             app.no_graph,
             app.no_color,
+            app.alerts.panel_firing(&view.info.name, true),
+            &|width| overlay(true, width),
         );
         draw_traffic_panel(
             frame,
@@ -260,11 +470,14 @@ fn draw_panels(frame: &mut Frame, area: Rect, app: &App) {
             app.fixed_max,
             app.no_graph,
             app.no_color,
+            app.alerts.panel_firing(&view.info.name, false),
+            &|width| overlay(false, width),
         );
     }
 }
 
-fn draw_traffic_panel(
+#[allow(clippy::too_many_arguments)]
+fn draw_traffic_panel<'a>(
     frame: &mut Frame,
     area: Rect,
     label: &str,
@@ -278,6 +491,8 @@ fn draw_traffic_panel(
     fixed_max: Option<f64>,
     no_graph: bool,
     no_color: bool,
+    alerting: bool,
+    overlay: &dyn Fn(usize) -> Overlay<'a>,
 ) {
     if area.height < 2 || area.width < 20 {
         return;
@@ -297,20 +512,26 @@ fn draw_traffic_panel(
         graph::next_power_of_2_scaled(peak)
     };
     let scale_label = graph::get_graph_scale_label_unit(scale_max, unit);
-    let label_text = format!("{label} ({scale_label}):");
+    let label_text = if alerting {
+        format!("{label} ({scale_label}):{}", t("alert_banner"))
+    } else {
+        format!("{label} ({scale_label}):")
+    };
     let width = area.width as usize;
 
+    // 有告警触发时标签栏改为红色
+    let bar_color = if alerting { Color::Red } else { graph_color };
     let label_style = maybe_strip(match bar_style {
         BarStyle::Fill => Style::default()
-            .bg(graph_color)
+            .bg(bar_color)
             .fg(Color::Black)
             .add_modifier(Modifier::BOLD),
         BarStyle::Color => Style::default()
-            .bg(graph_color)
+            .bg(bar_color)
             .fg(Color::Black)
             .add_modifier(Modifier::BOLD),
         BarStyle::Plain => Style::default()
-            .fg(graph_color)
+            .fg(bar_color)
             .add_modifier(Modifier::BOLD),
     }, no_color);
     let label_display = if bar_style == BarStyle::Fill {
@@ -332,38 +553,488 @@ fn draw_traffic_panel(
             .constraints([Constraint::Min(10), Constraint::Length(stat_width)])
             .split(panel_chunks[1]);
 
-        draw_graph(frame, content_chunks[0], history, scale_max, unicode, graph_color, no_color);
+        let overlay = overlay(content_chunks[0].width as usize);
+        draw_graph(frame, content_chunks[0], history, scale_max, unicode, graph_color, no_color, &overlay);
         draw_stats(frame, content_chunks[1], stats, emoji, unit, no_color);
     }
 }
 
+// ─── Alerts ────────────────────────────────────────────────
+
+fn draw_alerts(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if app.emoji { t("alerts_title_emoji") } else { t("alerts_title") };
+    let firing = app.alerts.firing().count();
+    let title_text = format!(
+        "{title} ({firing} {} / {} {}):",
+        t("alerts_firing"),
+        app.alerts.rules.len(),
+        t("alerts_rules"),
+    );
+    let title_style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let title_display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&title_text, area.width as usize)
+    } else {
+        title_text
+    };
+
+    let mut lines = vec![Line::from(Span::styled(title_display, title_style))];
+
+    if app.alerts.rules.is_empty() {
+        lines.push(Line::from(Span::raw(format!(" {}", t("alerts_none")))));
+    }
+
+    let dim = maybe_strip(Style::default().fg(Color::DarkGray), app.no_color);
+    let value_style = maybe_strip(Style::default().fg(Color::White), app.no_color);
+
+    for (idx, rule) in app.alerts.rules.iter().enumerate() {
+        let instances: Vec<&Alert> = app.alerts.alerts.iter().filter(|a| a.rule == idx).collect();
+        if instances.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {:<12}", "-"), dim),
+                Span::styled(format!("{} ", rule.text), value_style),
+                Span::styled(t("alerts_no_data"), dim),
+            ]));
+            continue;
+        }
+
+        // 正在触发的排在前面
+        let mut instances = instances;
+        instances.sort_by_key(|a| !a.is_firing());
+        for alert in instances {
+            let (state, color) = match alert.state {
+                State::Ok => (t("alert_state_ok"), Color::Green),
+                State::Pending { .. } => (t("alert_state_pending"), Color::Yellow),
+                State::Firing => (t("alert_state_firing"), Color::Red),
+                State::Recovering { .. } => (t("alert_state_recovering"), Color::Magenta),
+            };
+            let since = alert
+                .fired_at
+                .filter(|_| alert.is_firing())
+                .map(|ts| format!("  @ {}", ts.format("%H:%M:%S")))
+                .unwrap_or_default();
+            let state_style = maybe_strip(Style::default().fg(color).add_modifier(Modifier::BOLD), app.no_color);
+            let state_text = format!("[{state}]");
+            let pad = " ".repeat(13usize.saturating_sub(str_display_width(&state_text)));
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {state_text}{pad}"), state_style),
+                Span::styled(format!("{:<16}", alert.device), value_style),
+                Span::styled(format!("{:>16}", format_alert_value(alert, app)), value_style),
+                Span::styled(format!("  {}{since}", rule.text), dim),
+            ]));
+        }
+    }
+
+    if let Some(ref err) = app.notify_error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!(" {}: {err}", t("alerts_notify_error")),
+            maybe_strip(Style::default().fg(Color::Yellow), app.no_color),
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+// ─── Events ────────────────────────────────────────────────
+
+fn draw_events(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if app.emoji { t("events_title_emoji") } else { t("events_title") };
+    let rows = (area.height as usize).saturating_sub(1);
+    let total = app.events.len();
+    let first = app.events_scroll.min(total);
+    let last = (first + rows).min(total);
+    let title_text = if total > 0 {
+        format!("{title} ({}-{last}/{total}) | {}:", first + 1, t("events_scroll_hint"))
+    } else {
+        format!("{title}:")
+    };
+    let title_style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Magenta).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Magenta).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let title_display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&title_text, area.width as usize)
+    } else {
+        title_text
+    };
+
+    let mut lines = vec![Line::from(Span::styled(title_display, title_style))];
+
+    if app.events.is_empty() {
+        lines.push(Line::from(Span::raw(format!(" {}", t("events_none")))));
+    }
+
+    let dim = maybe_strip(Style::default().fg(Color::DarkGray), app.no_color);
+    let value_style = maybe_strip(Style::default().fg(Color::White), app.no_color);
+
+    // 最新的事件在最上面，events_scroll 条之前的已被翻过
+    for event in app.events.latest().skip(first).take(rows) {
+        let (tag, color) = match event.kind {
+            EventKind::DeviceAdded => (t("event_added"), Color::Cyan),
+            EventKind::DeviceRemoved => (t("event_removed"), Color::Yellow),
+            EventKind::LinkUp => (t("event_link_up"), Color::Green),
+            EventKind::LinkDown => (t("event_link_down"), Color::Red),
+            EventKind::LinkSpeed => (t("event_link_speed"), Color::Yellow),
+            EventKind::CounterReset => (t("event_counter_reset"), Color::Yellow),
+            EventKind::AlertFiring => (t("alert_state_firing"), Color::Red),
+            EventKind::AlertCleared => (t("event_cleared"), Color::Green),
+            EventKind::AnomalyStart => (t("event_anomaly"), Color::LightRed),
+            EventKind::AnomalyEnd => (t("event_normal"), Color::Green),
+            EventKind::Error => (t("event_error"), Color::Red),
+            EventKind::Marker => (t("event_marker"), Color::Yellow),
+        };
+        let tag_text = format!("[{tag}]");
+        let pad = " ".repeat(12usize.saturating_sub(str_display_width(&tag_text)));
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", event.time.format("%m-%d %H:%M:%S")), dim),
+            Span::styled(format!("{tag_text}{pad}"), maybe_strip(Style::default().fg(color).add_modifier(Modifier::BOLD), app.no_color)),
+            Span::styled(format!("{:<16}", event.device), value_style),
+            Span::styled(event.message.clone(), value_style),
+        ]));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+// ─── Flows ─────────────────────────────────────────────────
+
+/// 连接表每列数值的宽度
+const FLOW_COL: usize = 13;
+
+/// iftop 风格的连接表: 每个连接两行，=> 为本机发出，<= 为收到，右侧为 2s/10s/40s 平均速率和累计
+fn draw_flows(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if app.emoji { t("flows_title_emoji") } else { t("flows_title") };
+    let sort = match app.flows.sort {
+        SortBy::Avg2s => t("flows_sort_2s"),
+        SortBy::Avg10s => t("flows_sort_10s"),
+        SortBy::Avg40s => t("flows_sort_40s"),
+        SortBy::Total => t("flows_sort_total"),
+    };
+    // 标题和列名各占一行，每个连接占两行
+    let rows = (area.height as usize).saturating_sub(2) / 2;
+    let flows = app.flows.sorted();
+    let total = flows.len();
+    let first = app.flows_scroll.min(total);
+    let last = (first + rows).min(total);
+    let title_text = if total > 0 {
+        format!("{title} ({}-{last}/{total}) | {}: {sort} | {}:", first + 1, t("flows_sort"), t("flows_hint"))
+    } else {
+        format!("{title} | {}: {sort} | {}:", t("flows_sort"), t("flows_hint"))
+    };
+    let title_style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let title_display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&title_text, area.width as usize)
+    } else {
+        title_text
+    };
+
+    let mut lines = vec![Line::from(Span::styled(title_display, title_style))];
+
+    if !app.capturing {
+        let why = if app.player().is_some() { t("flows_no_capture_replay") } else { t("flows_no_capture") };
+        lines.push(Line::from(Span::raw(format!(" {why}"))));
+        frame.render_widget(Paragraph::new(lines), area);
+        return;
+    }
+    if flows.is_empty() {
+        lines.push(Line::from(Span::raw(format!(" {}", t("flows_none")))));
+        frame.render_widget(Paragraph::new(lines), area);
+        return;
+    }
+
+    let dim = maybe_strip(Style::default().fg(Color::DarkGray), app.no_color);
+    let value_style = maybe_strip(Style::default().fg(Color::White), app.no_color);
+    let in_style = maybe_strip(Style::default().fg(app.in_color), app.no_color);
+    let out_style = maybe_strip(Style::default().fg(app.out_color), app.no_color);
+
+    // 左侧为 "协议 本机 => 对端"，宽度不够时截断对端
+    let visible = &flows[first..last];
+    let local_w = visible.iter().map(|f| f.key.local.to_string().len()).max().unwrap_or(0);
+    let left_w = (area.width as usize).saturating_sub(FLOW_COL * 4).max(local_w + 12);
+
+    let mut header = " ".repeat(left_w);
+    for window in WINDOWS {
+        header.push_str(&format!("{:>FLOW_COL$}", format!("{window}s")));
+    }
+    header.push_str(&format!("{:>FLOW_COL$}", t("flows_total")));
+    lines.push(Line::from(Span::styled(header, dim)));
+
+    for flow in visible {
+        let key = &flow.key;
+        let head = format!(" {:<7}{:<local_w$} ", key.protocol_name(), key.local.to_string());
+        for (dir, arrow, text, style) in [
+            (1, "=> ", key.remote.to_string(), out_style),
+            (0, "<= ", String::new(), in_style),
+        ] {
+            let left = if dir == 1 { head.clone() } else { " ".repeat(head.len()) };
+            let mut text: String = text.chars().take(left_w.saturating_sub(left.len() + arrow.len())).collect();
+            text = pad_to_width(&text, left_w.saturating_sub(left.len() + arrow.len()));
+            let mut spans = vec![
+                Span::styled(left, value_style),
+                Span::styled(arrow, style),
+                Span::styled(text, value_style),
+            ];
+            for rate in flow.rates {
+                spans.push(Span::styled(format!("{:>FLOW_COL$}", stats::format_speed_unit(rate[dir], app.unit)), style));
+            }
+            spans.push(Span::styled(format!("{:>FLOW_COL$}", stats::format_bytes(flow.total[dir])), dim));
+            lines.push(Line::from(spans));
+        }
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+// ─── Protocols ─────────────────────────────────────────────
+
+/// 协议分布表每列数值的宽度
+const PROTO_COL: usize = 14;
+
+/// 协议分布表名称列的宽度
+const PROTO_NAME: usize = 20;
+
+fn class_label(class: Class) -> String {
+    match class {
+        Class::Https => "HTTPS (tcp/443)".to_string(),
+        Class::Quic => "QUIC (udp/443)".to_string(),
+        Class::Ssh => "SSH (tcp/22)".to_string(),
+        Class::Dns => "DNS (53)".to_string(),
+        Class::Tcp => format!("TCP ({})", t("protocols_other_ports")),
+        Class::Udp => format!("UDP ({})", t("protocols_other_ports")),
+        Class::Icmp => "ICMP".to_string(),
+        Class::Other => t("protocols_other").to_string(),
+    }
+}
+
+fn class_color(class: Class) -> Color {
+    match class {
+        Class::Https => Color::Green,
+        Class::Quic => Color::Cyan,
+        Class::Ssh => Color::Magenta,
+        Class::Dns => Color::Yellow,
+        Class::Tcp => Color::Blue,
+        Class::Udp => Color::LightRed,
+        Class::Icmp => Color::Red,
+        Class::Other => Color::Gray,
+    }
+}
+
+/// 协议分布: 上方为各类别和 IPv4 / IPv6 的当前收发速率及占比，下方为各类别速率的堆叠图
+fn draw_protocols(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if app.emoji { t("protocols_title_emoji") } else { t("protocols_title") };
+    let stats = &app.protocols;
+    let width = area.width as usize;
+
+    // 缩放按图形可见部分的峰值，同收发面板
+    let peak = stats.history.iter().take(width).map(|layers| layers.iter().sum()).fold(0.0_f64, f64::max);
+    let scale_max = app.fixed_max.unwrap_or_else(|| graph::next_power_of_2_scaled(peak));
+    let title_text = format!(
+        "{title} ({} | {}: {}):",
+        graph::get_graph_scale_label_unit(scale_max, app.unit),
+        t("protocols_total"),
+        stats::format_speed_unit(stats.total(), app.unit),
+    );
+    let title_style = maybe_strip(match app.bar_style {
+        BarStyle::Fill => Style::default().bg(Color::Green).fg(Color::Black).add_modifier(Modifier::BOLD),
+        BarStyle::Color => Style::default().bg(Color::Green).fg(Color::Black).add_modifier(Modifier::BOLD),
+        BarStyle::Plain => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+    }, app.no_color);
+    let title_display = if app.bar_style == BarStyle::Fill {
+        pad_to_width(&title_text, width)
+    } else {
+        title_text
+    };
+
+    let mut lines = vec![Line::from(Span::styled(title_display, title_style))];
+
+    if !app.capturing {
+        let why = if app.player().is_some() { t("flows_no_capture_replay") } else { t("flows_no_capture") };
+        lines.push(Line::from(Span::raw(format!(" {why}"))));
+        frame.render_widget(Paragraph::new(lines), area);
+        return;
+    }
+
+    let dim = maybe_strip(Style::default().fg(Color::DarkGray), app.no_color);
+    let value_style = maybe_strip(Style::default().fg(Color::White), app.no_color);
+    let in_style = maybe_strip(Style::default().fg(app.in_color), app.no_color);
+    let out_style = maybe_strip(Style::default().fg(app.out_color), app.no_color);
+    let swatch = if app.unicode { "██" } else { "##" };
+
+    let header = format!(
+        "{}{:>PROTO_COL$}{:>PROTO_COL$}{:>8}",
+        " ".repeat(PROTO_NAME + 4),
+        t("incoming"),
+        t("outgoing"),
+        t("protocols_share"),
+    );
+    lines.push(Line::from(Span::styled(header, dim)));
+
+    let row = |swatch: Span<'static>, name: &str, rate: [f64; 2], total: f64| {
+        let share = if total > 0.0 { (rate[0] + rate[1]) / total * 100.0 } else { 0.0 };
+        let pad = " ".repeat(PROTO_NAME.saturating_sub(str_display_width(name)));
+        Line::from(vec![
+            Span::raw(" "),
+            swatch,
+            Span::styled(format!(" {name}{pad}"), value_style),
+            Span::styled(format!("{:>PROTO_COL$}", stats::format_speed_unit(rate[0], app.unit)), in_style),
+            Span::styled(format!("{:>PROTO_COL$}", stats::format_speed_unit(rate[1], app.unit)), out_style),
+            Span::styled(format!("{:>7.1}%", share), value_style),
+        ])
+    };
+
+    let total = stats.total();
+    for class in Class::ALL {
+        let color = maybe_strip(Style::default().fg(class_color(class)), app.no_color);
+        lines.push(row(Span::styled(swatch, color), &class_label(class), stats.classes[class.index()], total));
+    }
+    // IPv4 / IPv6 的占比按两者之和计算 (不含 ARP 等非 IP 流量)
+    let ip_total: f64 = stats.families.iter().map(|r| r[0] + r[1]).sum();
+    for (name, rate) in ["IPv4", "IPv6"].iter().zip(stats.families) {
+        lines.push(row(Span::raw("  "), name, rate, ip_total));
+    }
+
+    let table_height = lines.len() as u16;
+    frame.render_widget(Paragraph::new(lines), area);
+
+    // 表格下方剩余的空间画堆叠图
+    if app.no_graph || area.height <= table_height + 2 {
+        return;
+    }
+    let graph_area = Rect {
+        y: area.y + table_height + 1,
+        height: area.height - table_height - 1,
+        ..area
+    };
+    draw_stacked_graph(frame, graph_area, &stats.history, scale_max, app.unicode, app.no_color);
+}
+
+/// 堆叠图，每层用对应类别的颜色
+fn draw_stacked_graph(frame: &mut Frame, area: Rect, history: &VecDeque<Vec<f64>>, max_value: f64, unicode: bool, no_color: bool) {
+    let cells = graph::render_stacked_graph(history, area.width as usize, area.height as usize, max_value, unicode);
+    let styled_lines: Vec<Line> = cells
+        .into_iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .into_iter()
+                .map(|cell| match cell {
+                    Some((ch, layer)) => Span::styled(
+                        ch.to_string(),
+                        maybe_strip(Style::default().fg(class_color(Class::ALL[layer])), no_color),
+                    ),
+                    None => Span::raw(" "),
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(styled_lines), area);
+}
+
 // ─── Graph ─────────────────────────────────────────────────
 
-fn draw_graph(frame: &mut Frame, area: Rect, history: &VecDeque<f64>, max_value: f64, unicode: bool, graph_color: Color, no_color: bool) {
+/// 叠加在图形上的信息
+struct Overlay<'a> {
+    /// 从右往左第 i 列是否处于异常区间
+    anomaly: Vec<bool>,
+    /// 用户标记: (样本下标, 标签)，下标 0 为最新一列
+    markers: Vec<(usize, &'a str)>,
+}
+
+/// 标记标签写在图形第一行、竖线的右侧，遇到下一条竖线或图形边缘时截断
+///
+/// 返回每列要显示的字符；宽字符之后的一列为 Some("")，占位不输出
+fn marker_labels(markers: &[(usize, &str)], width: usize) -> Vec<Option<String>> {
+    let mut cells: Vec<Option<String>> = vec![None; width];
+    // 已经移出图形左边缘的标记不画
+    let markers: Vec<(usize, &str)> = markers.iter().copied().filter(|(age, _)| *age < width).collect();
+    let line_cols: Vec<usize> = markers.iter().map(|(age, _)| width - 1 - age).collect();
+    for (&(_, label), &line_col) in markers.iter().zip(&line_cols) {
+        let mut col = line_col + 1;
+        for ch in label.chars() {
+            let w = str_display_width(ch.encode_utf8(&mut [0; 4]));
+            if col + w > width || (col..col + w).any(|c| line_cols.contains(&c)) {
+                break;
+            }
+            cells[col] = Some(ch.to_string());
+            if w == 2 {
+                cells[col + 1] = Some(String::new());
+            }
+            col += w;
+        }
+    }
+    cells
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_graph(frame: &mut Frame, area: Rect, history: &VecDeque<f64>, max_value: f64, unicode: bool, graph_color: Color, no_color: bool, overlay: &Overlay) {
     let width = area.width as usize;
     let height = area.height as usize;
 
-    let lines = graph::render_graph(history, width, height, max_value, unicode);
+    let marker_ages: Vec<usize> = overlay.markers.iter().map(|(age, _)| *age).collect();
+    let lines = graph::render_graph(history, width, height, max_value, unicode, &marker_ages);
+    let labels = marker_labels(&overlay.markers, width);
+    let marker_ch = graph::marker_char(unicode);
+    let marker_style = Style::default().fg(Color::Yellow);
 
     // 较暗的颜色用于低密度区域
     let dim_color = Color::DarkGray;
 
+    // 异常区间: 柱子用红色，空白处加暗红底色
+    let anomaly_color = Color::LightRed;
+    let anomaly_bg = Color::Rgb(0x40, 0x00, 0x10);
+
     let styled_lines: Vec<Line> = lines
         .iter()
-        .map(|line| {
+        .enumerate()
+        .map(|(row, line)| {
             let spans: Vec<Span> = line
                 .chars()
-                .map(|ch| match ch {
-                    // Unicode block chars
-                    '█' => Span::styled("█", maybe_strip(Style::default().fg(graph_color), no_color)),
-                    '▓' => Span::styled("▓", maybe_strip(Style::default().fg(graph_color), no_color)),
-                    '░' => Span::styled("░", maybe_strip(Style::default().fg(dim_color), no_color)),
-                    '·' => Span::styled("·", maybe_strip(Style::default().fg(dim_color), no_color)),
-                    // ASCII chars
-                    '#' => Span::styled("#", maybe_strip(Style::default().fg(graph_color), no_color)),
-                    '|' => Span::styled("|", maybe_strip(Style::default().fg(graph_color), no_color)),
-                    '.' => Span::styled(".", maybe_strip(Style::default().fg(dim_color), no_color)),
-                    _ => Span::raw(" "),
+                .enumerate()
+                .map(|(col, ch)| {
+                    if let Some(Some(label)) = labels.get(col).filter(|_| row == 0) {
+                        return Span::styled(
+                            label.clone(),
+                            maybe_strip(marker_style.add_modifier(Modifier::BOLD), no_color),
+                        );
+                    }
+                    if ch == marker_ch {
+                        return Span::styled(ch.to_string(), maybe_strip(marker_style, no_color));
+                    }
+                    let anomalous = overlay
+                        .anomaly
+                        .get(width.saturating_sub(col + 1))
+                        .copied()
+                        .unwrap_or(false);
+                    let (bar, dim) = if anomalous {
+                        (anomaly_color, anomaly_color)
+                    } else {
+                        (graph_color, dim_color)
+                    };
+                    let span = match ch {
+                        // Unicode block chars
+                        '█' => Span::styled("█", Style::default().fg(bar)),
+                        '▓' => Span::styled("▓", Style::default().fg(bar)),
+                        '░' => Span::styled("░", Style::default().fg(dim)),
+                        '·' => Span::styled("·", Style::default().fg(dim)),
+                        // ASCII chars
+                        '#' => Span::styled("#", Style::default().fg(bar)),
+                        '|' => Span::styled("|", Style::default().fg(bar)),
+                        '.' => Span::styled(".", Style::default().fg(dim)),
+                        _ => Span::raw(" "),
+                    };
+                    let mut span = if anomalous { span.patch_style(Style::default().bg(anomaly_bg)) } else { span };
+                    span.style = maybe_strip(span.style, no_color);
+                    span
                 })
                 .collect();
             Line::from(spans)
@@ -454,8 +1125,12 @@ fn format_stats_lines(st: &TrafficStats, emoji: bool, unit: Unit, no_color: bool
 
 // ─── Help / Error ──────────────────────────────────────────
 
-fn draw_help(frame: &mut Frame, area: Rect, emoji: bool, bar_style: BarStyle, no_color: bool) {
-    let help_text = if emoji {
+fn draw_help(frame: &mut Frame, area: Rect, emoji: bool, bar_style: BarStyle, no_color: bool, prompt: Option<&str>) {
+    // 输入标记标签时，帮助栏变为输入框
+    let prompt_text = prompt.map(|input| format!(" {}: {input}_  ({})", t("marker_prompt"), t("marker_prompt_keys")));
+    let help_text = if let Some(ref text) = prompt_text {
+        text.as_str()
+    } else if emoji {
         #[cfg(target_os = "windows")]
         { t("help_bar_win_emoji") }
         #[cfg(not(target_os = "windows"))]
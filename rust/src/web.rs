@@ -0,0 +1,158 @@
+//! 网页仪表盘 (--web)
+//!
+//! 在指定地址上提供一个自包含的 HTML 页面 (不依赖 CDN，见 web.html)，
+//! 数据通过 Server-Sent Events (`/events`) 实时推送，来源与 TUI 是同一组 StatisticsEngine:
+//!
+//! - 连接时先发送 `history` 事件: 各设备最近 HISTORY_LEN 个速率点和显示单位
+//! - 之后每次采集发送一个 `sample` 事件: 各设备的 Cur / Avg / Min / Max / Ttl
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::http;
+use crate::stats::TrafficStats;
+use crate::{App, Unit};
+
+const PAGE: &str = include_str!("web.html");
+
+/// 新连接时发送的历史点数
+const HISTORY_LEN: usize = 600;
+/// 没有数据时发送 SSE 注释的间隔，防止代理断开空闲连接
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Default)]
+struct Shared {
+    /// 各 SSE 连接的发送端
+    clients: Vec<Sender<String>>,
+    /// 设备名 → (收, 发) 速率历史，按设备顺序
+    history: Vec<(String, VecDeque<f64>, VecDeque<f64>)>,
+    unit: &'static str,
+}
+
+/// 网页服务
+pub struct WebServer {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl WebServer {
+    /// 开始监听；返回服务和实际监听的地址
+    pub fn start(addr: &str, unit: Unit) -> Result<(Self, String), String> {
+        let shared = Arc::new(Mutex::new(Shared {
+            unit: match unit {
+                Unit::Bit => "bit",
+                Unit::Byte => "byte",
+            },
+            ..Default::default()
+        }));
+        let state = shared.clone();
+        let local = http::serve(addr, move |req, mut stream| {
+            match (req.method.as_str(), req.path.as_str()) {
+                ("GET", "/") => {
+                    let _ = http::respond(&mut stream, 200, "text/html; charset=utf-8", PAGE.as_bytes());
+                }
+                ("GET", "/events") => stream_events(&state, stream),
+                ("GET", _) => {
+                    let _ = http::respond(&mut stream, 404, "text/plain", b"not found\n");
+                }
+                _ => {
+                    let _ = http::respond(&mut stream, 405, "text/plain", b"method not allowed\n");
+                }
+            }
+        })?;
+        Ok((Self { shared }, local.to_string()))
+    }
+
+    /// 记录最新速率，并把本次采集推送给所有已连接的页面
+    pub fn publish(&self, app: &App) {
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+
+        // 按当前设备列表重建历史顺序，保留已有设备的数据
+        let mut old: HashMap<String, (VecDeque<f64>, VecDeque<f64>)> =
+            shared.history.drain(..).map(|(name, rx, tx)| (name, (rx, tx))).collect();
+        for view in &app.views {
+            let (mut rx, mut tx) = old.remove(&view.info.name).unwrap_or_default();
+            if !view.engine.incoming_history.is_empty() {
+                for (hist, value) in [(&mut rx, view.engine.incoming.current), (&mut tx, view.engine.outgoing.current)] {
+                    if hist.len() >= HISTORY_LEN {
+                        hist.pop_front();
+                    }
+                    hist.push_back(value);
+                }
+            }
+            shared.history.push((view.info.name.clone(), rx, tx));
+        }
+
+        if shared.clients.is_empty() {
+            return;
+        }
+        let devices: Vec<_> = app
+            .views
+            .iter()
+            .map(|v| {
+                json!({
+                    "name": v.info.name,
+                    "addrs": v.info.addrs,
+                    "owner": app.owner(&v.info.name),
+                    "in": stats_json(&v.engine.incoming),
+                    "out": stats_json(&v.engine.outgoing),
+                })
+            })
+            .collect();
+        let msg = format!(
+            "event: sample\ndata: {}\n\n",
+            json!({ "time": app.now.to_rfc3339(), "devices": devices })
+        );
+        // 发送失败说明连接已断开
+        shared.clients.retain(|c| c.send(msg.clone()).is_ok());
+    }
+}
+
+fn stats_json(s: &TrafficStats) -> serde_json::Value {
+    json!({
+        "current": s.current,
+        "average": s.average,
+        // 还没有速率样本时 minimum 为 INFINITY，JSON 无法表示
+        "min": if s.minimum.is_finite() { s.minimum } else { 0.0 },
+        "max": s.maximum,
+        "total": s.total,
+    })
+}
+
+/// 处理一个 SSE 连接: 先发历史，再逐条转发采集数据，直到连接断开
+fn stream_events(shared: &Mutex<Shared>, mut stream: std::net::TcpStream) {
+    let (tx, rx) = mpsc::channel();
+    let history = {
+        let Ok(mut shared) = shared.lock() else {
+            return;
+        };
+        shared.clients.push(tx);
+        let devices: Vec<_> = shared
+            .history
+            .iter()
+            .map(|(name, rx, tx)| json!({ "name": name, "in": rx, "out": tx }))
+            .collect();
+        json!({ "unit": shared.unit, "devices": devices })
+    };
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    if write!(stream, "{head}event: history\ndata: {history}\n\n").is_err() {
+        return;
+    }
+    loop {
+        let msg = match rx.recv_timeout(KEEPALIVE) {
+            Ok(msg) => msg,
+            Err(mpsc::RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        if stream.write_all(msg.as_bytes()).and_then(|_| stream.flush()).is_err() {
+            return;
+        }
+    }
+}